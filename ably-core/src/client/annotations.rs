@@ -0,0 +1,274 @@
+// Annotations, such as reactions, on published messages
+
+use crate::client::pagination::{PageHook, PaginatedResult, PaginatedStream};
+use crate::client::realtime::RealtimeChannel;
+use crate::client::rest::message_path;
+use crate::client::subscription::{BufferedListener, SubscribeOptions, Subscription};
+use crate::crypto::key_set::{decode_payload, encode_payload};
+use crate::error::{AblyError, AblyResult};
+use crate::http::AblyHttpClient;
+use crate::protocol::annotations::{Annotation, AnnotationAction};
+use crate::protocol::encoding::EncodingFormat;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::warn;
+
+/// Check and encode an annotation for publishing on a message (RSAN1)
+///
+/// Annotation data is encoded but never encrypted.
+pub(crate) fn prepare_annotation(
+    message_serial: &str,
+    mut annotation: Annotation,
+    action: AnnotationAction,
+) -> AblyResult<Annotation> {
+    if annotation.annotation_type.is_none() {
+        return Err(AblyError::invalid_request("An annotation type is required"));
+    }
+    annotation.action = Some(action);
+    annotation.message_serial = Some(message_serial.to_string());
+    encode_payload(&mut annotation, None, EncodingFormat::Json)?;
+    Ok(annotation)
+}
+
+/// Decode received annotation payloads; ones that fail keep their encoding
+pub(crate) fn decode_annotations(annotations: &mut [Annotation], channel: &str) {
+    for annotation in annotations.iter_mut() {
+        if let Err(e) = decode_payload(annotation, None) {
+            warn!("Failed to decode annotation on channel {}: {}", channel, e);
+        }
+    }
+}
+
+/// Annotation operations on a REST channel (RSAN)
+pub struct RestAnnotations<'a> {
+    channel: String,
+    http_client: &'a AblyHttpClient,
+}
+
+impl<'a> RestAnnotations<'a> {
+    pub(crate) fn new(channel: &str, http_client: &'a AblyHttpClient) -> Self {
+        Self {
+            channel: channel.to_string(),
+            http_client,
+        }
+    }
+
+    /// Add an annotation to the message with the given serial
+    pub async fn publish(&self, message_serial: &str, annotation: Annotation) -> AblyResult<()> {
+        self.send(message_serial, annotation, AnnotationAction::Create).await
+    }
+
+    /// Remove an annotation from the message with the given serial
+    pub async fn delete(&self, message_serial: &str, annotation: Annotation) -> AblyResult<()> {
+        self.send(message_serial, annotation, AnnotationAction::Delete).await
+    }
+
+    /// List the annotations on the message with the given serial
+    pub fn get(&self, message_serial: &str) -> AnnotationsQuery<'a> {
+        AnnotationsQuery::new(&self.channel, message_serial, self.http_client)
+    }
+
+    async fn send(&self, message_serial: &str, annotation: Annotation, action: AnnotationAction) -> AblyResult<()> {
+        let annotation = prepare_annotation(message_serial, annotation, action)?;
+        let path = format!("{}/annotations", message_path(&self.channel, message_serial));
+        self.http_client
+            .post(&path)
+            .json(&vec![annotation])
+            .send()
+            .await?
+            .error_for_status()
+            .await?;
+        Ok(())
+    }
+}
+
+/// Query builder for the annotations on a message
+pub struct AnnotationsQuery<'a> {
+    channel: String,
+    path: String,
+    http_client: &'a AblyHttpClient,
+    params: HashMap<String, String>,
+}
+
+impl<'a> AnnotationsQuery<'a> {
+    fn new(channel: &str, message_serial: &str, http_client: &'a AblyHttpClient) -> Self {
+        Self {
+            channel: channel.to_string(),
+            path: format!("{}/annotations", message_path(channel, message_serial)),
+            http_client,
+            params: HashMap::new(),
+        }
+    }
+
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.params.insert("limit".to_string(), limit.to_string());
+        self
+    }
+
+    pub async fn execute(&self) -> AblyResult<PaginatedResult<'a, Annotation>> {
+        let channel = self.channel.clone();
+        let hook: PageHook<Annotation> = Arc::new(move |mut annotations: Vec<Annotation>| {
+            decode_annotations(&mut annotations, &channel);
+            Box::pin(async move { Ok(annotations) })
+        });
+        PaginatedResult::fetch(self.http_client, &self.path, &self.params, Some(hook)).await
+    }
+
+    /// Stream every annotation in the result set, fetching pages as needed
+    pub fn into_stream(self) -> PaginatedStream<'a, Annotation> {
+        PaginatedStream::new(async move { self.execute().await })
+    }
+}
+
+/// Annotation operations on a realtime channel (RTAN)
+///
+/// Receiving annotations requires the channel to be attached with the
+/// `flags::ANNOTATION_SUBSCRIBE` mode.
+pub struct RealtimeAnnotations {
+    channel: RealtimeChannel,
+}
+
+impl RealtimeAnnotations {
+    pub(crate) fn new(channel: RealtimeChannel) -> Self {
+        Self { channel }
+    }
+
+    /// Add an annotation to the message with the given serial
+    pub async fn publish(&self, message_serial: &str, annotation: Annotation) -> AblyResult<()> {
+        let annotation = prepare_annotation(message_serial, annotation, AnnotationAction::Create)?;
+        self.channel.send_annotation(annotation).await
+    }
+
+    /// Remove an annotation from the message with the given serial
+    pub async fn delete(&self, message_serial: &str, annotation: Annotation) -> AblyResult<()> {
+        let annotation = prepare_annotation(message_serial, annotation, AnnotationAction::Delete)?;
+        self.channel.send_annotation(annotation).await
+    }
+
+    /// List the annotations on the message with the given serial via REST
+    pub async fn get(&self, message_serial: &str) -> AblyResult<PaginatedResult<'_, Annotation>> {
+        self.channel.rest_annotations().get(message_serial).execute().await
+    }
+
+    /// Stream of all annotations subsequently received on the channel
+    pub async fn subscribe(&self) -> Subscription<Annotation> {
+        self.subscribe_with_options(SubscribeOptions::default()).await
+    }
+
+    /// Stream of received annotations of one type, such as `reaction:distinct.v1`
+    pub async fn subscribe_type(&self, annotation_type: &str) -> Subscription<Annotation> {
+        self.subscribe_type_with_options(annotation_type, SubscribeOptions::default()).await
+    }
+
+    /// Subscribe to all annotations with explicit buffer size and overflow policy
+    ///
+    /// The options' message filter does not apply to annotations.
+    pub async fn subscribe_with_options(&self, options: SubscribeOptions) -> Subscription<Annotation> {
+        self.channel.subscribe_annotations(None, options).await
+    }
+
+    /// Subscribe to annotations of one type with explicit buffer size and overflow policy
+    pub async fn subscribe_type_with_options(
+        &self,
+        annotation_type: &str,
+        options: SubscribeOptions,
+    ) -> Subscription<Annotation> {
+        self.channel.subscribe_annotations(Some(annotation_type.to_string()), options).await
+    }
+}
+
+/// Channel-side end of an annotation subscription
+pub(crate) struct AnnotationListener {
+    annotation_type: Option<String>,
+    listener: BufferedListener<Annotation>,
+}
+
+impl AnnotationListener {
+    pub(crate) fn new(annotation_type: Option<String>, options: &SubscribeOptions) -> (Self, Subscription<Annotation>) {
+        let (listener, subscription) = BufferedListener::new(options);
+        (Self { annotation_type, listener }, subscription)
+    }
+
+    pub(crate) fn is_active(&self) -> bool {
+        self.listener.is_active()
+    }
+
+    /// Deliver an annotation, returning false once the listener should be removed
+    pub(crate) async fn deliver(&self, annotation: &Annotation) -> bool {
+        if !self.is_active() {
+            return false;
+        }
+        if self.annotation_type.is_some() && annotation.annotation_type != self.annotation_type {
+            return true;
+        }
+        self.listener.deliver(annotation).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::mock::{MockResponse, MockServer};
+    use crate::http::HttpConfig;
+    use serde_json::{json, Value};
+    use std::sync::Mutex;
+
+    #[tokio::test]
+    async fn test_publish_delete_and_get() {
+        let posted = Arc::new(Mutex::new(Vec::new()));
+        let recorded = posted.clone();
+        let server = MockServer::start(move |request| {
+            assert_eq!(request.path.split('?').next(), Some("/channels/chat/messages/01%3A0/annotations"));
+            if request.method == "POST" {
+                recorded.lock().unwrap().push(serde_json::from_slice::<Value>(&request.body).unwrap());
+                return MockResponse::json(201, &json!({}));
+            }
+            assert_eq!(request.query("limit"), Some("10"));
+            MockResponse::json(200, &json!([
+                {"type": "reaction:distinct.v1", "name": "👍", "action": 0, "clientId": "ana",
+                 "data": "eyJzaXplIjoyfQ==", "encoding": "json/base64", "messageSerial": "01:0"}
+            ]))
+        })
+        .await;
+        let config = HttpConfig::builder().base_url(server.url()).build();
+        let client = AblyHttpClient::from_config(config);
+        let annotations = RestAnnotations::new("chat", &client);
+
+        let reaction = Annotation::new("reaction:distinct.v1").name("👍").data(json!({"size": 2}));
+        annotations.publish("01:0", reaction.clone()).await.unwrap();
+        annotations.delete("01:0", reaction).await.unwrap();
+
+        let posted = posted.lock().unwrap();
+        assert_eq!(posted[0][0]["action"], 0);
+        assert_eq!(posted[0][0]["messageSerial"], "01:0");
+        assert_eq!(posted[0][0]["data"], "{\"size\":2}");
+        assert_eq!(posted[0][0]["encoding"], "json");
+        assert_eq!(posted[1][0]["action"], 1);
+
+        let page = annotations.get("01:0").limit(10).execute().await.unwrap();
+        assert_eq!(page.items[0].client_id.as_deref(), Some("ana"));
+        assert_eq!(page.items[0].data, Some(json!({"size": 2}).into()));
+    }
+
+    #[tokio::test]
+    async fn test_publish_error_status() {
+        let server = MockServer::start(|_| {
+            MockResponse::json(401, &json!({"error": {"code": 40160, "statusCode": 401, "message": "Not permitted"}}))
+        })
+        .await;
+        let config = HttpConfig::builder().base_url(server.url()).build();
+        let client = AblyHttpClient::from_config(config);
+        let annotations = RestAnnotations::new("chat", &client);
+
+        let reaction = Annotation::new("reaction:distinct.v1").name("👍");
+        let err = annotations.publish("01:0", reaction.clone()).await.unwrap_err();
+        assert!(matches!(err, AblyError::Authentication { .. }));
+        assert!(annotations.delete("01:0", reaction).await.is_err());
+    }
+
+    #[test]
+    fn test_annotation_type_required() {
+        let err = prepare_annotation("01:0", Annotation::default(), AnnotationAction::Create).unwrap_err();
+        assert!(matches!(err, AblyError::BadRequest { .. }));
+    }
+}
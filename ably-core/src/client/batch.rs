@@ -0,0 +1,165 @@
+// Typed results for batch publish and batch presence requests
+
+use crate::error::{AblyError, AblyResult};
+use crate::protocol::messages::{ErrorInfo, Message, PresenceMessage};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+
+/// Messages to publish to each of a set of channels (BSP1)
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchPublishSpec {
+    pub channels: Vec<String>,
+    pub messages: Vec<Message>,
+}
+
+impl BatchPublishSpec {
+    pub fn new(channels: Vec<String>, messages: Vec<Message>) -> Self {
+        Self { channels, messages }
+    }
+}
+
+/// Outcome of a batch request across its channels (BAR1)
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", bound(deserialize = "T: DeserializeOwned"))]
+pub struct BatchResult<T> {
+    pub success_count: u32,
+    pub failure_count: u32,
+    pub results: Vec<BatchChannelResult<T>>,
+}
+
+impl<T> BatchResult<T> {
+    /// Channels whose part of the request succeeded
+    pub fn successes(&self) -> impl Iterator<Item = (&str, &T)> {
+        self.results
+            .iter()
+            .filter_map(|result| Some((result.channel.as_str(), result.result.as_ref().ok()?)))
+    }
+
+    /// Channels whose part of the request failed, with the reason
+    pub fn failures(&self) -> impl Iterator<Item = (&str, &ErrorInfo)> {
+        self.results
+            .iter()
+            .filter_map(|result| Some((result.channel.as_str(), result.result.as_ref().err()?)))
+    }
+}
+
+impl<T: DeserializeOwned> BatchResult<T> {
+    /// Read a batch result, accepting the bare per-channel array of older protocol versions
+    pub(crate) fn from_value(value: Value) -> AblyResult<Self> {
+        let result = match value {
+            Value::Array(results) => {
+                let results = results
+                    .into_iter()
+                    .map(serde_json::from_value)
+                    .collect::<Result<Vec<BatchChannelResult<T>>, _>>();
+                results.map(|results| {
+                    let failure_count = results.iter().filter(|result| result.result.is_err()).count() as u32;
+                    Self {
+                        success_count: results.len() as u32 - failure_count,
+                        failure_count,
+                        results,
+                    }
+                })
+            }
+            value => serde_json::from_value(value),
+        };
+        result.map_err(|e| AblyError::decode(format!("Failed to parse batch response: {}", e)))
+    }
+}
+
+/// Result of a batch request for one channel
+#[derive(Debug, Clone)]
+pub struct BatchChannelResult<T> {
+    pub channel: String,
+    pub result: Result<T, ErrorInfo>,
+}
+
+impl<T> BatchChannelResult<T> {
+    pub fn is_success(&self) -> bool {
+        self.result.is_ok()
+    }
+}
+
+impl<'de, T: DeserializeOwned> Deserialize<'de> for BatchChannelResult<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut value = Value::deserialize(deserializer)?;
+        let channel = value
+            .get("channel")
+            .and_then(Value::as_str)
+            .ok_or_else(|| serde::de::Error::missing_field("channel"))?
+            .to_string();
+
+        let result = match value.get_mut("error").map(Value::take) {
+            Some(error) => Err(serde_json::from_value(error).map_err(serde::de::Error::custom)?),
+            None => Ok(serde_json::from_value(value).map_err(serde::de::Error::custom)?),
+        };
+        Ok(Self { channel, result })
+    }
+}
+
+/// Messages published to one channel of a batch (BPB1)
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchPublishSuccess {
+    /// Id prefix shared by the published messages
+    pub message_id: String,
+    /// Serial of each published message, in order
+    #[serde(default)]
+    pub serials: Vec<Option<String>>,
+}
+
+/// Presence members of one channel of a batch (BGR1)
+#[derive(Debug, Clone, Deserialize)]
+pub struct BatchPresenceSuccess {
+    #[serde(default)]
+    pub presence: Vec<PresenceMessage>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_mixed_results() {
+        let value = json!({
+            "successCount": 1,
+            "failureCount": 1,
+            "results": [
+                {"channel": "orders", "messageId": "abc", "serials": ["s1", null]},
+                {"channel": "secret", "error": {"code": 40160, "statusCode": 401, "message": "Not permitted"}}
+            ]
+        });
+        let result = BatchResult::<BatchPublishSuccess>::from_value(value).unwrap();
+
+        assert_eq!(result.success_count, 1);
+        let successes: Vec<_> = result.successes().collect();
+        assert_eq!(successes[0].0, "orders");
+        assert_eq!(successes[0].1.serials, vec![Some("s1".to_string()), None]);
+        let failures: Vec<_> = result.failures().collect();
+        assert_eq!(failures[0].0, "secret");
+        assert_eq!(failures[0].1.code, 40160);
+    }
+
+    #[test]
+    fn test_bare_array_counts_outcomes() {
+        let value = json!([
+            {"channel": "a", "presence": [{"clientId": "bob", "action": 1}]},
+            {"channel": "b", "error": {"code": 40400}},
+            {"channel": "c"}
+        ]);
+        let result = BatchResult::<BatchPresenceSuccess>::from_value(value).unwrap();
+
+        assert_eq!((result.success_count, result.failure_count), (2, 1));
+        let members = &result.results[0].result.as_ref().unwrap().presence;
+        assert_eq!(members[0].client_id.as_deref(), Some("bob"));
+        assert!(!result.results[1].is_success());
+    }
+
+    #[test]
+    fn test_result_without_channel_rejected() {
+        let value = json!([{"messageId": "abc"}]);
+        assert!(BatchResult::<BatchPublishSuccess>::from_value(value).is_err());
+    }
+}
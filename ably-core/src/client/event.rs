@@ -0,0 +1,117 @@
+// Channel event enums mapped to named messages
+
+use crate::client::typed::{ensure_decoded, TypedMessage};
+use crate::error::{AblyError, AblyResult};
+use crate::protocol::messages::Message;
+
+/// Derive `AblyEvent` for an enum; see the `ably-derive` crate for attributes
+pub use ably_derive::AblyEvent;
+
+/// An enum whose variants are the events published on a channel
+///
+/// Each variant maps to a `Message.name` and its payload to `Message.data`.
+/// Usually derived with `#[derive(AblyEvent)]` rather than implemented by hand.
+pub trait AblyEvent: Sized {
+    /// Message names of every variant
+    const NAMES: &'static [&'static str];
+
+    /// Message name of this event
+    fn event_name(&self) -> &'static str;
+
+    /// Encode the event as a message ready to publish
+    fn to_message(&self) -> AblyResult<Message>;
+
+    /// Decode an event from a received message, or None if its name is not one of ours
+    fn from_message(message: &Message) -> AblyResult<Option<Self>>;
+}
+
+/// Decode a subscribed message into an event, keeping its metadata
+pub(crate) fn decode_event<E: AblyEvent>(message: Message) -> AblyResult<TypedMessage<E>> {
+    ensure_decoded(&message)?;
+    match E::from_message(&message)? {
+        Some(event) => Ok(TypedMessage::with_data(message, event)),
+        None => Err(AblyError::decode(format!(
+            "Unknown event '{}'",
+            message.name.as_deref().unwrap_or_default()
+        ))),
+    }
+}
+
+/// Support functions called by the code `#[derive(AblyEvent)]` generates
+#[doc(hidden)]
+pub mod __derive {
+    use crate::client::typed::deserialize_data;
+    use crate::error::{AblyError, AblyResult};
+    use crate::protocol::messages::{Message, MessageData};
+    use serde::de::DeserializeOwned;
+    use serde::Serialize;
+    use serde_json::Map;
+    use std::fmt::Display;
+    use std::str::FromStr;
+
+    pub use serde_json::Value;
+
+    pub fn message(name: &str, data: Option<MessageData>) -> Message {
+        Message {
+            name: Some(name.to_string()),
+            data,
+            ..Default::default()
+        }
+    }
+
+    pub fn encode_json<T: Serialize + ?Sized>(event: &str, value: &T) -> AblyResult<MessageData> {
+        serde_json::to_value(value)
+            .map(MessageData::from)
+            .map_err(|e| AblyError::encoding(format!("Failed to encode event '{}': {}", event, e)))
+    }
+
+    pub fn encode_field<T: Serialize + ?Sized>(event: &str, field: &str, value: &T) -> AblyResult<(String, Value)> {
+        let value = serde_json::to_value(value).map_err(|e| {
+            AblyError::encoding(format!("Failed to encode event '{}' field '{}': {}", event, field, e))
+        })?;
+        Ok((field.to_string(), value))
+    }
+
+    pub fn encode_object(fields: Vec<(String, Value)>) -> MessageData {
+        MessageData::Json(Value::Object(fields.into_iter().collect::<Map<String, Value>>()))
+    }
+
+    pub fn encode_string<T: Display + ?Sized>(value: &T) -> MessageData {
+        MessageData::String(value.to_string())
+    }
+
+    pub fn encode_binary<T: AsRef<[u8]> + ?Sized>(value: &T) -> MessageData {
+        MessageData::Binary(value.as_ref().to_vec())
+    }
+
+    pub fn decode_json<T: DeserializeOwned>(event: &str, message: &Message) -> AblyResult<T> {
+        deserialize_data(message.data.as_ref()).map_err(|e| decode_error(event, e))
+    }
+
+    pub fn decode_field<T: DeserializeOwned>(event: &str, object: &Value, field: &str) -> AblyResult<T> {
+        let value = object.get(field).cloned().unwrap_or(Value::Null);
+        serde_json::from_value(value).map_err(|e| decode_error(event, format!("field '{}': {}", field, e)))
+    }
+
+    pub fn decode_string<T>(event: &str, message: &Message) -> AblyResult<T>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        match &message.data {
+            Some(MessageData::String(s)) => s.parse().map_err(|e| decode_error(event, e)),
+            _ => Err(decode_error(event, "expected string data")),
+        }
+    }
+
+    pub fn decode_binary<T: From<Vec<u8>>>(event: &str, message: &Message) -> AblyResult<T> {
+        match &message.data {
+            Some(MessageData::Binary(b)) => Ok(T::from(b.clone())),
+            _ => Err(decode_error(event, "expected binary data")),
+        }
+    }
+
+    fn decode_error(event: &str, e: impl Display) -> AblyError {
+        AblyError::decode(format!("Failed to decode event '{}': {}", event, e))
+    }
+}
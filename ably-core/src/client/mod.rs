@@ -1,10 +1,24 @@
 // Client module organization
 
+pub mod annotations;
+pub mod batch;
+pub mod event;
+pub mod pagination;
 pub mod rest;
 pub mod realtime;
+pub mod request;
+pub mod stats;
+pub mod subscription;
+pub mod typed;
 
 // Re-export main types
 pub use rest::{RestClient, Channel};
+pub use annotations::{RealtimeAnnotations, RestAnnotations};
+pub use batch::{BatchPublishSpec, BatchResult};
+pub use request::HttpPaginatedResponse;
+pub use subscription::{MessageEvent, MessageEvents, MessageFilter, OverflowPolicy, SubscribeOptions, Subscription, SubscriptionHandle};
+pub use typed::{TypedMessage, TypedSubscription};
+pub use event::AblyEvent;
 pub use crate::protocol::messages::Message;
 
 // Keep legacy client for backward compatibility
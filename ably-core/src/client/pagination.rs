@@ -0,0 +1,372 @@
+// Paginated REST results navigated with Link headers
+
+use crate::error::AblyResult;
+use crate::http::{AblyHttpClient, HttpResponse};
+use futures_util::future::BoxFuture;
+use futures_util::Stream;
+use serde::de::DeserializeOwned;
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+/// Post-processing applied to the items of every page, such as decoding payloads
+pub(crate) type PageHook<T> = Arc<dyn Fn(Vec<T>) -> BoxFuture<'static, AblyResult<Vec<T>>> + Send + Sync>;
+
+/// Page URLs from a response's `Link` header (TG4)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct PageLinks {
+    pub(crate) first: Option<String>,
+    pub(crate) current: Option<String>,
+    pub(crate) next: Option<String>,
+}
+
+impl PageLinks {
+    /// Parse a header such as `<./messages?limit=2>; rel="first", <./messages?cont=x>; rel="next"`
+    ///
+    /// Relative links are resolved against the path of the request that returned them.
+    pub(crate) fn parse(header: &str, request_path: &str) -> Self {
+        let mut links = Self::default();
+        for link in header.split(',') {
+            let mut parts = link.split(';');
+            let url = match parts.next().map(str::trim) {
+                Some(url) if url.starts_with('<') && url.ends_with('>') => &url[1..url.len() - 1],
+                _ => continue,
+            };
+            let url = resolve_link(url, request_path);
+
+            for param in parts {
+                let Some(rels) = param.trim().strip_prefix("rel=") else {
+                    continue;
+                };
+                for rel in rels.trim_matches('"').split_whitespace() {
+                    match rel {
+                        "first" => links.first = Some(url.clone()),
+                        "current" => links.current = Some(url.clone()),
+                        "next" => links.next = Some(url.clone()),
+                        _ => {}
+                    }
+                }
+            }
+        }
+        links
+    }
+
+    pub(crate) fn from_response(response: &HttpResponse, request_path: &str) -> Self {
+        response
+            .headers()
+            .get_all("link")
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .map(|value| Self::parse(value, request_path))
+            .fold(Self::default(), |links, parsed| Self {
+                first: links.first.or(parsed.first),
+                current: links.current.or(parsed.current),
+                next: links.next.or(parsed.next),
+            })
+    }
+}
+
+/// Resolve a `./`-relative link against the directory of the request path
+fn resolve_link(url: &str, request_path: &str) -> String {
+    match url.strip_prefix("./") {
+        Some(relative) => {
+            let path = request_path.split('?').next().unwrap_or_default();
+            let dir = path.rfind('/').map(|index| &path[..=index]).unwrap_or("/");
+            format!("{}{}", dir, relative)
+        }
+        None => url.to_string(),
+    }
+}
+
+/// Paginated result with navigation
+pub struct PaginatedResult<'a, T> {
+    pub items: Vec<T>,
+    http_client: &'a AblyHttpClient,
+    links: PageLinks,
+    hook: Option<PageHook<T>>,
+}
+
+impl<'a, T: DeserializeOwned> PaginatedResult<'a, T> {
+    /// Request one page and read its items and `Link` header
+    pub(crate) async fn fetch(
+        http_client: &'a AblyHttpClient,
+        path: &str,
+        params: &HashMap<String, String>,
+        hook: Option<PageHook<T>>,
+    ) -> AblyResult<Self> {
+        let response = http_client.get(path).query(params).send().await?;
+        Self::from_response(http_client, response, path, hook).await
+    }
+
+    /// Build a page from a response, running the page hook over its items
+    pub(crate) async fn from_response(
+        http_client: &'a AblyHttpClient,
+        response: HttpResponse,
+        path: &str,
+        hook: Option<PageHook<T>>,
+    ) -> AblyResult<Self> {
+        let links = PageLinks::from_response(&response, path);
+        let mut items: Vec<T> = response.json().await?;
+        if let Some(hook) = &hook {
+            items = hook(items).await?;
+        }
+
+        Ok(Self {
+            items,
+            http_client,
+            links,
+            hook,
+        })
+    }
+
+    /// Items on this page
+    pub fn items(&self) -> &[T] {
+        &self.items
+    }
+
+    /// Query for the first page of the result set
+    pub fn first(&self) -> Option<PaginationQuery<'a, T>> {
+        self.query(self.links.first.as_ref())
+    }
+
+    /// Query that fetches this page again
+    pub fn current(&self) -> Option<PaginationQuery<'a, T>> {
+        self.query(self.links.current.as_ref())
+    }
+
+    /// Query for the following page, or None on the last page
+    pub fn next(&self) -> Option<PaginationQuery<'a, T>> {
+        self.query(self.links.next.as_ref())
+    }
+
+    pub fn has_next(&self) -> bool {
+        self.links.next.is_some()
+    }
+
+    /// Whether this is the last page of the result set
+    pub fn is_last(&self) -> bool {
+        !self.has_next()
+    }
+
+    fn query(&self, url: Option<&String>) -> Option<PaginationQuery<'a, T>> {
+        url.map(|url| PaginationQuery::new(url.clone(), self.http_client, self.hook.clone()))
+    }
+}
+
+/// Request for a page linked from another page
+pub struct PaginationQuery<'a, T> {
+    url: String,
+    http_client: &'a AblyHttpClient,
+    hook: Option<PageHook<T>>,
+}
+
+impl<'a, T: DeserializeOwned> PaginationQuery<'a, T> {
+    fn new(url: String, http_client: &'a AblyHttpClient, hook: Option<PageHook<T>>) -> Self {
+        Self {
+            url,
+            http_client,
+            hook,
+        }
+    }
+
+    pub async fn execute(&self) -> AblyResult<PaginatedResult<'a, T>> {
+        PaginatedResult::fetch(self.http_client, &self.url, &HashMap::new(), self.hook.clone()).await
+    }
+}
+
+/// Stream over the items of every page, fetching pages as they are needed
+///
+/// Only the page being read is held in memory unless `prefetch` asks for
+/// pages to be requested ahead; those requests make progress whenever the
+/// stream is polled. A failed page request yields its error and ends the stream.
+pub struct PaginatedStream<'a, T> {
+    pending: Option<BoxFuture<'a, AblyResult<PaginatedResult<'a, T>>>>,
+    next: Option<PaginationQuery<'a, T>>,
+    pages: VecDeque<VecDeque<T>>,
+    prefetch: usize,
+    remaining: Option<usize>,
+}
+
+impl<'a, T: DeserializeOwned + Send + 'a> PaginatedStream<'a, T> {
+    /// Stream starting from the page returned by `first`
+    pub(crate) fn new<F>(first: F) -> Self
+    where
+        F: Future<Output = AblyResult<PaginatedResult<'a, T>>> + Send + 'a,
+    {
+        Self {
+            pending: Some(Box::pin(first)),
+            next: None,
+            pages: VecDeque::new(),
+            prefetch: 0,
+            remaining: None,
+        }
+    }
+
+    /// Request up to `depth` pages ahead of the one being read
+    pub fn prefetch(mut self, depth: usize) -> Self {
+        self.prefetch = depth;
+        self
+    }
+
+    /// End the stream after `limit` items, without requesting further pages
+    pub fn max_items(mut self, limit: usize) -> Self {
+        self.remaining = Some(limit);
+        self
+    }
+
+    fn buffered(&self) -> usize {
+        self.pages.iter().map(VecDeque::len).sum()
+    }
+
+    /// Whether another page should be requested now
+    fn wants_page(&self) -> bool {
+        let needed = self.remaining.is_none_or(|remaining| self.buffered() < remaining);
+        self.pending.is_none() && self.next.is_some() && needed && self.pages.len() <= self.prefetch
+    }
+}
+
+// Items are never pinned in place, so the stream can move freely
+impl<T> Unpin for PaginatedStream<'_, T> {}
+
+impl<'a, T: DeserializeOwned + Send + 'a> Stream for PaginatedStream<'a, T> {
+    type Item = AblyResult<T>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.remaining == Some(0) {
+            this.pending = None;
+            return Poll::Ready(None);
+        }
+
+        loop {
+            if let Some(pending) = this.pending.as_mut() {
+                if let Poll::Ready(result) = pending.as_mut().poll(cx) {
+                    this.pending = None;
+                    match result {
+                        Ok(page) => {
+                            this.next = page.next();
+                            this.pages.push_back(page.items.into());
+                        }
+                        Err(e) => {
+                            this.next = None;
+                            this.remaining = Some(0);
+                            return Poll::Ready(Some(Err(e)));
+                        }
+                    }
+                }
+            }
+
+            while this.pages.front().is_some_and(VecDeque::is_empty) {
+                this.pages.pop_front();
+            }
+            if this.wants_page() {
+                if let Some(query) = this.next.take() {
+                    this.pending = Some(Box::pin(async move { query.execute().await }));
+                    continue;
+                }
+            }
+            break;
+        }
+
+        if let Some(item) = this.pages.front_mut().and_then(VecDeque::pop_front) {
+            if let Some(remaining) = this.remaining.as_mut() {
+                *remaining -= 1;
+            }
+            return Poll::Ready(Some(Ok(item)));
+        }
+        match this.pending {
+            Some(_) => Poll::Pending,
+            None => Poll::Ready(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::mock::{MockResponse, MockServer};
+    use crate::http::HttpConfig;
+    use futures_util::StreamExt;
+
+    /// Serve `/items?page=N` as pages of two numbers linked up to `pages`
+    async fn paged_server(pages: usize) -> (AblyHttpClient, MockServer) {
+        let server = MockServer::start(move |request| {
+            let page: usize = request.query("page").and_then(|page| page.parse().ok()).unwrap_or(1);
+            let response = MockResponse::json(200, &serde_json::json!([page * 2 - 1, page * 2]));
+            match page < pages {
+                true => response.header("link", &format!("<./items?page={}>; rel=\"next\"", page + 1)),
+                false => response,
+            }
+        })
+        .await;
+        let config = HttpConfig::builder().base_url(server.url()).build();
+        (AblyHttpClient::from_config(config), server)
+    }
+
+    fn stream(client: &AblyHttpClient) -> PaginatedStream<'_, u32> {
+        PaginatedStream::new(async move { PaginatedResult::fetch(client, "/items", &HashMap::new(), None).await })
+    }
+
+    #[tokio::test]
+    async fn test_stream_follows_next_links_lazily() {
+        let (client, server) = paged_server(3).await;
+        let mut items = stream(&client);
+
+        assert_eq!(items.next().await.unwrap().unwrap(), 1);
+        assert_eq!(items.next().await.unwrap().unwrap(), 2);
+        assert_eq!(server.hits(), 1);
+
+        let rest: Vec<u32> = items.map(|item| item.unwrap()).collect().await;
+        assert_eq!(rest, vec![3, 4, 5, 6]);
+        assert_eq!(server.hits(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_stream_item_cap_and_prefetch() {
+        let (client, server) = paged_server(5).await;
+        let capped: Vec<u32> = stream(&client).max_items(3).map(|item| item.unwrap()).collect().await;
+        assert_eq!(capped, vec![1, 2, 3]);
+        assert_eq!(server.hits(), 2);
+
+        // Prefetching never requests pages beyond the cap
+        let (client, server) = paged_server(5).await;
+        let capped: Vec<u32> = stream(&client).prefetch(4).max_items(3).map(|item| item.unwrap()).collect().await;
+        assert_eq!(capped, vec![1, 2, 3]);
+        assert_eq!(server.hits(), 2);
+
+        let (client, _) = paged_server(5).await;
+        let all: Vec<u32> = stream(&client).prefetch(2).map(|item| item.unwrap()).collect().await;
+        assert_eq!(all, (1..=10).collect::<Vec<u32>>());
+    }
+
+    #[test]
+    fn test_parse_link_header() {
+        let header = "<./messages?limit=2&direction=backwards>; rel=\"first\", \
+                      <./messages?limit=2&cont=abc>; rel=\"current\", \
+                      <./messages?limit=2&cont=def>; rel=\"next\"";
+        let links = PageLinks::parse(header, "/channels/orders/messages?limit=2");
+
+        assert_eq!(links.first.as_deref(), Some("/channels/orders/messages?limit=2&direction=backwards"));
+        assert_eq!(links.current.as_deref(), Some("/channels/orders/messages?limit=2&cont=abc"));
+        assert_eq!(links.next.as_deref(), Some("/channels/orders/messages?limit=2&cont=def"));
+    }
+
+    #[test]
+    fn test_parse_last_page_and_shared_rels() {
+        let links = PageLinks::parse("<./stats?unit=hour>; rel=\"first current\"", "/stats");
+        assert_eq!(links.first.as_deref(), Some("/stats?unit=hour"));
+        assert_eq!(links.current, links.first);
+        assert_eq!(links.next, None);
+
+        assert_eq!(PageLinks::parse("", "/stats"), PageLinks::default());
+        assert_eq!(PageLinks::parse("not a link", "/stats"), PageLinks::default());
+    }
+
+    #[test]
+    fn test_absolute_links_kept() {
+        let links = PageLinks::parse("</push/deviceRegistrations?cont=1>; rel=\"next\"", "/push/deviceRegistrations");
+        assert_eq!(links.next.as_deref(), Some("/push/deviceRegistrations?cont=1"));
+    }
+}
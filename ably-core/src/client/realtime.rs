@@ -2,10 +2,29 @@
 // WebSocket-based real-time client
 
 use crate::auth::AuthMode;
-use crate::connection::state_machine::{ConnectionStateMachine, ConnectionState, ConnectionEvent};
+use crate::client::annotations::{decode_annotations, AnnotationListener, RealtimeAnnotations, RestAnnotations};
+use crate::client::rest::{Channel, PaginatedResult, RestClient};
+use crate::client::subscription::{
+    MessageEvents, MessageFilter, MessageListener, SubscribeOptions, Subscription, SubscriptionHandle,
+};
+use crate::client::event::{decode_event, AblyEvent};
+use crate::client::typed::{typed_message, TypedSubscription};
+use crate::crypto::key_set::{decode_payload, encode_payload};
+use crate::crypto::{CipherKeySet, CipherParams};
+use crate::connection::state_machine::{ConnectionStateMachine, ConnectionState, ConnectionEvent, ConnectionStateChange};
+use crate::delta::{ChannelDeltaHandler, DeltaChannelConfig, DeltaContext, DeltaDecodeStats, DeltaPlugin, VcdiffDecoder};
 use crate::error::{AblyError, AblyResult};
-use crate::protocol::messages::{ProtocolMessage, Action, Message, PresenceMessage, ErrorInfo, PresenceAction};
+use crate::plugin::PluginManager;
+use crate::protocol::encoding::EncodingFormat;
+use crate::protocol::encoding::data_encoding::EncodedPayload;
+use crate::protocol::annotations::Annotation;
+use crate::protocol::messages::{
+    flags, ProtocolMessage, Action, Message, MessageData, MessageOperation, PresenceMessage, ErrorInfo, PresenceAction,
+};
 use crate::transport::{WebSocketTransport, TransportConfig};
+use futures_util::Stream;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::{mpsc, RwLock, oneshot};
@@ -14,21 +33,30 @@ use tracing::{debug, info, warn, error};
 /// Realtime client for WebSocket connections
 pub struct RealtimeClient {
     transport: Arc<WebSocketTransport>,
+    rest: Arc<RestClient>,
     state_machine: Arc<ConnectionStateMachine>,
     channels: Arc<RwLock<HashMap<String, RealtimeChannel>>>,
     message_tx: mpsc::Sender<ProtocolMessage>,
     message_rx: Arc<RwLock<mpsc::Receiver<ProtocolMessage>>>,
     msg_serial: Arc<RwLock<i64>>,
+    plugins: PluginManager,
 }
 
 impl RealtimeClient {
     /// Create a new realtime client with API key
     pub async fn new(api_key: impl Into<String>) -> AblyResult<Self> {
+        Self::with_plugins(api_key.into(), PluginManager::new()).await
+    }
+    
+    async fn with_plugins(api_key: String, plugins: PluginManager) -> AblyResult<Self> {
         let config = TransportConfig::default();
-        let auth = AuthMode::ApiKey(api_key.into());
+        let auth = AuthMode::ApiKey(api_key.clone());
         let url = "wss://realtime.ably.io/"; // Trailing slash is REQUIRED!
         let transport = WebSocketTransport::new(url, config, auth);
         
+        // REST client for history and other request/response operations, same credentials
+        let rest = Arc::new(RestClient::new(api_key).with_plugins(plugins.clone()));
+        
         let state_machine = Arc::new(ConnectionStateMachine::new());
         
         // Start state machine event processor
@@ -42,11 +70,13 @@ impl RealtimeClient {
         
         let client = Self {
             transport: Arc::new(transport),
+            rest,
             state_machine,
             channels,
             message_tx,
             message_rx: Arc::new(RwLock::new(message_rx)),
             msg_serial: Arc::new(RwLock::new(0)),
+            plugins,
         };
         
         Ok(client)
@@ -114,13 +144,43 @@ impl RealtimeClient {
                 RealtimeChannel::new(
                     name.clone(),
                     self.transport.clone(),
+                    self.rest.clone(),
                     self.state_machine.clone(),
                     self.msg_serial.clone(),
+                    self.plugins.clone(),
                 )
             })
             .clone()
     }
     
+    /// Get or create a channel and apply the given options
+    pub async fn channel_with_options(
+        &self,
+        name: impl Into<String>,
+        options: RealtimeChannelOptions,
+    ) -> AblyResult<RealtimeChannel> {
+        let channel = self.channel(name).await;
+        channel.set_options(options).await?;
+        Ok(channel)
+    }
+    
+    /// Get the REST client sharing this client's credentials
+    pub fn rest(&self) -> &RestClient {
+        &self.rest
+    }
+    
+    /// Get the plugins messages and protocol frames are run through
+    pub fn plugins(&self) -> &PluginManager {
+        &self.plugins
+    }
+    
+    /// Get a handle on the connection for state events
+    pub fn connection(&self) -> Connection {
+        Connection {
+            state_machine: self.state_machine.clone(),
+        }
+    }
+    
     /// Get connection state
     pub async fn state(&self) -> ConnectionState {
         self.state_machine.state().await
@@ -141,6 +201,7 @@ impl RealtimeClient {
         let transport = self.transport.clone();
         let state_machine = self.state_machine.clone();
         let channels = self.channels.clone();
+        let plugins = self.plugins.clone();
         
         tokio::spawn(async move {
             loop {
@@ -149,55 +210,12 @@ impl RealtimeClient {
                     Ok(message) => {
                         debug!("Received message: {:?}", message.action);
                         
-                        // Process message based on action
-                        match message.action {
-                            Action::Connected => {
-                                // Extract connection ID from message
-                                let connection_id = message.connection_id.clone()
-                                    .or_else(|| message.connection_details.as_ref()
-                                        .and_then(|d| d.connection_key.clone()))
-                                    .unwrap_or_else(|| "unknown".to_string());
-                                    
-                                let _ = state_machine.send_event(ConnectionEvent::Connected(connection_id)).await;
-                            }
-                            Action::Disconnected => {
-                                let _ = state_machine.send_event(ConnectionEvent::Disconnected(None)).await;
-                            }
-                            Action::Error => {
-                                if let Some(error) = message.error {
-                                    error!("Protocol error: {:?}", error);
-                                    let _ = state_machine.send_event(ConnectionEvent::Error(error.clone())).await;
-                                }
-                            }
-                            Action::Message => {
-                                // Route to appropriate channel
-                                if let Some(channel_name) = &message.channel {
-                                    let channels = channels.read().await;
-                                    if let Some(channel) = channels.get(channel_name) {
-                                        channel.handle_message(message).await;
-                                    }
-                                }
-                            }
-                            Action::Attached => {
-                                if let Some(channel_name) = &message.channel {
-                                    let channels = channels.read().await;
-                                    if let Some(channel) = channels.get(channel_name) {
-                                        channel.handle_attached().await;
-                                    }
-                                }
-                            }
-                            Action::Detached => {
-                                if let Some(channel_name) = &message.channel {
-                                    let channels = channels.read().await;
-                                    if let Some(channel) = channels.get(channel_name) {
-                                        channel.handle_detached().await;
-                                    }
-                                }
-                            }
-                            _ => {
-                                debug!("Unhandled message action: {:?}", message.action);
-                            }
+                        // Plugins observe every frame; a failing plugin must not stall the connection
+                        if let Err(e) = plugins.handle_protocol(&message).await {
+                            warn!("Plugin failed to handle {:?} frame: {}", message.action, e);
                         }
+                        
+                        route_frame(&state_machine, &channels, message).await;
                     }
                     Err(e) => {
                         error!("Error receiving message: {}", e);
@@ -212,47 +230,536 @@ impl RealtimeClient {
     }
 }
 
+/// Process a received frame based on its action
+async fn route_frame(
+    state_machine: &ConnectionStateMachine,
+    channels: &RwLock<HashMap<String, RealtimeChannel>>,
+    message: ProtocolMessage,
+) {
+    match message.action {
+        Action::Connected => {
+            // Extract connection ID from message
+            let connection_id = message.connection_id.clone()
+                .or_else(|| message.connection_details.as_ref()
+                    .and_then(|d| d.connection_key.clone()))
+                .unwrap_or_else(|| "unknown".to_string());
+                
+            let _ = state_machine.send_event(ConnectionEvent::Connected(connection_id)).await;
+        }
+        Action::Disconnected => {
+            let _ = state_machine.send_event(ConnectionEvent::Disconnected(message.error.clone())).await;
+        }
+        Action::Error => {
+            if let Some(error) = message.error {
+                error!("Protocol error: {:?}", error);
+                let _ = state_machine.send_event(ConnectionEvent::Error(error.clone())).await;
+            }
+        }
+        Action::Message | Action::Presence | Action::Annotation
+        | Action::Attached | Action::Detached => {
+            route_channel_frame(channels, message).await;
+        }
+        _ => {
+            debug!("Unhandled message action: {:?}", message.action);
+        }
+    }
+}
+
+/// Hand a channel frame to its channel without waiting on the channel's subscribers
+///
+/// The channel is cloned out of the map so the lock is not held while it
+/// handles the frame. Every frame, ATTACHED and DETACHED included, goes to the
+/// channel's own delivery task so they are handled in arrival order.
+async fn route_channel_frame(channels: &RwLock<HashMap<String, RealtimeChannel>>, message: ProtocolMessage) {
+    let channel = match &message.channel {
+        Some(name) => channels.read().await.get(name).cloned(),
+        None => None,
+    };
+    if let Some(channel) = channel {
+        channel.enqueue_inbound(message).await;
+    }
+}
+
+/// Handle on the realtime connection
+#[derive(Clone)]
+pub struct Connection {
+    state_machine: Arc<ConnectionStateMachine>,
+}
+
+impl Connection {
+    /// Get connection state
+    pub async fn state(&self) -> ConnectionState {
+        self.state_machine.state().await
+    }
+    
+    /// Get connection ID if connected
+    pub async fn id(&self) -> Option<String> {
+        self.state_machine.connection_id().await
+    }
+    
+    /// Get the error that caused the last failure or disconnection
+    pub async fn error_reason(&self) -> Option<ErrorInfo> {
+        self.state_machine.error().await
+    }
+    
+    /// Register a handler for every change into the given state
+    pub async fn on<F>(&self, state: ConnectionState, handler: F)
+    where
+        F: Fn(ConnectionStateChange) + Send + Sync + 'static,
+    {
+        self.state_machine.add_change_listener(move |change| {
+            if change.current == state {
+                handler(change.clone());
+            }
+            true
+        }).await;
+    }
+    
+    /// Register a handler for all state changes
+    pub async fn on_state_change<F>(&self, handler: F)
+    where
+        F: Fn(ConnectionStateChange) + Send + Sync + 'static,
+    {
+        self.state_machine.add_change_listener(move |change| {
+            handler(change.clone());
+            true
+        }).await;
+    }
+    
+    /// Register a handler for the next change into the given state only
+    pub async fn once<F>(&self, state: ConnectionState, handler: F)
+    where
+        F: Fn(ConnectionStateChange) + Send + Sync + 'static,
+    {
+        self.state_machine.add_change_listener(move |change| {
+            if change.current != state {
+                return true;
+            }
+            handler(change.clone());
+            false
+        }).await;
+    }
+    
+    /// Stream of all subsequent state changes
+    pub async fn state_changes(&self) -> impl Stream<Item = ConnectionStateChange> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.state_machine.add_change_listener(move |change| tx.send(change.clone()).is_ok()).await;
+        receiver_stream(rx)
+    }
+}
+
+/// Adapt an unbounded receiver into a stream
+fn receiver_stream<T>(rx: mpsc::UnboundedReceiver<T>) -> impl Stream<Item = T> {
+    futures_util::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|item| (item, rx))
+    })
+}
+
+/// Options for a realtime channel
+#[derive(Debug, Clone, Default)]
+pub struct RealtimeChannelOptions {
+    /// Channel params sent with ATTACH
+    pub params: HashMap<String, String>,
+    /// Encrypt outgoing and decrypt incoming message and presence data
+    pub cipher: Option<CipherParams>,
+    /// Rotatable keys; takes precedence over `cipher` when set
+    pub cipher_keys: Option<CipherKeySet>,
+    /// Channel modes requested on ATTACH, as `flags` bits; the server default when unset
+    pub modes: Option<u32>,
+    delta_context: Option<DeltaContext>,
+}
+
+impl RealtimeChannelOptions {
+    /// Create empty channel options
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Add a channel param
+    pub fn param(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.params.insert(key.into(), value.into());
+        self
+    }
+    
+    /// Request channel modes, e.g. `flags::SUBSCRIBE | flags::ANNOTATION_SUBSCRIBE`
+    pub fn modes(mut self, modes: u32) -> Self {
+        self.modes = Some(modes);
+        self
+    }
+    
+    /// Enable end-to-end encryption with the given cipher params
+    pub fn with_cipher(mut self, params: CipherParams) -> Self {
+        self.cipher = Some(params);
+        self
+    }
+    
+    /// Enable end-to-end encryption with a key set that can be rotated at runtime
+    pub fn with_cipher_keys(mut self, keys: CipherKeySet) -> Self {
+        self.cipher_keys = Some(keys);
+        self
+    }
+    
+    /// Keys used for this channel, if encryption is enabled
+    fn key_set(&self) -> Option<CipherKeySet> {
+        self.cipher_keys.clone().or_else(|| self.cipher.clone().map(CipherKeySet::from))
+    }
+    
+    /// Attach with `delta=vcdiff` and decode deltas with the built-in VCDIFF decoder
+    pub fn with_vcdiff_delta(self) -> Self {
+        self.with_delta_plugin(DeltaPlugin::new(VcdiffDecoder::new()))
+    }
+    
+    /// Attach with `delta=vcdiff` and decode deltas with a custom decoder plugin
+    pub fn with_delta_plugin(mut self, plugin: DeltaPlugin) -> Self {
+        let mut context = DeltaContext::new();
+        context.add_plugin("vcdiff", plugin);
+        
+        self.params.extend(DeltaChannelConfig::new().with_vcdiff().build_params());
+        self.delta_context = Some(context);
+        self
+    }
+    
+    /// Check if delta decoding is configured
+    pub fn is_delta_enabled(&self) -> bool {
+        self.delta_context.is_some()
+    }
+}
+
+/// Parameters for realtime channel history
+#[derive(Debug, Clone, Default)]
+pub struct RealtimeHistoryParams {
+    pub start: Option<i64>,
+    pub end: Option<i64>,
+    pub direction: Option<String>,
+    pub limit: Option<u32>,
+    /// End the history exactly where live delivery on this attachment began (RTL10b)
+    pub until_attach: bool,
+}
+
+/// Realtime channel states
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelState {
+    Initialized,
+    Attaching,
+    Attached,
+    Detaching,
+    Detached,
+    Suspended,
+    Failed,
+}
+
+/// Events emitted by a realtime channel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelEvent {
+    Initialized,
+    Attaching,
+    Attached,
+    Detaching,
+    Detached,
+    Suspended,
+    Failed,
+    /// Channel reattached or changed while remaining attached
+    Update,
+}
+
+impl From<ChannelState> for ChannelEvent {
+    fn from(state: ChannelState) -> Self {
+        match state {
+            ChannelState::Initialized => ChannelEvent::Initialized,
+            ChannelState::Attaching => ChannelEvent::Attaching,
+            ChannelState::Attached => ChannelEvent::Attached,
+            ChannelState::Detaching => ChannelEvent::Detaching,
+            ChannelState::Detached => ChannelEvent::Detached,
+            ChannelState::Suspended => ChannelEvent::Suspended,
+            ChannelState::Failed => ChannelEvent::Failed,
+        }
+    }
+}
+
+/// Channel state change or update event
+#[derive(Debug, Clone)]
+pub struct ChannelStateChange {
+    pub previous: ChannelState,
+    pub current: ChannelState,
+    pub event: ChannelEvent,
+    pub reason: Option<ErrorInfo>,
+    /// False when message continuity was lost on (re)attach
+    pub resumed: bool,
+}
+
 /// Realtime channel for pub/sub
 #[derive(Clone)]
 pub struct RealtimeChannel {
     name: String,
     transport: Arc<WebSocketTransport>,
+    rest: Arc<RestClient>,
     state_machine: Arc<ConnectionStateMachine>,
-    message_handlers: Arc<RwLock<Vec<MessageHandler>>>,
+    message_listeners: Arc<RwLock<Vec<Arc<MessageListener>>>>,
     presence_handlers: Arc<RwLock<Vec<PresenceHandler>>>,
+    annotation_listeners: Arc<RwLock<Vec<Arc<AnnotationListener>>>>,
     msg_serial: Arc<RwLock<i64>>,
+    options: Arc<RwLock<RealtimeChannelOptions>>,
+    delta_handler: Arc<RwLock<Option<ChannelDeltaHandler>>>,
+    state: Arc<RwLock<ChannelState>>,
+    channel_serial: Arc<RwLock<Option<String>>>,
+    attach_serial: Arc<RwLock<Option<String>>>,
+    state_handlers: Arc<RwLock<Vec<ChannelStateHandler>>>,
+    cipher: Arc<RwLock<Option<CipherKeySet>>>,
+    error_handlers: Arc<RwLock<Vec<ChannelErrorHandler>>>,
+    plugins: PluginManager,
+    /// Frames waiting for this channel's delivery task
+    inbound: mpsc::Sender<ProtocolMessage>,
 }
 
-type MessageHandler = Arc<dyn Fn(Message) + Send + Sync>;
+/// Maximum number of frames queued for a channel's delivery task
+const INBOUND_QUEUE_CAPACITY: usize = 256;
+
 type PresenceHandler = Arc<dyn Fn(PresenceMessage) + Send + Sync>;
+type ChannelErrorHandler = Arc<dyn Fn(ErrorInfo) + Send + Sync>;
+/// Returns false once the handler should be removed
+type ChannelStateHandler = Box<dyn Fn(&ChannelStateChange) -> bool + Send + Sync>;
 
 impl RealtimeChannel {
     fn new(
         name: String,
         transport: Arc<WebSocketTransport>,
+        rest: Arc<RestClient>,
         state_machine: Arc<ConnectionStateMachine>,
         msg_serial: Arc<RwLock<i64>>,
+        plugins: PluginManager,
     ) -> Self {
-        Self {
+        let (inbound, mut inbound_rx) = mpsc::channel(INBOUND_QUEUE_CAPACITY);
+        let channel = Self {
             name,
             transport,
+            rest,
             state_machine,
-            message_handlers: Arc::new(RwLock::new(Vec::new())),
+            message_listeners: Arc::new(RwLock::new(Vec::new())),
             presence_handlers: Arc::new(RwLock::new(Vec::new())),
+            annotation_listeners: Arc::new(RwLock::new(Vec::new())),
             msg_serial,
+            options: Arc::new(RwLock::new(RealtimeChannelOptions::default())),
+            delta_handler: Arc::new(RwLock::new(None)),
+            state: Arc::new(RwLock::new(ChannelState::Initialized)),
+            channel_serial: Arc::new(RwLock::new(None)),
+            attach_serial: Arc::new(RwLock::new(None)),
+            state_handlers: Arc::new(RwLock::new(Vec::new())),
+            cipher: Arc::new(RwLock::new(None)),
+            error_handlers: Arc::new(RwLock::new(Vec::new())),
+            plugins,
+            inbound,
+        };
+        
+        // The task's copy holds a closed sender, so it ends once every other copy is dropped
+        let worker = Self {
+            inbound: mpsc::channel(1).0,
+            ..channel.clone()
+        };
+        tokio::spawn(async move {
+            while let Some(message) = inbound_rx.recv().await {
+                worker.handle_message(message).await;
+            }
+        });
+        channel
+    }
+    
+    /// Queue a frame for delivery in order, without waiting for subscribers
+    ///
+    /// When a blocked subscriber has let the queue fill up, the frame is
+    /// dropped and reported to the channel's error handlers.
+    async fn enqueue_inbound(&self, message: ProtocolMessage) {
+        match self.inbound.try_send(message) {
+            Ok(()) => {}
+            Err(mpsc::error::TrySendError::Full(message)) => {
+                warn!("Inbound queue for channel {} is full, dropping {:?} frame", self.name, message.action);
+                self.emit_error(ErrorInfo {
+                    code: 50001,
+                    message: Some(format!(
+                        "Inbound queue of {} frames overflowed; dropped {:?} frame",
+                        INBOUND_QUEUE_CAPACITY, message.action
+                    )),
+                    status_code: Some(500),
+                    ..Default::default()
+                }).await;
+            }
+            Err(mpsc::error::TrySendError::Closed(_)) => {
+                warn!("Delivery task for channel {} has stopped", self.name);
+            }
         }
     }
     
-    /// Attach to the channel
-    pub async fn attach(&self) -> AblyResult<()> {
-        info!("Attaching to channel: {}", self.name);
+    /// Get the channel name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    
+    /// Get the current channel state
+    pub async fn state(&self) -> ChannelState {
+        *self.state.read().await
+    }
+    
+    /// Get the latest channel serial received from the server
+    pub async fn channel_serial(&self) -> Option<String> {
+        self.channel_serial.read().await.clone()
+    }
+    
+    /// Register a handler for channel state changes and update events
+    pub async fn on_state_change<F>(&self, handler: F)
+    where
+        F: Fn(ChannelStateChange) + Send + Sync + 'static,
+    {
+        self.add_state_handler(move |change| {
+            handler(change.clone());
+            true
+        }).await;
+    }
+    
+    /// Register a handler for every occurrence of the given event
+    pub async fn on<F>(&self, event: ChannelEvent, handler: F)
+    where
+        F: Fn(ChannelStateChange) + Send + Sync + 'static,
+    {
+        self.add_state_handler(move |change| {
+            if change.event == event {
+                handler(change.clone());
+            }
+            true
+        }).await;
+    }
+    
+    /// Register a handler for the next occurrence of the given event only
+    pub async fn once<F>(&self, event: ChannelEvent, handler: F)
+    where
+        F: Fn(ChannelStateChange) + Send + Sync + 'static,
+    {
+        self.add_state_handler(move |change| {
+            if change.event != event {
+                return true;
+            }
+            handler(change.clone());
+            false
+        }).await;
+    }
+    
+    /// Stream of all subsequent state changes and update events
+    pub async fn state_changes(&self) -> impl Stream<Item = ChannelStateChange> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.add_state_handler(move |change| tx.send(change.clone()).is_ok()).await;
+        receiver_stream(rx)
+    }
+    
+    async fn add_state_handler<F>(&self, handler: F)
+    where
+        F: Fn(&ChannelStateChange) -> bool + Send + Sync + 'static,
+    {
+        let mut handlers = self.state_handlers.write().await;
+        handlers.push(Box::new(handler));
+    }
+    
+    /// Transition to a new state, notifying handlers if it changed
+    async fn set_state(&self, current: ChannelState, reason: Option<ErrorInfo>, resumed: bool) {
+        let previous = {
+            let mut state = self.state.write().await;
+            std::mem::replace(&mut *state, current)
+        };
+        
+        if previous == current {
+            return;
+        }
         
-        let attach_message = ProtocolMessage {
+        // The channel serial is only meaningful while the channel is attached
+        if matches!(current, ChannelState::Detached | ChannelState::Suspended | ChannelState::Failed) {
+            *self.channel_serial.write().await = None;
+        }
+        
+        self.emit_state_change(ChannelStateChange {
+            previous,
+            current,
+            event: current.into(),
+            reason,
+            resumed,
+        }).await;
+    }
+    
+    /// Notify state change handlers
+    async fn emit_state_change(&self, change: ChannelStateChange) {
+        debug!("Channel {} event {:?}: {:?} -> {:?}", self.name, change.event, change.previous, change.current);
+        
+        let mut handlers = self.state_handlers.write().await;
+        handlers.retain(|handler| handler(&change));
+    }
+    
+    /// Set channel options, taking effect on the next attach
+    pub async fn set_options(&self, options: RealtimeChannelOptions) -> AblyResult<()> {
+        let delta_handler = match &options.delta_context {
+            Some(context) => {
+                let mut handler = ChannelDeltaHandler::new(self.name.clone(), context.clone());
+                handler.set_channel_params(options.params.clone());
+                handler.validate_configuration()?;
+                Some(handler)
+            }
+            None => None,
+        };
+        
+        *self.delta_handler.write().await = delta_handler;
+        *self.cipher.write().await = options.key_set();
+        *self.options.write().await = options;
+        Ok(())
+    }
+    
+    /// Get the current channel options
+    pub async fn options(&self) -> RealtimeChannelOptions {
+        self.options.read().await.clone()
+    }
+    
+    /// Register a handler for non-fatal channel errors, such as messages that failed to decrypt
+    pub async fn on_error<F>(&self, handler: F)
+    where
+        F: Fn(ErrorInfo) + Send + Sync + 'static,
+    {
+        let mut handlers = self.error_handlers.write().await;
+        handlers.push(Arc::new(handler));
+    }
+    
+    /// Notify error handlers
+    async fn emit_error(&self, error: ErrorInfo) {
+        let handlers = self.error_handlers.read().await;
+        for handler in handlers.iter() {
+            handler(error.clone());
+        }
+    }
+    
+    /// Get delta decode statistics, if delta decoding is enabled
+    pub async fn delta_stats(&self) -> Option<DeltaDecodeStats> {
+        self.delta_handler.read().await
+            .as_ref()
+            .map(|handler| handler.decode_stats().clone())
+    }
+    
+    /// Build an ATTACH message carrying the channel params
+    async fn attach_message(&self, channel_serial: Option<String>) -> ProtocolMessage {
+        let options = self.options.read().await;
+        let params = options.params.clone();
+        
+        ProtocolMessage {
             action: Action::Attach,
             channel: Some(self.name.clone()),
+            channel_serial,
+            flags: options.modes,
+            params: if params.is_empty() { None } else { Some(params) },
             ..Default::default()
-        };
+        }
+    }
+    
+    /// Attach to the channel
+    pub async fn attach(&self) -> AblyResult<()> {
+        info!("Attaching to channel: {}", self.name);
+        
+        // Resume from the last known position so the server can report continuity
+        let channel_serial = self.channel_serial().await;
+        let attach_message = self.attach_message(channel_serial).await;
         
+        self.set_state(ChannelState::Attaching, None, false).await;
         self.transport.send_message(attach_message).await?;
         
         // TODO: Wait for ATTACHED confirmation
@@ -272,6 +779,7 @@ impl RealtimeChannel {
             ..Default::default()
         };
         
+        self.set_state(ChannelState::Detaching, None, false).await;
         self.transport.send_message(detach_message).await?;
         
         info!("Detached from channel: {}", self.name);
@@ -279,18 +787,15 @@ impl RealtimeChannel {
     }
     
     /// Publish a message to the channel
-    pub async fn publish(&self, message: Message) -> AblyResult<()> {
-        // Increment and get message serial
-        let mut serial = self.msg_serial.write().await;
-        *serial += 1;
-        let current_serial = *serial;
-        drop(serial);
+    pub async fn publish(&self, mut message: Message) -> AblyResult<()> {
+        self.plugins.process_channel_outbound(&self.name, &mut message).await?;
+        encode_payload(&mut message, self.cipher.read().await.as_ref(), EncodingFormat::Json)?;
         
         let protocol_message = ProtocolMessage {
             action: Action::Message,
             channel: Some(self.name.clone()),
             messages: Some(vec![message]),
-            msg_serial: Some(current_serial),
+            msg_serial: Some(self.next_msg_serial().await),
             ..Default::default()
         };
         
@@ -298,31 +803,84 @@ impl RealtimeChannel {
         Ok(())
     }
     
-    /// Subscribe to messages (returns a receiver for the messages)
-    pub async fn subscribe(&self) -> mpsc::Receiver<Message> {
-        let (tx, rx) = mpsc::channel(100);
-        let tx = Arc::new(tx);
-        
-        let handler: MessageHandler = Arc::new(move |msg: Message| {
-            let tx = tx.clone();
-            tokio::spawn(async move {
-                let _ = tx.send(msg).await;
-            });
-        });
-        
-        let mut handlers = self.message_handlers.write().await;
-        handlers.push(handler);
-        
-        rx
+    /// Increment and get the connection's message serial
+    async fn next_msg_serial(&self) -> i64 {
+        let mut serial = self.msg_serial.write().await;
+        *serial += 1;
+        *serial
+    }
+    
+    /// Publish a named message whose data is serialized from `value`
+    pub async fn publish_typed<T: Serialize + ?Sized>(&self, name: &str, value: &T) -> AblyResult<()> {
+        self.publish(typed_message(name, value)?).await
+    }
+    
+    /// Publish an event as a message named after its variant
+    pub async fn publish_event<E: AblyEvent>(&self, event: E) -> AblyResult<()> {
+        self.publish(event.to_message()?).await
+    }
+    
+    /// Subscribe to all messages as an ordered stream
+    pub async fn subscribe(&self) -> Subscription {
+        self.subscribe_with_options(SubscribeOptions::default()).await
+    }
+    
+    /// Subscribe to messages with any of the given names
+    pub async fn subscribe_names(&self, names: &[&str]) -> Subscription {
+        let filter = names.iter().fold(MessageFilter::new(), |filter, name| filter.name(*name));
+        self.subscribe_filtered(filter).await
+    }
+    
+    /// Subscribe to messages matching a name and extras header filter
+    pub async fn subscribe_filtered(&self, filter: MessageFilter) -> Subscription {
+        self.subscribe_with_options(SubscribeOptions::new().filter(filter)).await
+    }
+    
+    /// Subscribe with explicit buffer size, overflow policy and filter
+    pub async fn subscribe_with_options(&self, options: SubscribeOptions) -> Subscription {
+        let (listener, subscription) = MessageListener::buffered(options);
+        self.message_listeners.write().await.push(Arc::new(listener));
+        subscription
+    }
+    
+    /// Subscribe to all messages, deserializing their data into `T`
+    pub async fn subscribe_typed<T: DeserializeOwned>(&self) -> TypedSubscription<T> {
+        self.subscribe_typed_with_options(SubscribeOptions::default()).await
+    }
+    
+    /// Subscribe with explicit options, deserializing message data into `T`
+    pub async fn subscribe_typed_with_options<T: DeserializeOwned>(&self, options: SubscribeOptions) -> TypedSubscription<T> {
+        TypedSubscription::new(self.subscribe_with_options(options).await)
+    }
+    
+    /// Subscribe to the events of `E`, skipping messages with other names
+    pub async fn subscribe_events<E: AblyEvent>(&self) -> TypedSubscription<E> {
+        let filter = E::NAMES.iter().fold(MessageFilter::new(), |filter, name| filter.name(*name));
+        let subscription = self.subscribe_filtered(filter).await;
+        TypedSubscription::with_decoder(subscription, decode_event::<E>)
+    }
+    
+    /// Subscribe to all messages, classified as created, updated, deleted and so on
+    pub async fn subscribe_message_events(&self) -> MessageEvents {
+        MessageEvents::new(self.subscribe().await)
     }
     
     /// Subscribe to messages with handler function
-    pub async fn subscribe_with_handler<F>(&self, handler: F) 
+    pub async fn subscribe_with_handler<F>(&self, handler: F) -> SubscriptionHandle
     where
         F: Fn(Message) + Send + Sync + 'static,
     {
-        let mut handlers = self.message_handlers.write().await;
-        handlers.push(Arc::new(handler));
+        self.subscribe_filtered_with_handler(MessageFilter::new(), handler).await
+    }
+    
+    /// Subscribe to messages matching a filter with handler function
+    pub async fn subscribe_filtered_with_handler<F>(&self, filter: MessageFilter, handler: F) -> SubscriptionHandle
+    where
+        F: Fn(Message) + Send + Sync + 'static,
+    {
+        let (listener, handle) = MessageListener::handler(filter, Arc::new(handler));
+        self.message_listeners.write().await.push(Arc::new(listener));
+        handle
     }
     
     /// Subscribe to presence messages
@@ -334,6 +892,106 @@ impl RealtimeChannel {
         handlers.push(Arc::new(handler));
     }
     
+    /// Publish, delete and subscribe to annotations on this channel's messages
+    pub fn annotations(&self) -> RealtimeAnnotations {
+        RealtimeAnnotations::new(self.clone())
+    }
+    
+    pub(crate) fn rest_annotations(&self) -> RestAnnotations<'_> {
+        self.rest.channel(self.name.clone()).annotations()
+    }
+    
+    /// Send an already encoded annotation
+    pub(crate) async fn send_annotation(&self, annotation: Annotation) -> AblyResult<()> {
+        let protocol_message = ProtocolMessage {
+            action: Action::Annotation,
+            channel: Some(self.name.clone()),
+            annotations: Some(vec![annotation]),
+            msg_serial: Some(self.next_msg_serial().await),
+            ..Default::default()
+        };
+        
+        self.transport.send_message(protocol_message).await?;
+        Ok(())
+    }
+    
+    /// Subscribe to received annotations, optionally of a single type
+    pub(crate) async fn subscribe_annotations(
+        &self,
+        annotation_type: Option<String>,
+        options: SubscribeOptions,
+    ) -> Subscription<Annotation> {
+        let (listener, subscription) = AnnotationListener::new(annotation_type, &options);
+        self.annotation_listeners.write().await.push(Arc::new(listener));
+        subscription
+    }
+    
+    /// REST view of this channel, sharing its cipher keys
+    async fn rest_channel(&self) -> Channel<'_> {
+        let channel = self.rest.channel(self.name.clone());
+        match self.cipher.read().await.clone() {
+            Some(keys) => channel.with_cipher_keys(keys),
+            None => channel,
+        }
+    }
+    
+    /// Get message history via REST
+    pub async fn history(&self, params: RealtimeHistoryParams) -> AblyResult<PaginatedResult<'_, Message>> {
+        let mut query = self.rest_channel().await.history();
+        
+        if let Some(start) = params.start {
+            query = query.start(start);
+        }
+        if let Some(end) = params.end {
+            query = query.end(end);
+        }
+        if let Some(direction) = &params.direction {
+            query = query.direction(direction);
+        }
+        if let Some(limit) = params.limit {
+            query = query.limit(limit);
+        }
+        
+        if params.until_attach {
+            if self.state().await != ChannelState::Attached {
+                return Err(AblyError::invalid_request(
+                    "untilAttach requires the channel to be attached"
+                ));
+            }
+            
+            let attach_serial = self.attach_serial.read().await.clone()
+                .ok_or_else(|| AblyError::unexpected("Attach serial not available"))?;
+            query = query.from_serial(&attach_serial);
+        }
+        
+        query.execute().await
+    }
+    
+    /// Replace the published message with the same serial
+    pub async fn update_message(&self, message: Message, operation: Option<MessageOperation>) -> AblyResult<()> {
+        self.rest_channel().await.update_message(message, operation).await
+    }
+    
+    /// Mark the published message with the same serial as deleted
+    pub async fn delete_message(&self, message: Message, operation: Option<MessageOperation>) -> AblyResult<()> {
+        self.rest_channel().await.delete_message(message, operation).await
+    }
+    
+    /// Append data to the published message with the same serial
+    pub async fn append_message(&self, message: Message, operation: Option<MessageOperation>) -> AblyResult<()> {
+        self.rest_channel().await.append_message(message, operation).await
+    }
+    
+    /// Get the latest version of a message via REST
+    pub async fn get_message(&self, serial: &str) -> AblyResult<Message> {
+        self.rest_channel().await.get_message(serial).await
+    }
+    
+    /// Get every version of a message via REST
+    pub async fn get_message_versions(&self, serial: &str) -> AblyResult<PaginatedResult<'_, Message>> {
+        self.rest_channel().await.get_message_versions(serial).await
+    }
+    
     /// Get presence members
     pub async fn presence_get(&self) -> AblyResult<Vec<PresenceMessage>> {
         // TODO: Implement presence get
@@ -342,19 +1000,21 @@ impl RealtimeChannel {
     
     /// Enter presence
     pub async fn presence_enter(&self, data: Option<serde_json::Value>) -> AblyResult<()> {
+        let mut presence_message = PresenceMessage {
+            action: Some(PresenceAction::Enter),
+            client_id: Some("rust-client".to_string()),
+            data: data.map(MessageData::from),
+            ..Default::default()
+        };
+        
+        encode_payload(&mut presence_message, self.cipher.read().await.as_ref(), EncodingFormat::Json)?;
+        
         // Increment and get message serial
         let mut serial = self.msg_serial.write().await;
         *serial += 1;
         let current_serial = *serial;
         drop(serial);
         
-        let presence_message = PresenceMessage {
-            action: Some(PresenceAction::Enter),
-            client_id: Some("rust-client".to_string()),
-            data,
-            ..Default::default()
-        };
-        
         let protocol_message = ProtocolMessage {
             action: Action::Presence,
             channel: Some(self.name.clone()),
@@ -393,18 +1053,64 @@ impl RealtimeChannel {
         Ok(())
     }
     
-    /// Handle incoming message
-    async fn handle_message(&self, message: ProtocolMessage) {
-        if let Some(messages) = message.messages {
-            let handlers = self.message_handlers.read().await;
-            for msg in messages {
-                for handler in handlers.iter() {
-                    handler(msg.clone());
+    /// Decode delta-encoded messages, starting recovery on failure
+    async fn decode_messages(&self, message: &ProtocolMessage) -> Option<Vec<Message>> {
+        let mut delta_handler = self.delta_handler.write().await;
+        
+        let handler = match delta_handler.as_mut() {
+            Some(handler) => handler,
+            None => return message.messages.clone(),
+        };
+        
+        match handler.process_protocol_message(message).await {
+            Ok(messages) => Some(messages),
+            Err(e) => {
+                warn!("Delta decode failure on channel {}: {}", self.name, e);
+                
+                if handler.is_recovery_in_progress() {
+                    // Reattach from the last good channel serial (RTL18)
+                    let channel_serial = handler.last_channel_serial();
+                    drop(delta_handler);
+                    
+                    // Listeners see recovery in progress before the ATTACH goes out (RTL18c)
+                    self.set_state(ChannelState::Attaching, Some(ErrorInfo {
+                        code: 40018,
+                        message: Some(e.to_string()),
+                        status_code: Some(400),
+                        ..Default::default()
+                    }), false).await;
+                    
+                    let attach_message = self.attach_message(channel_serial).await;
+                    if let Err(e) = self.transport.send_message(attach_message).await {
+                        error!("Failed to reattach channel {} for delta recovery: {}", self.name, e);
+                    }
                 }
+                
+                None
             }
         }
+    }
+    
+    /// Handle incoming channel frame
+    async fn handle_message(&self, message: ProtocolMessage) {
+        match message.action {
+            Action::Attached => return self.handle_attached(&message).await,
+            Action::Detached => return self.handle_detached().await,
+            _ => {}
+        }
+        
+        if let Some(serial) = &message.channel_serial {
+            *self.channel_serial.write().await = Some(serial.clone());
+        }
         
-        if let Some(presence) = message.presence {
+        if let Some(mut messages) = self.decode_messages(&message).await {
+            self.decode_payloads(&mut messages).await;
+            let messages = self.process_inbound_plugins(messages).await;
+            self.dispatch_messages(messages).await;
+        }
+        
+        if let Some(mut presence) = message.presence {
+            self.decode_payloads(&mut presence).await;
             let handlers = self.presence_handlers.read().await;
             for msg in presence {
                 for handler in handlers.iter() {
@@ -412,16 +1118,122 @@ impl RealtimeChannel {
                 }
             }
         }
+        
+        if let Some(mut annotations) = message.annotations {
+            decode_annotations(&mut annotations, &self.name);
+            self.dispatch_annotations(annotations).await;
+        }
+    }
+    
+    /// Decode and decrypt payloads in place; failures are delivered with their encoding intact (RTL7e)
+    async fn decode_payloads<P: EncodedPayload>(&self, items: &mut [P]) {
+        let cipher = self.cipher.read().await.clone();
+        
+        for item in items.iter_mut() {
+            if let Err(e) = decode_payload(item, cipher.as_ref()) {
+                warn!("Failed to decode message on channel {}: {}", self.name, e);
+                self.emit_error(ErrorInfo {
+                    code: 40013,
+                    message: Some(format!("Failed to decode message: {}", e)),
+                    status_code: Some(400),
+                    ..Default::default()
+                }).await;
+            }
+        }
+    }
+    
+    /// Run delivered messages through plugins; rejected ones are dropped and reported as channel errors
+    async fn process_inbound_plugins(&self, messages: Vec<Message>) -> Vec<Message> {
+        let mut accepted = Vec::with_capacity(messages.len());
+        
+        for mut message in messages {
+            match self.plugins.process_channel_inbound(&self.name, &mut message).await {
+                Ok(()) => accepted.push(message),
+                Err(e) => {
+                    warn!("Plugin rejected message on channel {}: {}", self.name, e);
+                    self.emit_error(ErrorInfo {
+                        code: e.code().map(|code| code.as_u16()).unwrap_or(50000),
+                        message: Some(format!("Plugin rejected message: {}", e)),
+                        ..Default::default()
+                    }).await;
+                }
+            }
+        }
+        
+        accepted
+    }
+    
+    /// Deliver messages in order to every subscriber, pruning unsubscribed ones
+    async fn dispatch_messages(&self, messages: Vec<Message>) {
+        // Snapshot so blocked subscribers don't hold the listener lock
+        let listeners = self.message_listeners.read().await.clone();
+        let mut pruned = false;
+        
+        for msg in &messages {
+            for listener in &listeners {
+                pruned |= !listener.deliver(msg).await;
+            }
+        }
+        
+        if pruned {
+            self.message_listeners.write().await.retain(|listener| listener.is_active());
+        }
+    }
+    
+    /// Deliver annotations in order to every subscriber, pruning unsubscribed ones
+    async fn dispatch_annotations(&self, annotations: Vec<Annotation>) {
+        let listeners = self.annotation_listeners.read().await.clone();
+        let mut pruned = false;
+        
+        for annotation in &annotations {
+            for listener in &listeners {
+                pruned |= !listener.deliver(annotation).await;
+            }
+        }
+        
+        if pruned {
+            self.annotation_listeners.write().await.retain(|listener| listener.is_active());
+        }
     }
     
     /// Handle channel attached
-    async fn handle_attached(&self) {
+    async fn handle_attached(&self, message: &ProtocolMessage) {
         info!("Channel attached: {}", self.name);
+        
+        if let Some(serial) = &message.channel_serial {
+            *self.channel_serial.write().await = Some(serial.clone());
+            *self.attach_serial.write().await = Some(serial.clone());
+        }
+        
+        if let Some(handler) = self.delta_handler.write().await.as_mut() {
+            handler.complete_recovery();
+        }
+        
+        let resumed = message.flags
+            .map(|f| f & flags::RESUMED != 0)
+            .unwrap_or(false);
+        
+        if self.state().await == ChannelState::Attached {
+            // A reattach while attached only matters if continuity was lost (RTL12)
+            if !resumed {
+                warn!("Channel {} reattached without continuity: {:?}", self.name, message.error);
+                self.emit_state_change(ChannelStateChange {
+                    previous: ChannelState::Attached,
+                    current: ChannelState::Attached,
+                    event: ChannelEvent::Update,
+                    reason: message.error.clone(),
+                    resumed,
+                }).await;
+            }
+        } else {
+            self.set_state(ChannelState::Attached, message.error.clone(), resumed).await;
+        }
     }
     
     /// Handle channel detached
     async fn handle_detached(&self) {
         info!("Channel detached: {}", self.name);
+        self.set_state(ChannelState::Detached, None, false).await;
     }
 }
 
@@ -432,6 +1244,7 @@ pub struct RealtimeClientBuilder {
     client_id: Option<String>,
     recover: Option<String>,
     auto_connect: bool,
+    plugins: PluginManager,
 }
 
 impl Default for RealtimeClientBuilder {
@@ -442,6 +1255,7 @@ impl Default for RealtimeClientBuilder {
             client_id: None,
             recover: None,
             auto_connect: true,
+            plugins: PluginManager::new(),
         }
     }
 }
@@ -472,11 +1286,17 @@ impl RealtimeClientBuilder {
         self
     }
     
+    /// Run published, delivered and history messages and received protocol frames through the given plugins
+    pub fn plugins(mut self, plugins: PluginManager) -> Self {
+        self.plugins = plugins;
+        self
+    }
+    
     pub async fn build(self) -> AblyResult<RealtimeClient> {
         let api_key = self.api_key
             .ok_or_else(|| AblyError::unexpected("API key required"))?;
         
-        let client = RealtimeClient::new(api_key).await?;
+        let client = RealtimeClient::with_plugins(api_key, self.plugins).await?;
         
         if self.auto_connect {
             client.connect().await?;
@@ -484,4 +1304,664 @@ impl RealtimeClientBuilder {
         
         Ok(client)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::subscription::MessageEvent;
+    use crate::crypto::MessageCrypto;
+    use crate::plugin::{Plugin, PluginConfig};
+    use crate::protocol::annotations::AnnotationSummary;
+    use crate::protocol::messages::MessageAction;
+    use futures_util::StreamExt;
+    use std::sync::Mutex;
+    
+    /// Records the order it sees messages in and rejects inbound messages named "blocked"
+    struct RecordingPlugin {
+        name: &'static str,
+        log: Arc<Mutex<Vec<String>>>,
+    }
+    
+    #[async_trait::async_trait]
+    impl Plugin for RecordingPlugin {
+        fn name(&self) -> &str {
+            self.name
+        }
+        
+        fn version(&self) -> &str {
+            "1.0.0"
+        }
+        
+        async fn initialize(&mut self, _config: PluginConfig) -> AblyResult<()> {
+            Ok(())
+        }
+        
+        async fn shutdown(&mut self) -> AblyResult<()> {
+            Ok(())
+        }
+        
+        async fn process_inbound(&self, message: &mut Message) -> AblyResult<()> {
+            self.log.lock().unwrap().push(format!("in:{}", self.name));
+            if message.name.as_deref() == Some("blocked") {
+                return Err(AblyError::Forbidden { message: "blocked".to_string() });
+            }
+            Ok(())
+        }
+        
+        async fn process_outbound(&self, _message: &mut Message) -> AblyResult<()> {
+            self.log.lock().unwrap().push(format!("out:{}", self.name));
+            Ok(())
+        }
+        
+        fn as_any(&self) -> &dyn std::any::Any {
+            self
+        }
+    }
+    
+    async fn test_channel() -> RealtimeChannel {
+        let client = RealtimeClient::new("app.key:secret").await.unwrap();
+        client.channel("continuity").await
+    }
+    
+    fn attached(channel_serial: &str, flags: Option<u32>, error: Option<ErrorInfo>) -> ProtocolMessage {
+        ProtocolMessage {
+            action: Action::Attached,
+            channel: Some("continuity".to_string()),
+            channel_serial: Some(channel_serial.to_string()),
+            flags,
+            error,
+            ..Default::default()
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_channel_serial_tracking() {
+        let channel = test_channel().await;
+        assert!(channel.channel_serial().await.is_none());
+        
+        channel.handle_attached(&attached("serial-1", None, None)).await;
+        assert_eq!(channel.state().await, ChannelState::Attached);
+        assert_eq!(channel.channel_serial().await.as_deref(), Some("serial-1"));
+        
+        channel.handle_message(ProtocolMessage {
+            action: Action::Message,
+            channel: Some("continuity".to_string()),
+            channel_serial: Some("serial-2".to_string()),
+            messages: Some(vec![Message::default()]),
+            ..Default::default()
+        }).await;
+        assert_eq!(channel.channel_serial().await.as_deref(), Some("serial-2"));
+        
+        channel.handle_detached().await;
+        assert!(channel.channel_serial().await.is_none());
+    }
+    
+    #[tokio::test]
+    async fn test_blocked_subscriber_only_holds_back_its_channel() {
+        use crate::client::subscription::OverflowPolicy;
+        use std::time::Duration;
+        
+        let client = RealtimeClient::new("app.key:secret").await.unwrap();
+        let slow = client.channel("slow").await;
+        let mut stalled = slow.subscribe_with_options(
+            SubscribeOptions::new().buffer(1).overflow(OverflowPolicy::Block),
+        ).await;
+        let mut slow_changes = Box::pin(slow.state_changes().await);
+        let mut fast = client.channel("fast").await.subscribe().await;
+        
+        let frame = |channel: &str, action: Action, serial: &str| ProtocolMessage {
+            action,
+            channel: Some(channel.to_string()),
+            channel_serial: Some(serial.to_string()),
+            messages: Some(vec![Message { name: Some(serial.to_string()), ..Default::default() }]),
+            ..Default::default()
+        };
+        async fn within<F: std::future::Future>(future: F) -> F::Output {
+            tokio::time::timeout(Duration::from_secs(1), future).await.expect("held back by a blocked subscriber")
+        }
+        for serial in ["m1", "m2", "m3"] {
+            within(route_channel_frame(&client.channels, frame("slow", Action::Message, serial))).await;
+        }
+        within(route_channel_frame(&client.channels, frame("slow", Action::Attached, "a1"))).await;
+        
+        // Another channel is delivered while "slow" waits on its subscriber
+        within(route_channel_frame(&client.channels, frame("fast", Action::Message, "f1"))).await;
+        let received = within(fast.recv()).await.unwrap();
+        assert_eq!(received.name.as_deref(), Some("f1"));
+        within(client.channel("opened-later")).await;
+        
+        // ATTACHED waits behind the messages that arrived before it
+        assert_eq!(slow.state().await, ChannelState::Initialized);
+        for serial in ["m1", "m2", "m3"] {
+            let received = within(stalled.recv()).await.unwrap();
+            assert_eq!(received.name.as_deref(), Some(serial));
+        }
+        let change = within(slow_changes.next()).await.unwrap();
+        assert_eq!(change.current, ChannelState::Attached);
+        assert_eq!(slow.channel_serial().await.as_deref(), Some("a1"));
+    }
+    
+    #[tokio::test]
+    async fn test_blocked_subscriber_cannot_grow_inbound_queue() {
+        use crate::client::subscription::OverflowPolicy;
+        
+        let client = RealtimeClient::new("app.key:secret").await.unwrap();
+        let channel = client.channel("slow").await;
+        let _stalled = channel.subscribe_with_options(
+            SubscribeOptions::new().buffer(1).overflow(OverflowPolicy::Block),
+        ).await;
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let errors_clone = errors.clone();
+        channel.on_error(move |error| errors_clone.lock().unwrap().push(error)).await;
+        
+        let overflow = 10;
+        for _ in 0..INBOUND_QUEUE_CAPACITY + overflow {
+            route_channel_frame(&client.channels, ProtocolMessage {
+                action: Action::Message,
+                channel: Some("slow".to_string()),
+                messages: Some(vec![Message::default()]),
+                ..Default::default()
+            }).await;
+            tokio::task::yield_now().await;
+        }
+        
+        // The delivery task holds at most two frames: one buffered, one blocked on the subscriber
+        assert_eq!(channel.inbound.capacity(), 0);
+        let errors = errors.lock().unwrap();
+        assert!((overflow - 2..=overflow).contains(&errors.len()));
+        assert!(errors.iter().all(|error| error.code == 50001));
+    }
+    
+    #[tokio::test]
+    async fn test_detached_clears_serial_after_earlier_messages() {
+        let client = RealtimeClient::new("app.key:secret").await.unwrap();
+        let channel = client.channel("continuity").await;
+        let mut changes = Box::pin(channel.state_changes().await);
+        
+        route_channel_frame(&client.channels, attached("serial-1", None, None)).await;
+        route_channel_frame(&client.channels, ProtocolMessage {
+            action: Action::Message,
+            channel: Some("continuity".to_string()),
+            channel_serial: Some("serial-2".to_string()),
+            messages: Some(vec![Message::default()]),
+            ..Default::default()
+        }).await;
+        route_channel_frame(&client.channels, ProtocolMessage {
+            action: Action::Detached,
+            channel: Some("continuity".to_string()),
+            ..Default::default()
+        }).await;
+        
+        assert_eq!(changes.next().await.unwrap().current, ChannelState::Attached);
+        assert_eq!(changes.next().await.unwrap().current, ChannelState::Detached);
+        assert!(channel.channel_serial().await.is_none());
+    }
+    
+    #[tokio::test]
+    async fn test_delta_decode_failure_reattaches_with_40018() {
+        use base64::{Engine, engine::general_purpose::STANDARD};
+        
+        let channel = test_channel().await;
+        channel.set_options(RealtimeChannelOptions::new().with_vcdiff_delta()).await.unwrap();
+        let mut changes = Box::pin(channel.state_changes().await);
+        
+        channel.handle_message(attached("serial-1", None, None)).await;
+        let mut extras = HashMap::new();
+        extras.insert("delta".to_string(), serde_json::json!({"from": "missing:0", "format": "vcdiff"}));
+        channel.handle_message(ProtocolMessage {
+            action: Action::Message,
+            channel: Some("continuity".to_string()),
+            channel_serial: Some("serial-2".to_string()),
+            messages: Some(vec![Message {
+                data: Some(STANDARD.encode("delta").into()),
+                encoding: Some("utf-8/vcdiff/base64".to_string()),
+                extras: Some(extras),
+                ..Default::default()
+            }]),
+            ..Default::default()
+        }).await;
+        
+        assert_eq!(changes.next().await.unwrap().current, ChannelState::Attached);
+        let attaching = changes.next().await.unwrap();
+        assert_eq!(attaching.previous, ChannelState::Attached);
+        assert_eq!(attaching.current, ChannelState::Attaching);
+        assert_eq!(attaching.reason.map(|r| r.code), Some(40018));
+        
+        channel.handle_message(attached("serial-3", None, None)).await;
+        assert_eq!(changes.next().await.unwrap().current, ChannelState::Attached);
+        assert_eq!(channel.delta_stats().await.unwrap().recovery_attempts, 1);
+    }
+    
+    #[tokio::test]
+    async fn test_history_until_attach_requires_attached_channel() {
+        let channel = test_channel().await;
+        let params = RealtimeHistoryParams {
+            until_attach: true,
+            ..Default::default()
+        };
+        
+        let result = channel.history(params).await;
+        assert!(matches!(result, Err(AblyError::BadRequest { .. })));
+    }
+    
+    #[tokio::test]
+    async fn test_update_event_on_discontinuity() {
+        let channel = test_channel().await;
+        let changes = Arc::new(Mutex::new(Vec::new()));
+        let changes_clone = changes.clone();
+        channel.on_state_change(move |change| {
+            changes_clone.lock().unwrap().push(change);
+        }).await;
+        
+        channel.handle_attached(&attached("serial-1", None, None)).await;
+        channel.handle_attached(&attached("serial-2", Some(flags::RESUMED), None)).await;
+        
+        let reason = ErrorInfo {
+            code: 50000,
+            message: Some("Unable to recover channel".to_string()),
+            ..Default::default()
+        };
+        channel.handle_attached(&attached("serial-3", None, Some(reason))).await;
+        
+        let changes = changes.lock().unwrap();
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].event, ChannelEvent::Attached);
+        assert_eq!(changes[1].event, ChannelEvent::Update);
+        assert!(!changes[1].resumed);
+        assert_eq!(changes[1].reason.as_ref().map(|r| r.code), Some(50000));
+    }
+    
+    #[tokio::test]
+    async fn test_channel_once_and_state_stream() {
+        let channel = test_channel().await;
+        let attached_count = Arc::new(Mutex::new(0));
+        let count_clone = attached_count.clone();
+        channel.once(ChannelEvent::Attached, move |_| {
+            *count_clone.lock().unwrap() += 1;
+        }).await;
+        let mut changes = Box::pin(channel.state_changes().await);
+        
+        channel.attach().await.ok();
+        channel.handle_attached(&attached("serial-1", None, None)).await;
+        channel.handle_detached().await;
+        channel.handle_attached(&attached("serial-2", None, None)).await;
+        
+        assert_eq!(*attached_count.lock().unwrap(), 1);
+        let events: Vec<ChannelEvent> = changes.by_ref().take(4).map(|c| c.event).collect().await;
+        assert_eq!(events, vec![
+            ChannelEvent::Attaching,
+            ChannelEvent::Attached,
+            ChannelEvent::Detached,
+            ChannelEvent::Attached,
+        ]);
+    }
+    
+    #[tokio::test]
+    async fn test_connection_state_stream() {
+        let client = RealtimeClient::new("app.key:secret").await.unwrap();
+        let connection = client.connection();
+        let mut changes = Box::pin(connection.state_changes().await);
+        
+        client.state_machine.send_event(ConnectionEvent::Connect).await.unwrap();
+        client.state_machine.send_event(ConnectionEvent::Disconnected(Some(ErrorInfo {
+            code: 50003,
+            ..Default::default()
+        }))).await.unwrap();
+        
+        let connecting = changes.next().await.unwrap();
+        assert_eq!(connecting.previous, ConnectionState::Initialized);
+        assert_eq!(connecting.current, ConnectionState::Connecting);
+        
+        let disconnected = changes.next().await.unwrap();
+        assert_eq!(disconnected.current, ConnectionState::Disconnected);
+        assert_eq!(disconnected.reason.map(|r| r.code), Some(50003));
+    }
+    
+    #[tokio::test]
+    async fn test_server_disconnect_reason_reaches_listeners() {
+        let client = RealtimeClient::new("app.key:secret").await.unwrap();
+        let mut changes = Box::pin(client.connection().state_changes().await);
+        
+        client.state_machine.send_event(ConnectionEvent::Connect).await.unwrap();
+        route_frame(&client.state_machine, &client.channels, ProtocolMessage {
+            action: Action::Connected,
+            connection_id: Some("connection-1".to_string()),
+            ..Default::default()
+        }).await;
+        route_frame(&client.state_machine, &client.channels, ProtocolMessage {
+            action: Action::Disconnected,
+            error: Some(ErrorInfo {
+                code: 50003,
+                message: Some("Timeout waiting for activity".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }).await;
+        
+        assert_eq!(changes.next().await.unwrap().current, ConnectionState::Connecting);
+        assert_eq!(changes.next().await.unwrap().current, ConnectionState::Connected);
+        let disconnected = changes.next().await.unwrap();
+        assert_eq!(disconnected.current, ConnectionState::Disconnected);
+        assert_eq!(disconnected.reason.map(|r| r.code), Some(50003));
+    }
+    
+    #[tokio::test]
+    async fn test_subscriptions_receive_in_order_and_unsubscribe() {
+        let channel = test_channel().await;
+        let mut all = channel.subscribe().await;
+        let mut named = channel.subscribe_names(&["b"]).await;
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let received_clone = received.clone();
+        let handle = channel.subscribe_with_handler(move |msg| {
+            received_clone.lock().unwrap().push(msg.name.unwrap());
+        }).await;
+        
+        let frame = |names: &[&str]| ProtocolMessage {
+            action: Action::Message,
+            channel: Some("continuity".to_string()),
+            messages: Some(names.iter().map(|name| Message {
+                name: Some(name.to_string()),
+                ..Default::default()
+            }).collect()),
+            ..Default::default()
+        };
+        
+        channel.handle_message(frame(&["a", "b", "c"])).await;
+        handle.unsubscribe();
+        channel.handle_message(frame(&["b"])).await;
+        
+        let names: Vec<String> = all.by_ref().take(4).map(|m| m.name.unwrap()).collect().await;
+        assert_eq!(names, vec!["a", "b", "c", "b"]);
+        assert_eq!(named.recv().await.and_then(|m| m.name).as_deref(), Some("b"));
+        assert_eq!(named.recv().await.and_then(|m| m.name).as_deref(), Some("b"));
+        assert_eq!(*received.lock().unwrap(), vec!["a", "b", "c"]);
+        assert_eq!(channel.message_listeners.read().await.len(), 2);
+    }
+    
+    #[tokio::test]
+    async fn test_message_events_typed_by_action() {
+        let channel = test_channel().await;
+        let mut events = channel.subscribe_message_events().await;
+        
+        let messages = [None, Some(MessageAction::Update), Some(MessageAction::Delete)]
+            .into_iter()
+            .map(|action| Message { serial: Some("01:0".to_string()), action, ..Default::default() })
+            .collect();
+        channel.handle_message(ProtocolMessage {
+            action: Action::Message,
+            channel: Some("continuity".to_string()),
+            messages: Some(messages),
+            ..Default::default()
+        }).await;
+        
+        assert!(matches!(events.recv().await, Some(MessageEvent::Created(_))));
+        assert!(matches!(events.recv().await, Some(MessageEvent::Updated(_))));
+        let deleted = events.recv().await.unwrap();
+        assert!(matches!(deleted, MessageEvent::Deleted(_)));
+        assert_eq!(deleted.message().serial.as_deref(), Some("01:0"));
+    }
+    
+    #[tokio::test]
+    async fn test_annotation_subscriptions_and_summaries() {
+        let channel = test_channel().await;
+        let mut all = Box::pin(channel.annotations().subscribe().await);
+        let mut flags = Box::pin(channel.annotations().subscribe_type("flag:flag.v1").await);
+        let mut summaries = channel.subscribe_message_events().await;
+        
+        let annotations = ["reaction:distinct.v1", "flag:flag.v1"]
+            .into_iter()
+            .map(|annotation_type| Annotation { message_serial: Some("01:0".to_string()), ..Annotation::new(annotation_type) })
+            .collect();
+        channel.handle_message(ProtocolMessage {
+            action: Action::Annotation,
+            channel: Some("continuity".to_string()),
+            annotations: Some(annotations),
+            ..Default::default()
+        }).await;
+        let summary: Message = serde_json::from_value(serde_json::json!({
+            "serial": "01:0",
+            "action": 4,
+            "annotations": {"summary": {"reaction:distinct.v1": {"👍": {"total": 1, "clientIds": ["ana"]}}}}
+        })).unwrap();
+        channel.handle_message(ProtocolMessage {
+            action: Action::Message,
+            channel: Some("continuity".to_string()),
+            messages: Some(vec![summary]),
+            ..Default::default()
+        }).await;
+        
+        assert_eq!(all.next().await.unwrap().annotation_type.as_deref(), Some("reaction:distinct.v1"));
+        assert_eq!(all.next().await.unwrap().annotation_type.as_deref(), Some("flag:flag.v1"));
+        assert_eq!(flags.next().await.unwrap().annotation_type.as_deref(), Some("flag:flag.v1"));
+        
+        let MessageEvent::Summary(message) = summaries.recv().await.unwrap() else {
+            panic!("expected a summary event");
+        };
+        match &message.annotations.unwrap().summaries()["reaction:distinct.v1"] {
+            AnnotationSummary::Distinct(names) => assert_eq!(names["👍"].client_ids, vec!["ana"]),
+            other => panic!("unexpected summary {:?}", other),
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_annotation_subscriptions_are_bounded() {
+        use crate::client::subscription::OverflowPolicy;
+        
+        let channel = test_channel().await;
+        let options = SubscribeOptions::new().buffer(2).overflow(OverflowPolicy::DropOldest);
+        let mut reactions = channel.annotations().subscribe_with_options(options).await;
+        
+        let annotations = ["a", "b", "c", "d", "e"]
+            .into_iter()
+            .map(|name| Annotation::new("reaction:distinct.v1").name(name))
+            .collect();
+        channel.handle_message(ProtocolMessage {
+            action: Action::Annotation,
+            channel: Some("continuity".to_string()),
+            annotations: Some(annotations),
+            ..Default::default()
+        }).await;
+        
+        reactions.unsubscribe();
+        let received: Vec<_> = reactions.map(|annotation| annotation.name.unwrap()).collect().await;
+        assert_eq!(received, vec!["d", "e"]);
+    }
+    
+    #[tokio::test]
+    async fn test_encrypted_messages_decrypted_or_flagged() {
+        let channel = test_channel().await;
+        let params = CipherParams::aes256_cbc(vec![1u8; 32]).unwrap();
+        channel.set_options(RealtimeChannelOptions::new().with_cipher(params.clone())).await.unwrap();
+        
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let errors_clone = errors.clone();
+        channel.on_error(move |error| errors_clone.lock().unwrap().push(error.code)).await;
+        let mut subscription = channel.subscribe().await;
+        
+        let mut encrypted = Message {
+            data: Some(serde_json::json!({ "secret": true }).into()),
+            ..Default::default()
+        };
+        MessageCrypto::new(params).encrypt_message(&mut encrypted).unwrap();
+        let mut foreign = Message {
+            data: Some("other".into()),
+            ..Default::default()
+        };
+        MessageCrypto::new(CipherParams::aes256_cbc(vec![2u8; 32]).unwrap())
+            .encrypt_message(&mut foreign).unwrap();
+        
+        channel.handle_message(ProtocolMessage {
+            action: Action::Message,
+            channel: Some("continuity".to_string()),
+            messages: Some(vec![encrypted, foreign]),
+            ..Default::default()
+        }).await;
+        
+        let decrypted = subscription.recv().await.unwrap();
+        assert_eq!(decrypted.data, Some(serde_json::json!({ "secret": true }).into()));
+        assert!(decrypted.encoding.is_none());
+        
+        let undecryptable = subscription.recv().await.unwrap();
+        assert_eq!(undecryptable.encoding.as_deref(), Some("utf-8/cipher+aes-256-cbc/base64"));
+        assert_eq!(*errors.lock().unwrap(), vec![40013]);
+    }
+    
+    #[tokio::test]
+    async fn test_cipher_keys_rotated_on_live_channel() {
+        let channel = test_channel().await;
+        let keys = CipherKeySet::new("k1", CipherParams::aes256_cbc(vec![1u8; 32]).unwrap());
+        channel.set_options(RealtimeChannelOptions::new().with_cipher_keys(keys.clone())).await.unwrap();
+        let mut subscription = channel.subscribe().await;
+        
+        let sender = CipherKeySet::new("k1", CipherParams::aes256_cbc(vec![1u8; 32]).unwrap());
+        let mut before = Message {
+            data: Some("before".into()),
+            ..Default::default()
+        };
+        sender.encode(&mut before, EncodingFormat::Json).unwrap();
+        
+        sender.rotate("k2", CipherParams::aes256_cbc(vec![2u8; 32]).unwrap());
+        let mut after = Message {
+            data: Some("after".into()),
+            ..Default::default()
+        };
+        sender.encode(&mut after, EncodingFormat::Json).unwrap();
+        
+        // The channel learns the new key at runtime through the shared key set
+        keys.rotate("k2", CipherParams::aes256_cbc(vec![2u8; 32]).unwrap());
+        channel.handle_message(ProtocolMessage {
+            action: Action::Message,
+            channel: Some("continuity".to_string()),
+            messages: Some(vec![before, after]),
+            ..Default::default()
+        }).await;
+        
+        for expected in ["before", "after"] {
+            let message = subscription.recv().await.unwrap();
+            assert_eq!(message.data.as_ref().and_then(MessageData::as_str), Some(expected));
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_plugins_run_in_order_and_reject_messages() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let plugins = PluginManager::new();
+        for name in ["first", "second"] {
+            plugins.register(Box::new(RecordingPlugin { name, log: log.clone() })).await.unwrap();
+        }
+        let client = RealtimeClient::with_plugins("app.key:secret".to_string(), plugins).await.unwrap();
+        let channel = client.channel("continuity").await;
+        
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let errors_clone = errors.clone();
+        channel.on_error(move |error| errors_clone.lock().unwrap().push(error.code)).await;
+        let mut subscription = channel.subscribe().await;
+        
+        // Outbound plugins run before the transport is touched
+        channel.publish(Message::default()).await.ok();
+        assert_eq!(*log.lock().unwrap(), vec!["out:first", "out:second"]);
+        log.lock().unwrap().clear();
+        
+        channel.handle_message(ProtocolMessage {
+            action: Action::Message,
+            channel: Some("continuity".to_string()),
+            messages: Some(["blocked", "allowed"].iter().map(|name| Message {
+                name: Some(name.to_string()),
+                ..Default::default()
+            }).collect()),
+            ..Default::default()
+        }).await;
+        
+        assert_eq!(subscription.recv().await.and_then(|m| m.name).as_deref(), Some("allowed"));
+        assert_eq!(*log.lock().unwrap(), vec!["in:second", "in:second", "in:first"]);
+        assert_eq!(*errors.lock().unwrap(), vec![40300]);
+    }
+    
+    #[tokio::test]
+    async fn test_typed_subscription_survives_decode_failures() {
+        let channel = test_channel().await;
+        let mut typed = channel.subscribe_typed::<HashMap<String, u32>>().await;
+        
+        let data = [
+            serde_json::json!({ "count": 1 }),
+            serde_json::json!("not a map"),
+            serde_json::json!({ "count": 2 }),
+        ];
+        channel.handle_message(ProtocolMessage {
+            action: Action::Message,
+            channel: Some("continuity".to_string()),
+            messages: Some(data.into_iter().map(|data| Message {
+                name: Some("tick".to_string()),
+                data: Some(data.into()),
+                ..Default::default()
+            }).collect()),
+            ..Default::default()
+        }).await;
+        
+        let first = typed.recv().await.unwrap().unwrap();
+        assert_eq!(first.data["count"], 1);
+        assert_eq!(first.name.as_deref(), Some("tick"));
+        assert!(matches!(typed.recv().await, Some(Err(AblyError::Decode { .. }))));
+        assert_eq!(typed.recv().await.unwrap().unwrap().data["count"], 2);
+    }
+    
+    #[derive(Debug, PartialEq)]
+    enum Presence {
+        Joined(String),
+        Left,
+    }
+    
+    impl AblyEvent for Presence {
+        const NAMES: &'static [&'static str] = &["joined", "left"];
+        
+        fn event_name(&self) -> &'static str {
+            match self {
+                Presence::Joined(_) => "joined",
+                Presence::Left => "left",
+            }
+        }
+        
+        fn to_message(&self) -> AblyResult<Message> {
+            let data = match self {
+                Presence::Joined(who) => Some(who.as_str().into()),
+                Presence::Left => None,
+            };
+            Ok(Message { name: Some(self.event_name().to_string()), data, ..Default::default() })
+        }
+        
+        fn from_message(message: &Message) -> AblyResult<Option<Self>> {
+            Ok(match message.name.as_deref() {
+                Some("joined") => Some(Presence::Joined(
+                    message.data.as_ref().and_then(MessageData::as_str)
+                        .ok_or_else(|| AblyError::decode("expected a name"))?.to_string(),
+                )),
+                Some("left") => Some(Presence::Left),
+                _ => None,
+            })
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_event_subscription_matches_variants() {
+        let channel = test_channel().await;
+        let mut events = channel.subscribe_events::<Presence>().await;
+        
+        let mut messages: Vec<Message> = [Presence::Joined("ana".to_string()), Presence::Left]
+            .iter()
+            .map(|event| event.to_message().unwrap())
+            .collect();
+        messages.insert(1, Message { name: Some("unrelated".to_string()), ..Default::default() });
+        messages.insert(2, Message { name: Some("joined".to_string()), ..Default::default() });
+        channel.handle_message(ProtocolMessage {
+            action: Action::Message,
+            channel: Some("continuity".to_string()),
+            messages: Some(messages),
+            ..Default::default()
+        }).await;
+        
+        assert_eq!(events.recv().await.unwrap().unwrap().data, Presence::Joined("ana".to_string()));
+        assert!(events.recv().await.unwrap().is_err());
+        assert_eq!(events.recv().await.unwrap().unwrap().data, Presence::Left);
+    }
+}
@@ -0,0 +1,247 @@
+// Generic REST requests for endpoints without a dedicated wrapper
+
+use crate::client::pagination::PageLinks;
+use crate::error::{AblyError, AblyResult};
+use crate::http::{AblyHttpClient, HttpMethod, HttpResponse};
+use serde_json::Value;
+use std::collections::HashMap;
+
+const MSGPACK_CONTENT_TYPE: &str = "application/x-msgpack";
+
+/// Response to `RestClient::request`, one page at a time (HP1)
+///
+/// Unlike the wrapped endpoints, an error status is not returned as `Err`:
+/// check `success` and read `error_code` and `error_message` instead.
+pub struct HttpPaginatedResponse<'a> {
+    pub status_code: u16,
+    /// Whether the status code is 2xx
+    pub success: bool,
+    /// Ably error code from the `X-Ably-Errorcode` header or the error body
+    pub error_code: Option<u32>,
+    pub error_message: Option<String>,
+    /// Response headers keyed by lowercase name
+    pub headers: HashMap<String, String>,
+    /// Decoded body: the elements of an array, or a single object
+    pub items: Vec<Value>,
+    request: HttpRequestSpec<'a>,
+    links: PageLinks,
+}
+
+/// Method, headers and client shared by every page of a request
+#[derive(Clone)]
+struct HttpRequestSpec<'a> {
+    http_client: &'a AblyHttpClient,
+    method: HttpMethod,
+    headers: HashMap<String, String>,
+}
+
+impl<'a> HttpRequestSpec<'a> {
+    async fn send(
+        &self,
+        path: &str,
+        params: &HashMap<String, String>,
+        body: Option<&Value>,
+    ) -> AblyResult<HttpPaginatedResponse<'a>> {
+        let mut request = self.http_client.request(self.method, path).query(params);
+        if let Some(body) = body {
+            request = request.json(body);
+        }
+        for (name, value) in &self.headers {
+            request = request.header(name, value);
+        }
+
+        let response = request.send().await?;
+        HttpPaginatedResponse::from_response(self.clone(), response, path).await
+    }
+}
+
+impl<'a> HttpPaginatedResponse<'a> {
+    /// Send a request through the client's auth, fallback hosts and retries
+    pub(crate) async fn send(
+        http_client: &'a AblyHttpClient,
+        method: HttpMethod,
+        path: &str,
+        params: &HashMap<String, String>,
+        body: Option<&Value>,
+        headers: HashMap<String, String>,
+    ) -> AblyResult<Self> {
+        let request = HttpRequestSpec { http_client, method, headers };
+        request.send(path, params, body).await
+    }
+
+    async fn from_response(request: HttpRequestSpec<'a>, response: HttpResponse, path: &str) -> AblyResult<Self> {
+        let status_code = response.status().as_u16();
+        let links = PageLinks::from_response(&response, path);
+        let headers: HashMap<String, String> = response
+            .headers()
+            .iter()
+            .filter_map(|(name, value)| Some((name.as_str().to_string(), value.to_str().ok()?.to_string())))
+            .collect();
+
+        let msgpack = headers
+            .get("content-type")
+            .is_some_and(|content_type| content_type.starts_with(MSGPACK_CONTENT_TYPE));
+        let bytes = response.bytes().await?;
+        let body: Value = match (bytes.is_empty(), msgpack) {
+            (true, _) => Value::Null,
+            (false, true) => rmp_serde::from_slice(&bytes)
+                .map_err(|e| AblyError::decode(format!("Failed to decode msgpack response: {}", e)))?,
+            (false, false) => serde_json::from_slice(&bytes)
+                .map_err(|e| AblyError::decode(format!("Failed to decode JSON response: {}", e)))?,
+        };
+
+        let success = (200..300).contains(&status_code);
+        let error = body.get("error").filter(|_| !success);
+        let error_code = headers
+            .get("x-ably-errorcode")
+            .and_then(|code| code.parse().ok())
+            .or_else(|| error.and_then(|e| e.get("code")?.as_u64()).map(|code| code as u32));
+        let error_message = headers
+            .get("x-ably-errormessage")
+            .cloned()
+            .or_else(|| error.and_then(|e| e.get("message")?.as_str()).map(str::to_string));
+
+        let items = match body {
+            _ if !success => Vec::new(),
+            Value::Array(items) => items,
+            Value::Null => Vec::new(),
+            item => vec![item],
+        };
+
+        Ok(Self {
+            status_code,
+            success,
+            error_code,
+            error_message,
+            headers,
+            items,
+            request,
+            links,
+        })
+    }
+
+    /// Items on this page
+    pub fn items(&self) -> &[Value] {
+        &self.items
+    }
+
+    /// Query for the first page of the result set
+    pub fn first(&self) -> Option<HttpPaginatedQuery<'a>> {
+        self.query(self.links.first.as_ref())
+    }
+
+    /// Query that fetches this page again
+    pub fn current(&self) -> Option<HttpPaginatedQuery<'a>> {
+        self.query(self.links.current.as_ref())
+    }
+
+    /// Query for the following page, or None on the last page
+    pub fn next(&self) -> Option<HttpPaginatedQuery<'a>> {
+        self.query(self.links.next.as_ref())
+    }
+
+    pub fn has_next(&self) -> bool {
+        self.links.next.is_some()
+    }
+
+    /// Whether this is the last page of the result set
+    pub fn is_last(&self) -> bool {
+        !self.has_next()
+    }
+
+    fn query(&self, url: Option<&String>) -> Option<HttpPaginatedQuery<'a>> {
+        url.map(|url| HttpPaginatedQuery {
+            url: url.clone(),
+            request: self.request.clone(),
+        })
+    }
+}
+
+/// Request for a page linked from an `HttpPaginatedResponse`
+pub struct HttpPaginatedQuery<'a> {
+    url: String,
+    request: HttpRequestSpec<'a>,
+}
+
+impl<'a> HttpPaginatedQuery<'a> {
+    pub async fn execute(&self) -> AblyResult<HttpPaginatedResponse<'a>> {
+        self.request.send(&self.url, &HashMap::new(), None).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::mock::{MockResponse, MockServer};
+    use crate::http::HttpConfig;
+    use serde_json::json;
+
+    fn client(server: &MockServer) -> AblyHttpClient {
+        let config = HttpConfig::builder().base_url(server.url()).build();
+        AblyHttpClient::from_config(config)
+    }
+
+    #[tokio::test]
+    async fn test_json_pages_follow_links() {
+        let server = MockServer::start(|request| match request.query("page") {
+            None => MockResponse::json(200, &json!([{"id": 1}, {"id": 2}]))
+                .header("link", "<./things?page=2>; rel=\"next\"")
+                .header("x-custom", &request.headers["x-custom"]),
+            Some(_) => MockResponse::json(200, &json!({"id": 3})).header("x-custom", &request.headers["x-custom"]),
+        })
+        .await;
+        let client = client(&server);
+
+        let headers = HashMap::from([("X-Custom".to_string(), "yes".to_string())]);
+        let page = HttpPaginatedResponse::send(&client, HttpMethod::Get, "/things", &HashMap::new(), None, headers)
+            .await
+            .unwrap();
+        assert!(page.success);
+        assert_eq!(page.status_code, 200);
+        assert_eq!(page.items().len(), 2);
+        assert_eq!(page.headers["x-custom"], "yes");
+
+        let next = page.next().unwrap().execute().await.unwrap();
+        assert_eq!(next.items, vec![json!({"id": 3})]);
+        assert_eq!(next.headers["x-custom"], "yes");
+        assert!(next.is_last());
+        assert!(next.next().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_msgpack_body_and_posted_json() {
+        let server = MockServer::start(|request| {
+            let posted: Value = serde_json::from_slice(&request.body).unwrap();
+            let body = rmp_serde::to_vec_named(&json!([{"method": request.method, "posted": posted}])).unwrap();
+            MockResponse::bytes(201, MSGPACK_CONTENT_TYPE, body)
+        })
+        .await;
+        let client = client(&server);
+
+        let body = json!({"name": "widget"});
+        let page = HttpPaginatedResponse::send(&client, HttpMethod::Post, "/things", &HashMap::new(), Some(&body), HashMap::new())
+            .await
+            .unwrap();
+        assert!(page.success);
+        assert_eq!(page.items, vec![json!({"method": "POST", "posted": {"name": "widget"}})]);
+    }
+
+    #[tokio::test]
+    async fn test_error_status_is_reported_not_returned() {
+        let server = MockServer::start(|_| {
+            MockResponse::json(404, &json!({"error": {"code": 40400, "message": "Not found"}}))
+                .header("x-ably-errorcode", "40400")
+        })
+        .await;
+        let client = client(&server);
+
+        let page = HttpPaginatedResponse::send(&client, HttpMethod::Get, "/missing", &HashMap::new(), None, HashMap::new())
+            .await
+            .unwrap();
+        assert!(!page.success);
+        assert_eq!(page.status_code, 404);
+        assert_eq!(page.error_code, Some(40400));
+        assert_eq!(page.error_message.as_deref(), Some("Not found"));
+        assert!(page.items.is_empty());
+    }
+}
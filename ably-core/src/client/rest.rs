@@ -2,20 +2,35 @@
 // Supports all major Ably REST API endpoints
 
 use crate::auth::{AuthMode, TokenDetails, TokenRequest};
+use crate::client::event::AblyEvent;
+use crate::client::annotations::RestAnnotations;
+use crate::client::batch::{BatchPresenceSuccess, BatchPublishSpec, BatchPublishSuccess, BatchResult};
+use crate::client::pagination::PageHook;
+use crate::client::request::HttpPaginatedResponse;
+use crate::client::typed::typed_message;
+use crate::crypto::key_set::{decode_payload, encode_payload};
+use crate::crypto::{CipherKeySet, CipherParams};
 use crate::error::{AblyError, AblyResult};
-use crate::http::{AblyHttpClient, HttpConfig};
-use crate::protocol::messages::{Message, PresenceMessage};
+use crate::http::{default_fallback_hosts, AblyHttpClient, HttpConfig, HttpMethod, HttpResponse};
+use crate::plugin::PluginManager;
+use crate::protocol::encoding::EncodingFormat;
+use crate::protocol::encoding::data_encoding::EncodedPayload;
+use crate::protocol::messages::{Message, MessageAction, MessageOperation, PresenceMessage};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::Duration;
 use tracing::{debug, info, warn};
+use base64::Engine;
 use chrono;
 
 /// Main REST client for Ably API
 pub struct RestClient {
     http_client: AblyHttpClient,
     environment: String,
+    plugins: PluginManager,
+    idempotent_rest_publishing: bool,
 }
 
 impl RestClient {
@@ -27,6 +42,8 @@ impl RestClient {
         Self {
             http_client: AblyHttpClient::with_auth(config, auth),
             environment: "production".to_string(),
+            plugins: PluginManager::new(),
+            idempotent_rest_publishing: false,
         }
     }
     
@@ -38,6 +55,8 @@ impl RestClient {
         Self {
             http_client: AblyHttpClient::with_auth(config, auth),
             environment: "production".to_string(),
+            plugins: PluginManager::new(),
+            idempotent_rest_publishing: false,
         }
     }
     
@@ -51,6 +70,17 @@ impl RestClient {
         &self.http_client
     }
     
+    /// Get the plugins messages are run through
+    pub fn plugins(&self) -> &PluginManager {
+        &self.plugins
+    }
+    
+    /// Share a plugin manager with another client
+    pub(crate) fn with_plugins(mut self, plugins: PluginManager) -> Self {
+        self.plugins = plugins;
+        self
+    }
+    
     /// Get server time
     pub async fn time(&self) -> AblyResult<i64> {
         let response = self.http_client
@@ -70,7 +100,7 @@ impl RestClient {
     
     /// Get a channel reference
     pub fn channel(&self, name: impl Into<String>) -> Channel {
-        Channel::new(name.into(), &self.http_client)
+        Channel::new(name.into(), &self.http_client, &self.plugins, self.idempotent_rest_publishing)
     }
     
     /// Get channels metadata
@@ -92,6 +122,74 @@ impl RestClient {
     pub fn batch(&self) -> BatchRequest {
         BatchRequest::new(&self.http_client)
     }
+    
+    /// Publish messages to many channels in one request, with a result per spec (RSC22)
+    ///
+    /// Messages go through the channel-independent plugin hooks, as a spec may
+    /// target several channels. A channel that fails is reported in its
+    /// `BatchResult` rather than failing the whole call.
+    pub async fn batch_publish(&self, mut specs: Vec<BatchPublishSpec>) -> AblyResult<Vec<BatchResult<BatchPublishSuccess>>> {
+        for spec in &mut specs {
+            for message in &mut spec.messages {
+                self.plugins.process_outbound(message).await?;
+                encode_payload(message, None, EncodingFormat::Json)?;
+            }
+            if self.idempotent_rest_publishing {
+                assign_idempotent_ids(&mut spec.messages)?;
+            }
+        }
+        
+        let response = self.http_client
+            .post("/messages")
+            .header("X-Ably-Version", BATCH_PROTOCOL_VERSION)
+            .json(&specs)
+            .send()
+            .await?;
+        match batch_response_body(response).await? {
+            Value::Array(results) => results.into_iter().map(BatchResult::from_value).collect(),
+            other => Ok(vec![BatchResult::from_value(other)?]),
+        }
+    }
+    
+    /// Get the presence members of many channels in one request (RSC24)
+    pub async fn batch_presence(&self, channels: &[&str]) -> AblyResult<BatchResult<BatchPresenceSuccess>> {
+        let response = self.http_client
+            .get("/presence")
+            .header("X-Ably-Version", BATCH_PROTOCOL_VERSION)
+            .query(&[("channels", channels.join(","))])
+            .send()
+            .await?;
+        let mut result: BatchResult<BatchPresenceSuccess> = BatchResult::from_value(batch_response_body(response).await?)?;
+        
+        for channel_result in &mut result.results {
+            if let Ok(success) = &mut channel_result.result {
+                decode_payloads(&mut success.presence, None, &channel_result.channel);
+            }
+        }
+        Ok(result)
+    }
+    
+    /// Call any REST endpoint with the client's auth, fallback hosts and retries (RSC19)
+    ///
+    /// Error statuses are reported on the response rather than as `Err`.
+    pub async fn request(
+        &self,
+        method: HttpMethod,
+        path: &str,
+        params: Option<HashMap<String, String>>,
+        body: Option<Value>,
+        headers: Option<HashMap<String, String>>,
+    ) -> AblyResult<HttpPaginatedResponse<'_>> {
+        HttpPaginatedResponse::send(
+            &self.http_client,
+            method,
+            path,
+            &params.unwrap_or_default(),
+            body.as_ref(),
+            headers.unwrap_or_default(),
+        )
+        .await
+    }
 }
 
 /// Builder for REST client with advanced options
@@ -102,6 +200,11 @@ pub struct RestClientBuilder {
     timeout: Option<Duration>,
     max_retries: u32,
     custom_headers: HashMap<String, String>,
+    plugins: PluginManager,
+    fallback_hosts: Option<Vec<String>>,
+    fallback_retry_timeout: Option<Duration>,
+    max_retry_duration: Option<Duration>,
+    idempotent_rest_publishing: bool,
 }
 
 impl Default for RestClientBuilder {
@@ -113,6 +216,11 @@ impl Default for RestClientBuilder {
             timeout: Some(Duration::from_secs(15)),
             max_retries: 3,
             custom_headers: HashMap::new(),
+            plugins: PluginManager::new(),
+            fallback_hosts: None,
+            fallback_retry_timeout: None,
+            max_retry_duration: None,
+            idempotent_rest_publishing: false,
         }
     }
 }
@@ -148,6 +256,42 @@ impl RestClientBuilder {
         self
     }
     
+    /// Run published and retrieved messages through the given plugins
+    pub fn plugins(mut self, plugins: PluginManager) -> Self {
+        self.plugins = plugins;
+        self
+    }
+    
+    /// Replace the environment's default fallback hosts; an empty list disables fallback
+    pub fn fallback_hosts(mut self, hosts: Vec<String>) -> Self {
+        self.fallback_hosts = Some(hosts);
+        self
+    }
+    
+    /// How long to keep using a fallback host after it succeeds (default 10 minutes)
+    pub fn fallback_retry_timeout(mut self, timeout: Duration) -> Self {
+        self.fallback_retry_timeout = Some(timeout);
+        self
+    }
+    
+    /// Maximum number of fallback hosts tried per request (default 3)
+    pub fn http_max_retry_count(mut self, count: u32) -> Self {
+        self.max_retries = count;
+        self
+    }
+    
+    /// Cumulative time allowed for a request and its retries (default 15 seconds)
+    pub fn http_max_retry_duration(mut self, duration: Duration) -> Self {
+        self.max_retry_duration = Some(duration);
+        self
+    }
+    
+    /// Give published messages ids so a retried publish is not duplicated (TO3n)
+    pub fn idempotent_rest_publishing(mut self, enabled: bool) -> Self {
+        self.idempotent_rest_publishing = enabled;
+        self
+    }
+    
     pub fn build(self) -> RestClient {
         let mut config = HttpConfig::default();
         
@@ -157,11 +301,17 @@ impl RestClientBuilder {
         
         config.max_retries = self.max_retries;
         
-        // Set base URL based on environment
-        config.base_url = match self.environment.as_str() {
-            "sandbox" => "https://sandbox-rest.ably.io".to_string(),
-            "production" | _ => "https://rest.ably.io".to_string(),
-        };
+        // Set base URL and fallback hosts based on environment
+        let (base_url, fallback_hosts) = environment_hosts(&self.environment);
+        config.base_url = base_url;
+        config.fallback_hosts = self.fallback_hosts.unwrap_or(fallback_hosts);
+        
+        if let Some(timeout) = self.fallback_retry_timeout {
+            config.fallback_retry_timeout = timeout;
+        }
+        if let Some(duration) = self.max_retry_duration {
+            config.max_retry_duration = duration;
+        }
         
         let auth = if let Some(key) = self.api_key {
             AuthMode::ApiKey(key)
@@ -181,39 +331,67 @@ impl RestClientBuilder {
         RestClient {
             http_client,
             environment: self.environment,
+            plugins: self.plugins,
+            idempotent_rest_publishing: self.idempotent_rest_publishing,
         }
     }
 }
 
+/// Base URL and default fallback hosts for an environment (RSC15g2)
+fn environment_hosts(environment: &str) -> (String, Vec<String>) {
+    match environment {
+        "production" => ("https://rest.ably.io".to_string(), default_fallback_hosts("production")),
+        env => (format!("https://{env}-rest.ably.io"), default_fallback_hosts(env)),
+    }
+}
+
 /// Channel operations
 pub struct Channel<'a> {
     name: String,
     http_client: &'a AblyHttpClient,
-    cipher: Option<crate::crypto::MessageCrypto>,
+    cipher: Option<CipherKeySet>,
+    plugins: &'a PluginManager,
+    idempotent: bool,
 }
 
 impl<'a> Channel<'a> {
-    fn new(name: String, http_client: &'a AblyHttpClient) -> Self {
+    fn new(name: String, http_client: &'a AblyHttpClient, plugins: &'a PluginManager, idempotent: bool) -> Self {
         Self { 
             name, 
             http_client,
             cipher: None,
+            plugins,
+            idempotent,
         }
     }
     
     /// Add encryption to this channel
-    pub fn with_cipher(mut self, params: crate::crypto::CipherParams) -> Self {
-        self.cipher = Some(crate::crypto::MessageCrypto::new(params));
+    pub fn with_cipher(mut self, params: CipherParams) -> Self {
+        self.cipher = Some(params.into());
+        self
+    }
+    
+    /// Add encryption using a rotatable key set shared with other channels
+    pub fn with_cipher_keys(mut self, keys: CipherKeySet) -> Self {
+        self.cipher = Some(keys);
         self
     }
     
+    /// Get the keys this channel encrypts and decrypts with
+    pub fn cipher_keys(&self) -> Option<&CipherKeySet> {
+        self.cipher.as_ref()
+    }
+    
     /// Publish a single message
     pub async fn publish(&self, mut message: Message) -> AblyResult<()> {
-        // Encrypt message if cipher is configured
-        if let Some(ref cipher) = self.cipher {
-            cipher.encrypt_message(&mut message)?;
+        self.plugins.process_channel_outbound(&self.name, &mut message).await?;
+        if self.idempotent {
+            assign_idempotent_ids(std::slice::from_mut(&mut message))?;
         }
         
+        // Encode payload, encrypting it if a cipher is configured
+        encode_payload(&mut message, self.cipher.as_ref(), EncodingFormat::Json)?;
+        
         let path = format!("/channels/{}/messages", self.name);
         self.http_client
             .post(&path)
@@ -223,13 +401,25 @@ impl<'a> Channel<'a> {
         Ok(())
     }
     
+    /// Publish a named message whose data is serialized from `value`
+    pub async fn publish_typed<T: Serialize + ?Sized>(&self, name: &str, value: &T) -> AblyResult<()> {
+        self.publish(typed_message(name, value)?).await
+    }
+    
+    /// Publish an event as a message named after its variant
+    pub async fn publish_event<E: AblyEvent>(&self, event: E) -> AblyResult<()> {
+        self.publish(event.to_message()?).await
+    }
+    
     /// Publish multiple messages
     pub async fn publish_batch(&self, mut messages: Vec<Message>) -> AblyResult<()> {
-        // Encrypt messages if cipher is configured
-        if let Some(ref cipher) = self.cipher {
-            for message in &mut messages {
-                cipher.encrypt_message(message)?;
-            }
+        // Encode payloads, encrypting them if a cipher is configured
+        for message in &mut messages {
+            self.plugins.process_channel_outbound(&self.name, message).await?;
+            encode_payload(message, self.cipher.as_ref(), EncodingFormat::Json)?;
+        }
+        if self.idempotent {
+            assign_idempotent_ids(&mut messages)?;
         }
         
         let path = format!("/channels/{}/messages", self.name);
@@ -243,12 +433,17 @@ impl<'a> Channel<'a> {
     
     /// Get message history
     pub fn history(&self) -> HistoryQuery<'a> {
-        HistoryQuery::new(&self.name, self.http_client, self.cipher.clone())
+        HistoryQuery::new(&self.name, self.http_client, self.cipher.clone(), self.plugins)
     }
     
     /// Get channel presence
     pub fn presence(&self) -> PresenceOperations<'a> {
-        PresenceOperations::new(&self.name, self.http_client)
+        PresenceOperations::new(&self.name, self.http_client, self.cipher.clone())
+    }
+    
+    /// Get annotations on this channel's messages
+    pub fn annotations(&self) -> RestAnnotations<'a> {
+        RestAnnotations::new(&self.name, self.http_client)
     }
     
     /// Get channel status
@@ -268,21 +463,135 @@ impl<'a> Channel<'a> {
             crate::error::AblyError::unexpected("Channel serial not available")
         })
     }
+    
+    /// Replace the published message with the same serial (RSL15)
+    pub async fn update_message(&self, message: Message, operation: Option<MessageOperation>) -> AblyResult<()> {
+        self.send_operation(message, MessageAction::Update, operation).await
+    }
+    
+    /// Mark the published message with the same serial as deleted (RSL15)
+    pub async fn delete_message(&self, message: Message, operation: Option<MessageOperation>) -> AblyResult<()> {
+        self.send_operation(message, MessageAction::Delete, operation).await
+    }
+    
+    /// Append data to the published message with the same serial
+    pub async fn append_message(&self, message: Message, operation: Option<MessageOperation>) -> AblyResult<()> {
+        self.send_operation(message, MessageAction::Append, operation).await
+    }
+    
+    /// Get the latest version of a message (RSL11)
+    pub async fn get_message(&self, serial: &str) -> AblyResult<Message> {
+        let response = self.http_client
+            .get(&self.message_path(serial))
+            .send()
+            .await?
+            .error_for_status()
+            .await?;
+        let mut message: Message = response.json().await?;
+        
+        decode_payloads(std::slice::from_mut(&mut message), self.cipher.as_ref(), &self.name);
+        self.plugins.process_channel_inbound(&self.name, &mut message).await?;
+        Ok(message)
+    }
+    
+    /// Get every version of a message, oldest first unless a direction is given (RSL14)
+    pub async fn get_message_versions(&self, serial: &str) -> AblyResult<PaginatedResult<'a, Message>> {
+        let path = format!("{}/versions", self.message_path(serial));
+        let hook = message_page_hook(&self.name, self.cipher.clone(), self.plugins.clone());
+        PaginatedResult::fetch(self.http_client, &path, &HashMap::new(), Some(hook)).await
+    }
+    
+    async fn send_operation(
+        &self,
+        mut message: Message,
+        action: MessageAction,
+        operation: Option<MessageOperation>,
+    ) -> AblyResult<()> {
+        let serial = message.serial.clone().ok_or_else(|| {
+            AblyError::invalid_request("A message serial is required to update, delete or append a message")
+        })?;
+        message.action = Some(action);
+        message.operation = operation;
+        
+        self.plugins.process_channel_outbound(&self.name, &mut message).await?;
+        encode_payload(&mut message, self.cipher.as_ref(), EncodingFormat::Json)?;
+        
+        let path = self.message_path(&serial);
+        let (method, path) = match action {
+            MessageAction::Update => (HttpMethod::Patch, path),
+            MessageAction::Delete => (HttpMethod::Post, format!("{}/delete", path)),
+            _ => (HttpMethod::Post, format!("{}/append", path)),
+        };
+        self.http_client
+            .request(method, &path)
+            .json(&message)
+            .send()
+            .await?
+            .error_for_status()
+            .await?;
+        Ok(())
+    }
+    
+    fn message_path(&self, serial: &str) -> String {
+        message_path(&self.name, serial)
+    }
+}
+
+/// Path of a published message, identified by its serial
+pub(crate) fn message_path(channel: &str, serial: &str) -> String {
+    format!("/channels/{}/messages/{}", channel, urlencoding::encode(serial))
+}
+
+/// Give every message in a publish an id, shared by all retries of the request (RSL1k)
+///
+/// Ids are a random base64 prefix plus each message's index in the batch.
+/// Messages that already carry ids are sent as they are, but a batch mixing
+/// supplied and missing ids is rejected.
+fn assign_idempotent_ids(messages: &mut [Message]) -> AblyResult<()> {
+    let supplied = messages.iter().filter(|message| message.id.is_some()).count();
+    if supplied == messages.len() {
+        return Ok(());
+    }
+    if supplied > 0 {
+        return Err(AblyError::invalid_request(
+            "Cannot publish a mix of messages with and without ids when idempotent publishing is enabled",
+        ));
+    }
+    
+    let prefix = base64::engine::general_purpose::STANDARD.encode(rand::random::<[u8; 9]>());
+    for (index, message) in messages.iter_mut().enumerate() {
+        message.id = Some(format!("{}:{}", prefix, index));
+    }
+    Ok(())
+}
+
+/// Protocol version whose batch responses carry success and failure counts
+const BATCH_PROTOCOL_VERSION: &str = "2";
+
+/// Body of a batch response, including partial failures reported with an error status
+async fn batch_response_body(response: HttpResponse) -> AblyResult<Value> {
+    let status = response.status();
+    let mut body: Value = response.json().await?;
+    if status.is_success() {
+        return Ok(body);
+    }
+    
+    match body.get_mut("batchResponse").map(Value::take) {
+        Some(results) => Ok(results),
+        None => {
+            let message = body["error"]["message"].as_str().unwrap_or("Batch request failed");
+            Err(AblyError::api(status.as_u16(), message))
+        }
+    }
 }
 
 /// Channel options for advanced features
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default)]
 pub struct ChannelOptions {
     pub cipher: Option<CipherParams>,
     pub params: Option<HashMap<String, String>>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct CipherParams {
-    pub algorithm: String,
-    pub key: String,
-}
-
 /// Channel status information
 #[derive(Debug, Deserialize)]
 pub struct ChannelStatus {
@@ -324,16 +633,23 @@ pub struct HistoryQuery<'a> {
     channel: String,
     http_client: &'a AblyHttpClient,
     params: HashMap<String, String>,
-    cipher: Option<crate::crypto::MessageCrypto>,
+    cipher: Option<CipherKeySet>,
+    plugins: &'a PluginManager,
 }
 
 impl<'a> HistoryQuery<'a> {
-    fn new(channel: &str, http_client: &'a AblyHttpClient, cipher: Option<crate::crypto::MessageCrypto>) -> Self {
+    fn new(
+        channel: &str,
+        http_client: &'a AblyHttpClient,
+        cipher: Option<CipherKeySet>,
+        plugins: &'a PluginManager,
+    ) -> Self {
         Self {
             channel: channel.to_string(),
             http_client,
             params: HashMap::new(),
             cipher,
+            plugins,
         }
     }
     
@@ -357,27 +673,47 @@ impl<'a> HistoryQuery<'a> {
         self
     }
     
+    /// Only return messages published before the given channel serial
+    pub fn from_serial(mut self, serial: &str) -> Self {
+        self.params.insert("fromSerial".to_string(), serial.to_string());
+        self
+    }
+    
     pub async fn execute(&self) -> AblyResult<PaginatedResult<'a, Message>> {
         let path = format!("/channels/{}/messages", self.channel);
-        let response = self.http_client
-            .get(&path)
-            .query(&self.params)
-            .send()
-            .await?;
-        let mut messages: Vec<Message> = response.json().await?;
-        
-        // Decrypt messages if cipher is configured
-        if let Some(ref cipher) = self.cipher {
+        let hook = message_page_hook(&self.channel, self.cipher.clone(), self.plugins.clone());
+        PaginatedResult::fetch(self.http_client, &path, &self.params, Some(hook)).await
+    }
+    
+    /// Stream every message in the result set, fetching pages as needed
+    pub fn into_stream(self) -> PaginatedStream<'a, Message> {
+        PaginatedStream::new(async move { self.execute().await })
+    }
+}
+
+/// Decode payloads and run inbound plugins on every page of messages
+fn message_page_hook(channel: &str, cipher: Option<CipherKeySet>, plugins: PluginManager) -> PageHook<Message> {
+    let channel = channel.to_string();
+    Arc::new(move |mut messages: Vec<Message>| {
+        let channel = channel.clone();
+        let cipher = cipher.clone();
+        let plugins = plugins.clone();
+        Box::pin(async move {
+            decode_payloads(&mut messages, cipher.as_ref(), &channel);
             for message in &mut messages {
-                cipher.decrypt_message(message)?;
+                plugins.process_channel_inbound(&channel, message).await?;
             }
-        }
-        
-        Ok(PaginatedResult {
-            items: messages,
-            http_client: self.http_client,
-            next_url: None, // TODO: Parse Link header
+            Ok(messages)
         })
+    })
+}
+
+/// Decode received payloads; ones that fail keep their encoding (RSL6b)
+fn decode_payloads<P: EncodedPayload>(items: &mut [P], cipher: Option<&CipherKeySet>, channel: &str) {
+    for item in items.iter_mut() {
+        if let Err(e) = decode_payload(item, cipher) {
+            warn!("Failed to decode message on channel {}: {}", channel, e);
+        }
     }
 }
 
@@ -385,33 +721,47 @@ impl<'a> HistoryQuery<'a> {
 pub struct PresenceOperations<'a> {
     channel: String,
     http_client: &'a AblyHttpClient,
+    cipher: Option<CipherKeySet>,
+    params: HashMap<String, String>,
 }
 
 impl<'a> PresenceOperations<'a> {
-    fn new(channel: &str, http_client: &'a AblyHttpClient) -> Self {
+    fn new(channel: &str, http_client: &'a AblyHttpClient, cipher: Option<CipherKeySet>) -> Self {
         Self {
             channel: channel.to_string(),
             http_client,
+            cipher,
+            params: HashMap::new(),
         }
     }
     
+    /// Maximum number of members per page returned by `get`
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.params.insert("limit".to_string(), limit.to_string());
+        self
+    }
+    
+    /// Only return members with this client id from `get`
+    pub fn client_id(mut self, client_id: &str) -> Self {
+        self.params.insert("clientId".to_string(), client_id.to_string());
+        self
+    }
+    
+    /// Only return members on this connection from `get`
+    pub fn connection_id(mut self, connection_id: &str) -> Self {
+        self.params.insert("connectionId".to_string(), connection_id.to_string());
+        self
+    }
+    
+    /// Get the members currently present, filtered by any of `limit`, `client_id` and `connection_id`
     pub async fn get(&self) -> AblyResult<PaginatedResult<'a, PresenceMessage>> {
         let path = format!("/channels/{}/presence", self.channel);
-        let response = self.http_client
-            .get(&path)
-            .send()
-            .await?;
-        let response: Vec<PresenceMessage> = response.json().await?;
-        
-        Ok(PaginatedResult {
-            items: response,
-            http_client: self.http_client,
-            next_url: None,
-        })
+        let hook = presence_page_hook(&self.channel, self.cipher.clone());
+        PaginatedResult::fetch(self.http_client, &path, &self.params, Some(hook)).await
     }
     
     pub fn history(&self) -> PresenceHistoryQuery<'a> {
-        PresenceHistoryQuery::new(&self.channel, self.http_client)
+        PresenceHistoryQuery::new(&self.channel, self.http_client, self.cipher.clone())
     }
 }
 
@@ -420,14 +770,16 @@ pub struct PresenceHistoryQuery<'a> {
     channel: String,
     http_client: &'a AblyHttpClient,
     params: HashMap<String, String>,
+    cipher: Option<CipherKeySet>,
 }
 
 impl<'a> PresenceHistoryQuery<'a> {
-    fn new(channel: &str, http_client: &'a AblyHttpClient) -> Self {
+    fn new(channel: &str, http_client: &'a AblyHttpClient, cipher: Option<CipherKeySet>) -> Self {
         Self {
             channel: channel.to_string(),
             http_client,
             params: HashMap::new(),
+            cipher,
         }
     }
     
@@ -436,6 +788,11 @@ impl<'a> PresenceHistoryQuery<'a> {
         self
     }
     
+    pub fn direction(mut self, direction: &str) -> Self {
+        self.params.insert("direction".to_string(), direction.to_string());
+        self
+    }
+    
     pub fn start(mut self, start: i64) -> Self {
         self.params.insert("start".to_string(), start.to_string());
         self
@@ -448,19 +805,23 @@ impl<'a> PresenceHistoryQuery<'a> {
     
     pub async fn execute(&self) -> AblyResult<PaginatedResult<'a, PresenceMessage>> {
         let path = format!("/channels/{}/presence/history", self.channel);
-        let response = self.http_client
-            .get(&path)
-            .query(&self.params)
-            .send()
-            .await?;
-        let response: Vec<PresenceMessage> = response.json().await?;
-        
-        Ok(PaginatedResult {
-            items: response,
-            http_client: self.http_client,
-            next_url: None,
-        })
+        let hook = presence_page_hook(&self.channel, self.cipher.clone());
+        PaginatedResult::fetch(self.http_client, &path, &self.params, Some(hook)).await
     }
+    
+    /// Stream every presence event in the result set, fetching pages as needed
+    pub fn into_stream(self) -> PaginatedStream<'a, PresenceMessage> {
+        PaginatedStream::new(async move { self.execute().await })
+    }
+}
+
+/// Decode presence payloads on every page, decrypting them if a cipher is configured
+fn presence_page_hook(channel: &str, cipher: Option<CipherKeySet>) -> PageHook<PresenceMessage> {
+    let channel = channel.to_string();
+    Arc::new(move |mut members: Vec<PresenceMessage>| {
+        decode_payloads(&mut members, cipher.as_ref(), &channel);
+        Box::pin(async move { Ok(members) })
+    })
 }
 
 /// Stats query builder
@@ -487,87 +848,32 @@ impl<'a> StatsQuery<'a> {
         self
     }
     
+    /// Only return intervals starting at or after this time, in milliseconds since the epoch
+    pub fn start(mut self, start: i64) -> Self {
+        self.params.insert("start".to_string(), start.to_string());
+        self
+    }
+    
+    /// Only return intervals starting at or before this time, in milliseconds since the epoch
+    pub fn end(mut self, end: i64) -> Self {
+        self.params.insert("end".to_string(), end.to_string());
+        self
+    }
+    
+    /// Granularity of the returned intervals (default minute)
+    pub fn unit(mut self, unit: StatsUnit) -> Self {
+        self.params.insert("unit".to_string(), unit.to_string());
+        self
+    }
+    
     pub async fn execute(&self) -> AblyResult<PaginatedResult<'a, Stats>> {
-        let response = self.http_client
-            .get("/stats")
-            .query(&self.params)
-            .send()
-            .await?;
-        let response: Vec<Stats> = response.json().await?;
-        
-        Ok(PaginatedResult {
-            items: response,
-            http_client: self.http_client,
-            next_url: None,
-        })
+        PaginatedResult::fetch(self.http_client, "/stats", &self.params, None).await
+    }
+    
+    /// Stream every stats interval in the result set, fetching pages as needed
+    pub fn into_stream(self) -> PaginatedStream<'a, Stats> {
+        PaginatedStream::new(async move { self.execute().await })
     }
-}
-
-/// Statistics data - comprehensive structure matching Ably API
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct Stats {
-    #[serde(default)]
-    pub interval_id: Option<String>,
-    #[serde(default)]
-    pub unit: Option<String>,
-    #[serde(default)]
-    pub processed: Option<bool>,
-    #[serde(default)]
-    pub in_progress: Option<serde_json::Value>,
-    #[serde(default)]
-    pub channels: Option<StatsChannelData>,
-    #[serde(default)]
-    pub api_requests: Option<serde_json::Value>,
-    #[serde(default)]
-    pub all: Option<StatsMessageTypes>,
-    #[serde(default)]
-    pub inbound: Option<StatsMessageTraffic>,
-    #[serde(default)]
-    pub outbound: Option<StatsMessageTraffic>,
-}
-
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct StatsChannelData {
-    pub count: Option<u64>,
-    pub mean_data: Option<f64>,
-    pub mean_rate: Option<f64>,
-}
-
-#[derive(Debug, Deserialize)]
-pub struct StatsMessageTypes {
-    #[serde(default)]
-    pub all: Option<StatsMessageCount>,
-    #[serde(default)]
-    pub messages: Option<StatsMessageCount>,
-    #[serde(default)]
-    pub presence: Option<StatsMessageCount>,
-}
-
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct StatsMessageCount {
-    #[serde(default)]
-    pub count: Option<u64>,
-    #[serde(default)]
-    pub data: Option<u64>,
-    #[serde(default)]
-    pub uncompressed_data: Option<u64>,
-    #[serde(default)]
-    pub billable_count: Option<u64>,
-    #[serde(default)]
-    pub category: Option<serde_json::Value>,
-}
-
-#[derive(Debug, Deserialize)]
-pub struct StatsMessageTraffic {
-    #[serde(default)]
-    pub all: Option<StatsMessageTypes>,
-    #[serde(default)]
-    pub realtime: Option<StatsMessageTypes>,
-    #[serde(default)]
-    pub rest: Option<StatsMessageTypes>,
 }
 
 /// Channels metadata query
@@ -612,13 +918,13 @@ impl<'a> ChannelListQuery<'a> {
         self
     }
     
-    pub async fn execute(&self) -> AblyResult<Vec<ChannelStatus>> {
-        let response = self.http_client
-            .get("/channels")
-            .query(&self.params)
-            .send()
-            .await?;
-        response.json().await
+    pub async fn execute(&self) -> AblyResult<PaginatedResult<'a, ChannelStatus>> {
+        PaginatedResult::fetch(self.http_client, "/channels", &self.params, None).await
+    }
+    
+    /// Stream every channel in the result set, fetching pages as needed
+    pub fn into_stream(self) -> PaginatedStream<'a, ChannelStatus> {
+        PaginatedStream::new(async move { self.execute().await })
     }
 }
 
@@ -815,54 +1121,238 @@ pub struct BatchResponse {
     pub body: Option<Value>,
 }
 
-/// Paginated result with navigation
-pub struct PaginatedResult<'a, T> {
-    pub items: Vec<T>,
-    http_client: &'a AblyHttpClient,
-    next_url: Option<String>,
-}
-
-impl<'a, T: for<'de> Deserialize<'de>> PaginatedResult<'a, T> {
-    pub fn next(&self) -> Option<PaginationQuery<'a, T>> {
-        self.next_url.as_ref().map(|url| {
-            PaginationQuery::new(url.clone(), self.http_client)
-        })
+// Re-export commonly used types
+pub use crate::client::pagination::{PaginatedResult, PaginatedStream, PaginationQuery};
+pub use crate::client::stats::{
+    Stats, StatsConnectionTypes, StatsMessageCount, StatsMessageTraffic, StatsMessageTypes, StatsPush,
+    StatsPushedMessages, StatsRequestCount, StatsResourceCount, StatsUnit, StatsXchgMessages,
+};
+pub use crate::protocol::messages::Message as MessageBuilder;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::mock::{MockResponse, MockServer};
+    use std::sync::Mutex;
+    
+    /// Client talking only to `server`, with default publishing options
+    fn mock_client(server: &MockServer) -> RestClient {
+        let config = HttpConfig::builder().base_url(server.url()).build();
+        RestClient {
+            http_client: AblyHttpClient::from_config(config),
+            environment: "test".to_string(),
+            plugins: PluginManager::new(),
+            idempotent_rest_publishing: false,
+        }
     }
     
-    pub fn has_next(&self) -> bool {
-        self.next_url.is_some()
+    fn idempotent_client(primary: &MockServer, fallback: &MockServer) -> RestClient {
+        let config = HttpConfig::builder()
+            .base_url(primary.url())
+            .fallback_hosts(vec![fallback.host.clone()])
+            .build();
+        RestClient {
+            http_client: AblyHttpClient::from_config(config),
+            environment: "test".to_string(),
+            plugins: PluginManager::new(),
+            idempotent_rest_publishing: true,
+        }
     }
-}
-
-pub struct PaginationQuery<'a, T> {
-    url: String,
-    http_client: &'a AblyHttpClient,
-    _phantom: std::marker::PhantomData<T>,
-}
-
-impl<'a, T: for<'de> Deserialize<'de>> PaginationQuery<'a, T> {
-    fn new(url: String, http_client: &'a AblyHttpClient) -> Self {
-        Self {
-            url,
-            http_client,
-            _phantom: std::marker::PhantomData,
+    
+    fn message(data: &str) -> Message {
+        Message {
+            data: Some(data.into()),
+            ..Default::default()
         }
     }
     
-    pub async fn execute(&self) -> AblyResult<PaginatedResult<'a, T>> {
-        let response = self.http_client
-            .get(&self.url)
-            .send()
-            .await?;
-        let response: Vec<T> = response.json().await?;
+    /// Server that records each posted body and answers with `status`
+    async fn recording_server(status: u16, bodies: Arc<Mutex<Vec<Value>>>) -> MockServer {
+        MockServer::start(move |request| {
+            bodies.lock().unwrap().push(serde_json::from_slice(&request.body).unwrap());
+            MockResponse::json(status, &json!({}))
+        })
+        .await
+    }
+    
+    #[test]
+    fn test_environment_hosts() {
+        let (base_url, fallback_hosts) = environment_hosts("production");
+        assert_eq!(base_url, "https://rest.ably.io");
+        assert_eq!(fallback_hosts, default_fallback_hosts("production"));
+        
+        let (base_url, fallback_hosts) = environment_hosts("acme");
+        assert_eq!(base_url, "https://acme-rest.ably.io");
+        assert_eq!(fallback_hosts, vec![
+            "acme-a-fallback.ably-realtime.com",
+            "acme-b-fallback.ably-realtime.com",
+            "acme-c-fallback.ably-realtime.com",
+            "acme-d-fallback.ably-realtime.com",
+            "acme-e-fallback.ably-realtime.com",
+        ]);
+    }
+    
+    #[tokio::test]
+    async fn test_ids_stable_across_fallback_retries() {
+        let bodies = Arc::new(Mutex::new(Vec::new()));
+        let primary = recording_server(500, bodies.clone()).await;
+        let fallback = recording_server(201, bodies.clone()).await;
+        let client = idempotent_client(&primary, &fallback);
+        
+        client.channel("orders").publish_batch(vec![message("a"), message("b")]).await.unwrap();
+        
+        let bodies = bodies.lock().unwrap();
+        assert_eq!(bodies.len(), 2);
+        assert_eq!(bodies[0], bodies[1]);
+        let ids: Vec<&str> = bodies[0].as_array().unwrap().iter().map(|m| m["id"].as_str().unwrap()).collect();
+        let (prefix, index) = ids[0].split_once(':').unwrap();
+        assert_eq!(index, "0");
+        assert_eq!(prefix.len(), 12);
+        assert_eq!(ids[1], format!("{}:1", prefix));
+    }
+    
+    #[tokio::test]
+    async fn test_batch_publish_partial_failure() {
+        let server = MockServer::start(|request| {
+            assert_eq!(request.headers["x-ably-version"], BATCH_PROTOCOL_VERSION);
+            let specs: Value = serde_json::from_slice(&request.body).unwrap();
+            assert_eq!(specs[0]["channels"], json!(["a", "b"]));
+            MockResponse::json(400, &json!({
+                "error": {"code": 40020, "message": "Batched response includes errors"},
+                "batchResponse": [{
+                    "successCount": 1,
+                    "failureCount": 1,
+                    "results": [
+                        {"channel": "a", "messageId": "id-a", "serials": ["s1"]},
+                        {"channel": "b", "error": {"code": 40160, "message": "Not permitted"}}
+                    ]
+                }]
+            }))
+        })
+        .await;
+        let client = mock_client(&server);
+        
+        let spec = BatchPublishSpec::new(vec!["a".to_string(), "b".to_string()], vec![message("hi")]);
+        let results = client.batch_publish(vec![spec]).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].successes().next().unwrap().1.message_id, "id-a");
+        assert_eq!(results[0].failures().next().unwrap().1.code, 40160);
+    }
+    
+    #[tokio::test]
+    async fn test_presence_get_filters_and_decrypts() {
+        let cipher = CipherParams::from_key(vec![7u8; 32]).unwrap();
+        let mut member = PresenceMessage {
+            client_id: Some("bob".to_string()),
+            data: Some(json!({"status": "away"}).into()),
+            ..Default::default()
+        };
+        encode_payload(&mut member, Some(&cipher.clone().into()), EncodingFormat::Json).unwrap();
+        let members = serde_json::to_value(vec![member]).unwrap();
+        
+        let server = MockServer::start(move |request| {
+            assert!(request.path.starts_with("/channels/room/presence?"));
+            assert_eq!(request.query("clientId"), Some("bob"));
+            assert_eq!(request.query("connectionId"), Some("conn-1"));
+            assert_eq!(request.query("limit"), Some("5"));
+            MockResponse::json(200, &members)
+        })
+        .await;
+        let client = mock_client(&server);
+        
+        let page = client.channel("room")
+            .with_cipher(cipher)
+            .presence()
+            .limit(5)
+            .client_id("bob")
+            .connection_id("conn-1")
+            .get()
+            .await
+            .unwrap();
+        assert_eq!(page.items[0].data, Some(json!({"status": "away"}).into()));
+        assert_eq!(page.items[0].encoding, None);
+    }
+    
+    #[tokio::test]
+    async fn test_message_operations() {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = requests.clone();
+        let server = MockServer::start(move |request| {
+            let body = match request.method.as_str() {
+                "GET" if request.path.ends_with("/versions") => json!([
+                    {"serial": "01:0", "version": "01:0", "action": 0, "data": "first"},
+                    {"serial": "01:0", "version": "02:0", "action": 1, "data": "second"}
+                ]),
+                "GET" => json!({"serial": "01:0", "action": 2, "data": "ZGF0YQ==", "encoding": "base64"}),
+                _ => json!({}),
+            };
+            let posted = serde_json::from_slice(&request.body).unwrap_or(Value::Null);
+            recorded.lock().unwrap().push((request.method, request.path, posted));
+            MockResponse::json(200, &body)
+        })
+        .await;
+        let client = mock_client(&server);
+        let channel = client.channel("chat");
+        
+        let edit = Message { serial: Some("01:0".to_string()), ..message("edited") };
+        let operation = MessageOperation::new().description("typo").metadata("reason", "spelling");
+        channel.update_message(edit.clone(), Some(operation)).await.unwrap();
+        channel.delete_message(edit.clone(), None).await.unwrap();
+        channel.append_message(edit, None).await.unwrap();
         
-        Ok(PaginatedResult {
-            items: response,
-            http_client: self.http_client,
-            next_url: None, // TODO: Parse Link header
+        let deleted = channel.get_message("01:0").await.unwrap();
+        assert_eq!(deleted.action, Some(MessageAction::Delete));
+        assert_eq!(deleted.data, Some(b"data".to_vec().into()));
+        let versions = channel.get_message_versions("01:0").await.unwrap();
+        assert_eq!(versions.items[1].action, Some(MessageAction::Update));
+        assert_eq!(versions.items[1].version.as_deref(), Some("02:0"));
+        
+        let requests = requests.lock().unwrap();
+        let (method, path, body) = &requests[0];
+        assert_eq!((method.as_str(), path.as_str()), ("PATCH", "/channels/chat/messages/01%3A0"));
+        assert_eq!(body["action"], 1);
+        assert_eq!(body["data"], "edited");
+        assert_eq!(body["operation"], json!({"description": "typo", "metadata": {"reason": "spelling"}}));
+        assert_eq!((requests[1].0.as_str(), requests[1].1.as_str()), ("POST", "/channels/chat/messages/01%3A0/delete"));
+        assert_eq!(requests[1].2["action"], 2);
+        assert_eq!((requests[2].0.as_str(), requests[2].1.as_str()), ("POST", "/channels/chat/messages/01%3A0/append"));
+        assert_eq!(requests[2].2["action"], 5);
+    }
+    
+    #[tokio::test]
+    async fn test_message_operation_error_status() {
+        let server = MockServer::start(|_| {
+            MockResponse::json(400, &json!({"error": {"code": 40003, "statusCode": 400, "message": "Invalid serial"}}))
         })
+        .await;
+        let client = mock_client(&server);
+        let channel = client.channel("chat");
+        
+        let edit = Message { serial: Some("01:0".to_string()), ..message("edited") };
+        let err = channel.update_message(edit.clone(), None).await.unwrap_err();
+        assert!(matches!(err, AblyError::BadRequest { .. }));
+        assert!(channel.delete_message(edit.clone(), None).await.is_err());
+        assert!(channel.append_message(edit, None).await.is_err());
+        assert!(channel.get_message("01:0").await.is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_message_operation_requires_serial() {
+        let server = MockServer::status(200).await;
+        let client = mock_client(&server);
+        
+        let err = client.channel("chat").update_message(message("edited"), None).await.unwrap_err();
+        assert!(matches!(err, AblyError::BadRequest { .. }));
+        assert_eq!(server.hits(), 0);
+    }
+    
+    #[test]
+    fn test_supplied_ids_kept_and_mixed_ids_rejected() {
+        let mut supplied = vec![Message { id: Some("mine:0".to_string()), ..message("a") }];
+        assign_idempotent_ids(&mut supplied).unwrap();
+        assert_eq!(supplied[0].id.as_deref(), Some("mine:0"));
+        
+        let mut mixed = vec![supplied.remove(0), message("b")];
+        let err = assign_idempotent_ids(&mut mixed).unwrap_err();
+        assert!(matches!(err, AblyError::BadRequest { .. }));
     }
 }
-
-// Re-export commonly used types
-pub use crate::protocol::messages::Message as MessageBuilder;
\ No newline at end of file
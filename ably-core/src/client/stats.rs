@@ -0,0 +1,346 @@
+// Application statistics returned by the stats endpoint
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Granularity of a stats interval (TS12c)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StatsUnit {
+    Minute,
+    Hour,
+    Day,
+    Month,
+}
+
+impl StatsUnit {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StatsUnit::Minute => "minute",
+            StatsUnit::Hour => "hour",
+            StatsUnit::Day => "day",
+            StatsUnit::Month => "month",
+        }
+    }
+}
+
+impl fmt::Display for StatsUnit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Statistics for one interval of an application (TS1)
+///
+/// Counters that are absent from a response are zero.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Stats {
+    pub interval_id: Option<String>,
+    pub unit: Option<StatsUnit>,
+    pub processed: Option<bool>,
+    pub in_progress: Option<Value>,
+    pub all: StatsMessageTypes,
+    pub inbound: StatsMessageTraffic,
+    pub outbound: StatsMessageTraffic,
+    pub persisted: StatsMessageTypes,
+    pub connections: StatsConnectionTypes,
+    pub channels: StatsResourceCount,
+    pub api_requests: StatsRequestCount,
+    pub token_requests: StatsRequestCount,
+    pub push: StatsPush,
+    pub xchg_producer: StatsXchgMessages,
+    pub xchg_consumer: StatsXchgMessages,
+}
+
+impl Stats {
+    /// Combine several intervals into one entry of totals
+    ///
+    /// Counts are summed, peaks and minimums take the extreme across entries
+    /// and means are averaged. The result has no interval or unit.
+    pub fn roll_up<'s>(entries: impl IntoIterator<Item = &'s Stats>) -> Stats {
+        let mut total = Stats::default();
+        let mut intervals = 0;
+        for entry in entries {
+            total.add(entry, intervals == 0);
+            intervals += 1;
+        }
+        total.finish(intervals);
+        total
+    }
+
+    fn add(&mut self, other: &Stats, first: bool) {
+        self.all.add(&other.all);
+        self.inbound.add(&other.inbound);
+        self.outbound.add(&other.outbound);
+        self.persisted.add(&other.persisted);
+        self.connections.add(&other.connections, first);
+        self.channels.add(&other.channels, first);
+        self.api_requests.add(&other.api_requests);
+        self.token_requests.add(&other.token_requests);
+        self.push.add(&other.push);
+        self.xchg_producer.add(&other.xchg_producer);
+        self.xchg_consumer.add(&other.xchg_consumer);
+    }
+
+    fn finish(&mut self, intervals: usize) {
+        self.connections.finish(intervals);
+        self.channels.finish(intervals);
+    }
+}
+
+/// Aggregate counts for a type of message (TS5)
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct StatsMessageCount {
+    pub count: f64,
+    pub data: f64,
+    pub uncompressed_data: f64,
+    pub failed: f64,
+    pub refused: f64,
+    /// Counts broken down by message category, such as `delta`
+    pub category: HashMap<String, StatsMessageCount>,
+}
+
+impl StatsMessageCount {
+    fn add(&mut self, other: &Self) {
+        self.count += other.count;
+        self.data += other.data;
+        self.uncompressed_data += other.uncompressed_data;
+        self.failed += other.failed;
+        self.refused += other.refused;
+        for (name, count) in &other.category {
+            self.category.entry(name.clone()).or_default().add(count);
+        }
+    }
+}
+
+/// Counts split into messages and presence messages (TS6)
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct StatsMessageTypes {
+    pub all: StatsMessageCount,
+    pub messages: StatsMessageCount,
+    pub presence: StatsMessageCount,
+}
+
+impl StatsMessageTypes {
+    fn add(&mut self, other: &Self) {
+        self.all.add(&other.all);
+        self.messages.add(&other.messages);
+        self.presence.add(&other.presence);
+    }
+}
+
+/// Message counts split by transport (TS7)
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct StatsMessageTraffic {
+    pub all: StatsMessageTypes,
+    pub realtime: StatsMessageTypes,
+    pub rest: StatsMessageTypes,
+    pub webhook: StatsMessageTypes,
+    pub shared_queue: StatsMessageTypes,
+    pub external_queue: StatsMessageTypes,
+    pub http_event: StatsMessageTypes,
+    pub push: StatsMessageTypes,
+}
+
+impl StatsMessageTraffic {
+    fn add(&mut self, other: &Self) {
+        self.all.add(&other.all);
+        self.realtime.add(&other.realtime);
+        self.rest.add(&other.rest);
+        self.webhook.add(&other.webhook);
+        self.shared_queue.add(&other.shared_queue);
+        self.external_queue.add(&other.external_queue);
+        self.http_event.add(&other.http_event);
+        self.push.add(&other.push);
+    }
+}
+
+/// Usage of a resource such as connections or channels over an interval (TS9)
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct StatsResourceCount {
+    pub opened: f64,
+    pub peak: f64,
+    pub mean: f64,
+    pub min: f64,
+    pub refused: f64,
+}
+
+impl StatsResourceCount {
+    fn add(&mut self, other: &Self, first: bool) {
+        self.opened += other.opened;
+        self.refused += other.refused;
+        self.mean += other.mean;
+        self.peak = self.peak.max(other.peak);
+        self.min = if first { other.min } else { self.min.min(other.min) };
+    }
+
+    fn finish(&mut self, intervals: usize) {
+        if intervals > 0 {
+            self.mean /= intervals as f64;
+        }
+    }
+}
+
+/// Connection usage split by TLS (TS4)
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct StatsConnectionTypes {
+    pub all: StatsResourceCount,
+    pub plain: StatsResourceCount,
+    pub tls: StatsResourceCount,
+}
+
+impl StatsConnectionTypes {
+    fn add(&mut self, other: &Self, first: bool) {
+        self.all.add(&other.all, first);
+        self.plain.add(&other.plain, first);
+        self.tls.add(&other.tls, first);
+    }
+
+    fn finish(&mut self, intervals: usize) {
+        self.all.finish(intervals);
+        self.plain.finish(intervals);
+        self.tls.finish(intervals);
+    }
+}
+
+/// Outcomes of API or token requests (TS8)
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct StatsRequestCount {
+    pub succeeded: f64,
+    pub failed: f64,
+    pub refused: f64,
+}
+
+impl StatsRequestCount {
+    fn add(&mut self, other: &Self) {
+        self.succeeded += other.succeeded;
+        self.failed += other.failed;
+        self.refused += other.refused;
+    }
+}
+
+/// Push notification activity (TS10)
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct StatsPush {
+    pub messages: f64,
+    pub notifications: StatsPushedMessages,
+    pub direct_publishes: f64,
+}
+
+impl StatsPush {
+    fn add(&mut self, other: &Self) {
+        self.messages += other.messages;
+        self.notifications.add(&other.notifications);
+        self.direct_publishes += other.direct_publishes;
+    }
+}
+
+/// Delivery outcomes of push notifications (TS11)
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct StatsPushedMessages {
+    pub invalid: f64,
+    pub attempted: f64,
+    pub successful: f64,
+    pub failed: f64,
+}
+
+impl StatsPushedMessages {
+    fn add(&mut self, other: &Self) {
+        self.invalid += other.invalid;
+        self.attempted += other.attempted;
+        self.successful += other.successful;
+        self.failed += other.failed;
+    }
+}
+
+/// API Streamer messages, split by which side pays for them (TS13)
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct StatsXchgMessages {
+    pub all: StatsMessageTypes,
+    pub producer_paid: StatsMessageTraffic,
+    pub consumer_paid: StatsMessageTraffic,
+}
+
+impl StatsXchgMessages {
+    fn add(&mut self, other: &Self) {
+        self.all.add(&other.all);
+        self.producer_paid.add(&other.producer_paid);
+        self.consumer_paid.add(&other.consumer_paid);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn entry(value: Value) -> Stats {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_parse_full_entry() {
+        let stats = entry(json!({
+            "intervalId": "2025-01-16:04:33",
+            "unit": "minute",
+            "inbound": {"realtime": {"messages": {"count": 5, "data": 120, "category": {"delta": {"count": 2}}}}},
+            "persisted": {"presence": {"count": 1}},
+            "connections": {"tls": {"opened": 3, "peak": 4, "mean": 2.5, "min": 1}},
+            "channels": {"peak": 1, "min": 1, "mean": 0.7},
+            "apiRequests": {"succeeded": 10, "refused": 1},
+            "tokenRequests": {"failed": 2},
+            "push": {"messages": 4, "notifications": {"successful": 3}, "directPublishes": 1},
+            "xchgProducer": {"producerPaid": {"rest": {"all": {"count": 6}}}}
+        }));
+
+        assert_eq!(stats.unit, Some(StatsUnit::Minute));
+        assert_eq!(stats.inbound.realtime.messages.count, 5.0);
+        assert_eq!(stats.inbound.realtime.messages.category["delta"].count, 2.0);
+        assert_eq!(stats.persisted.presence.count, 1.0);
+        assert_eq!(stats.connections.tls.peak, 4.0);
+        assert_eq!(stats.channels.mean, 0.7);
+        assert_eq!(stats.api_requests.refused, 1.0);
+        assert_eq!(stats.token_requests.failed, 2.0);
+        assert_eq!(stats.push.notifications.successful, 3.0);
+        assert_eq!(stats.xchg_producer.producer_paid.rest.all.count, 6.0);
+        assert_eq!(stats.outbound, StatsMessageTraffic::default());
+    }
+
+    #[test]
+    fn test_roll_up() {
+        let entries = vec![
+            entry(json!({
+                "all": {"messages": {"count": 3, "category": {"delta": {"count": 1}}}},
+                "channels": {"opened": 2, "peak": 5, "min": 2, "mean": 3},
+                "apiRequests": {"succeeded": 1}
+            })),
+            entry(json!({
+                "all": {"messages": {"count": 4, "category": {"delta": {"count": 2}}}},
+                "channels": {"opened": 1, "peak": 3, "min": 1, "mean": 2},
+                "apiRequests": {"succeeded": 2}
+            })),
+        ];
+
+        let total = Stats::roll_up(&entries);
+        assert_eq!(total.all.messages.count, 7.0);
+        assert_eq!(total.all.messages.category["delta"].count, 3.0);
+        assert_eq!(total.channels.opened, 3.0);
+        assert_eq!(total.channels.peak, 5.0);
+        assert_eq!(total.channels.min, 1.0);
+        assert_eq!(total.channels.mean, 2.5);
+        assert_eq!(total.api_requests.succeeded, 3.0);
+        assert_eq!(total.interval_id, None);
+    }
+}
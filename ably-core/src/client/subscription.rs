@@ -0,0 +1,584 @@
+// Ordered, bounded message subscriptions for realtime channels
+
+use crate::error::AblyError;
+use crate::protocol::messages::{Message, MessageAction};
+use futures_util::Stream;
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use tokio::sync::Notify;
+
+/// Default number of messages buffered per subscription
+pub const DEFAULT_SUBSCRIPTION_BUFFER: usize = 100;
+
+/// What to do when a subscriber falls behind and its buffer is full
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverflowPolicy {
+    /// Hold back delivery on this channel until the subscriber catches up
+    ///
+    /// Other channels and connection state changes are not held back. Frames
+    /// that arrive once the channel's inbound queue is full are dropped and
+    /// reported through the channel's error handlers.
+    Block,
+    /// Discard the oldest buffered message to make room
+    #[default]
+    DropOldest,
+    /// Discard the incoming message
+    DropNewest,
+    /// Close the subscription with an error
+    Error,
+}
+
+/// Filter selecting which messages a subscriber receives
+#[derive(Debug, Clone, Default)]
+pub struct MessageFilter {
+    names: Option<Vec<String>>,
+    actions: Option<Vec<MessageAction>>,
+    headers: HashMap<String, Value>,
+}
+
+impl MessageFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only accept messages with this name (may be repeated)
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.names.get_or_insert_with(Vec::new).push(name.into());
+        self
+    }
+
+    /// Only accept messages with this action (may be repeated)
+    ///
+    /// Messages without an action count as `MessageAction::Create`.
+    pub fn action(mut self, action: MessageAction) -> Self {
+        self.actions.get_or_insert_with(Vec::new).push(action);
+        self
+    }
+
+    /// Only accept messages whose `extras.headers` contain this value
+    pub fn header(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.headers.insert(key.into(), value.into());
+        self
+    }
+
+    /// Check whether a message passes the filter
+    pub fn matches(&self, message: &Message) -> bool {
+        if let Some(names) = &self.names {
+            match &message.name {
+                Some(name) if names.contains(name) => {}
+                _ => return false,
+            }
+        }
+
+        if let Some(actions) = &self.actions {
+            if !actions.contains(&message.action.unwrap_or_default()) {
+                return false;
+            }
+        }
+
+        if self.headers.is_empty() {
+            return true;
+        }
+
+        let headers = message.extras.as_ref().and_then(|extras| extras.get("headers"));
+        self.headers.iter().all(|(key, expected)| {
+            headers.and_then(|h| h.get(key)) == Some(expected)
+        })
+    }
+}
+
+/// Options for a message subscription
+#[derive(Debug, Clone)]
+pub struct SubscribeOptions {
+    pub buffer: usize,
+    pub overflow: OverflowPolicy,
+    pub filter: MessageFilter,
+}
+
+impl Default for SubscribeOptions {
+    fn default() -> Self {
+        Self {
+            buffer: DEFAULT_SUBSCRIPTION_BUFFER,
+            overflow: OverflowPolicy::default(),
+            filter: MessageFilter::default(),
+        }
+    }
+}
+
+impl SubscribeOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn buffer(mut self, size: usize) -> Self {
+        self.buffer = size.max(1);
+        self
+    }
+
+    pub fn overflow(mut self, policy: OverflowPolicy) -> Self {
+        self.overflow = policy;
+        self
+    }
+
+    pub fn filter(mut self, filter: MessageFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+}
+
+struct BufferState<T> {
+    queue: VecDeque<T>,
+    closed: bool,
+    error: Option<String>,
+    waker: Option<Waker>,
+}
+
+/// State shared between a listener and its subscriber
+struct Shared<T> {
+    active: AtomicBool,
+    buffer: Mutex<BufferState<T>>,
+    space: Notify,
+}
+
+impl<T> Shared<T> {
+    fn new() -> Arc<Self> {
+        Arc::new(Self {
+            active: AtomicBool::new(true),
+            buffer: Mutex::new(BufferState {
+                queue: VecDeque::new(),
+                closed: false,
+                error: None,
+                waker: None,
+            }),
+            space: Notify::new(),
+        })
+    }
+
+    fn is_active(&self) -> bool {
+        self.active.load(Ordering::SeqCst)
+    }
+
+    /// Buffer an item, applying the overflow policy; false once the subscription has closed
+    async fn push(&self, item: &T, capacity: usize, overflow: OverflowPolicy) -> bool
+    where
+        T: Clone,
+    {
+        loop {
+            let waker = {
+                let mut state = self.buffer.lock().unwrap();
+                if state.closed {
+                    return false;
+                }
+
+                if state.queue.len() >= capacity {
+                    match overflow {
+                        OverflowPolicy::Block => None,
+                        OverflowPolicy::DropOldest => {
+                            state.queue.pop_front();
+                            state.queue.push_back(item.clone());
+                            return true;
+                        }
+                        OverflowPolicy::DropNewest => return true,
+                        OverflowPolicy::Error => {
+                            state.error = Some(format!(
+                                "Subscription buffer of {} messages overflowed",
+                                capacity
+                            ));
+                            state.closed = true;
+                            self.active.store(false, Ordering::SeqCst);
+                            if let Some(waker) = state.waker.take() {
+                                waker.wake();
+                            }
+                            return false;
+                        }
+                    }
+                } else {
+                    state.queue.push_back(item.clone());
+                    Some(state.waker.take())
+                }
+            };
+
+            match waker {
+                Some(waker) => {
+                    if let Some(waker) = waker {
+                        waker.wake();
+                    }
+                    return true;
+                }
+                // Wait for the subscriber to make room
+                None => self.space.notified().await,
+            }
+        }
+    }
+
+    fn close(&self) {
+        self.active.store(false, Ordering::SeqCst);
+        let waker = {
+            let mut state = self.buffer.lock().unwrap();
+            state.closed = true;
+            state.waker.take()
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+        self.space.notify_one();
+    }
+}
+
+type MessageHandler = Arc<dyn Fn(Message) + Send + Sync>;
+
+enum Sink {
+    Handler(MessageHandler),
+    Buffer { capacity: usize, overflow: OverflowPolicy },
+}
+
+/// Channel-side end of a subscription
+pub(crate) struct MessageListener {
+    filter: MessageFilter,
+    shared: Arc<Shared<Message>>,
+    sink: Sink,
+}
+
+impl MessageListener {
+    /// Create a listener invoking a handler for each message
+    pub(crate) fn handler(filter: MessageFilter, handler: MessageHandler) -> (Self, SubscriptionHandle) {
+        let shared = Shared::new();
+        let handle = SubscriptionHandle { shared: shared.clone() };
+        (Self { filter, shared, sink: Sink::Handler(handler) }, handle)
+    }
+
+    /// Create a listener feeding a buffered stream
+    pub(crate) fn buffered(options: SubscribeOptions) -> (Self, Subscription) {
+        let shared = Shared::new();
+        let subscription = Subscription { shared: shared.clone() };
+        let sink = Sink::Buffer {
+            capacity: options.buffer.max(1),
+            overflow: options.overflow,
+        };
+        (Self { filter: options.filter, shared, sink }, subscription)
+    }
+
+    pub(crate) fn is_active(&self) -> bool {
+        self.shared.is_active()
+    }
+
+    /// Deliver a message, returning false once the listener should be removed
+    pub(crate) async fn deliver(&self, message: &Message) -> bool {
+        if !self.is_active() {
+            return false;
+        }
+        if !self.filter.matches(message) {
+            return true;
+        }
+
+        let (capacity, overflow) = match &self.sink {
+            Sink::Handler(handler) => {
+                handler(message.clone());
+                return true;
+            }
+            Sink::Buffer { capacity, overflow } => (*capacity, *overflow),
+        };
+
+        self.shared.push(message, capacity, overflow).await
+    }
+}
+
+/// Channel-side end of a buffered subscription to items other than messages
+pub(crate) struct BufferedListener<T> {
+    shared: Arc<Shared<T>>,
+    capacity: usize,
+    overflow: OverflowPolicy,
+}
+
+impl<T: Clone> BufferedListener<T> {
+    /// Create a listener feeding a stream; the options' message filter does not apply
+    pub(crate) fn new(options: &SubscribeOptions) -> (Self, Subscription<T>) {
+        let shared = Shared::new();
+        let subscription = Subscription { shared: shared.clone() };
+        let listener = Self {
+            shared,
+            capacity: options.buffer.max(1),
+            overflow: options.overflow,
+        };
+        (listener, subscription)
+    }
+
+    pub(crate) fn is_active(&self) -> bool {
+        self.shared.is_active()
+    }
+
+    /// Deliver an item, returning false once the listener should be removed
+    pub(crate) async fn deliver(&self, item: &T) -> bool {
+        self.is_active() && self.shared.push(item, self.capacity, self.overflow).await
+    }
+}
+
+/// Handle for removing a subscription from its channel
+pub struct SubscriptionHandle<T = Message> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Clone for SubscriptionHandle<T> {
+    fn clone(&self) -> Self {
+        Self { shared: self.shared.clone() }
+    }
+}
+
+impl<T> SubscriptionHandle<T> {
+    /// Stop delivering messages to this subscription
+    pub fn unsubscribe(&self) {
+        self.shared.close();
+    }
+
+    /// Check whether the subscription is still receiving messages
+    pub fn is_active(&self) -> bool {
+        self.shared.active.load(Ordering::SeqCst)
+    }
+}
+
+/// Ordered stream of messages, or annotations, delivered to a channel subscriber
+pub struct Subscription<T = Message> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Subscription<T> {
+    /// Receive the next item, or None once unsubscribed
+    pub async fn recv(&mut self) -> Option<T> {
+        futures_util::StreamExt::next(self).await
+    }
+
+    /// Get a handle that can unsubscribe from elsewhere
+    pub fn handle(&self) -> SubscriptionHandle<T> {
+        SubscriptionHandle { shared: self.shared.clone() }
+    }
+
+    /// Stop delivering messages; already buffered messages can still be read
+    pub fn unsubscribe(&self) {
+        self.shared.close();
+    }
+
+    /// Get the error that closed the subscription, if it overflowed
+    pub fn error(&self) -> Option<AblyError> {
+        self.shared.buffer.lock().unwrap().error.clone().map(AblyError::unexpected)
+    }
+}
+
+impl<T> Stream for Subscription<T> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let mut state = self.shared.buffer.lock().unwrap();
+        if let Some(message) = state.queue.pop_front() {
+            drop(state);
+            self.shared.space.notify_one();
+            return Poll::Ready(Some(message));
+        }
+
+        if state.closed {
+            return Poll::Ready(None);
+        }
+
+        state.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl<T> Drop for Subscription<T> {
+    fn drop(&mut self) {
+        self.shared.close();
+    }
+}
+
+/// A received message, classified by what it does to the message with its serial
+#[derive(Debug, Clone)]
+pub enum MessageEvent {
+    Created(Message),
+    Updated(Message),
+    Deleted(Message),
+    Appended(Message),
+    Meta(Message),
+    Summary(Message),
+    /// A message with an action this library does not know
+    Unknown(Message),
+}
+
+impl MessageEvent {
+    pub fn message(&self) -> &Message {
+        match self {
+            MessageEvent::Created(message)
+            | MessageEvent::Updated(message)
+            | MessageEvent::Deleted(message)
+            | MessageEvent::Appended(message)
+            | MessageEvent::Meta(message)
+            | MessageEvent::Summary(message)
+            | MessageEvent::Unknown(message) => message,
+        }
+    }
+
+    pub fn into_message(self) -> Message {
+        match self {
+            MessageEvent::Created(message)
+            | MessageEvent::Updated(message)
+            | MessageEvent::Deleted(message)
+            | MessageEvent::Appended(message)
+            | MessageEvent::Meta(message)
+            | MessageEvent::Summary(message)
+            | MessageEvent::Unknown(message) => message,
+        }
+    }
+}
+
+impl From<Message> for MessageEvent {
+    fn from(message: Message) -> Self {
+        match message.action.unwrap_or_default() {
+            MessageAction::Create => MessageEvent::Created(message),
+            MessageAction::Update => MessageEvent::Updated(message),
+            MessageAction::Delete => MessageEvent::Deleted(message),
+            MessageAction::Append => MessageEvent::Appended(message),
+            MessageAction::Meta => MessageEvent::Meta(message),
+            MessageAction::Summary => MessageEvent::Summary(message),
+            MessageAction::Unknown(_) => MessageEvent::Unknown(message),
+        }
+    }
+}
+
+/// Stream of channel messages as `MessageEvent`s
+pub struct MessageEvents {
+    inner: Subscription,
+}
+
+impl MessageEvents {
+    pub(crate) fn new(inner: Subscription) -> Self {
+        Self { inner }
+    }
+
+    /// Receive the next event, or None once unsubscribed
+    pub async fn recv(&mut self) -> Option<MessageEvent> {
+        futures_util::StreamExt::next(self).await
+    }
+
+    /// Get a handle that can unsubscribe from elsewhere
+    pub fn handle(&self) -> SubscriptionHandle {
+        self.inner.handle()
+    }
+
+    /// Stop delivering events; already buffered events can still be read
+    pub fn unsubscribe(&self) {
+        self.inner.unsubscribe();
+    }
+}
+
+impl Stream for MessageEvents {
+    type Item = MessageEvent;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<MessageEvent>> {
+        Pin::new(&mut self.get_mut().inner).poll_next(cx).map(|message| message.map(MessageEvent::from))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::StreamExt;
+
+    fn message(name: &str) -> Message {
+        Message {
+            name: Some(name.to_string()),
+            ..Default::default()
+        }
+    }
+
+    async fn drain(subscription: &mut Subscription) -> Vec<String> {
+        subscription.unsubscribe();
+        subscription.map(|m| m.name.unwrap()).collect().await
+    }
+
+    #[tokio::test]
+    async fn test_drop_oldest_and_newest() {
+        let (listener, mut oldest) = MessageListener::buffered(
+            SubscribeOptions::new().buffer(2).overflow(OverflowPolicy::DropOldest),
+        );
+        for name in ["a", "b", "c"] {
+            assert!(listener.deliver(&message(name)).await);
+        }
+        assert_eq!(drain(&mut oldest).await, vec!["b", "c"]);
+
+        let (listener, mut newest) = MessageListener::buffered(
+            SubscribeOptions::new().buffer(2).overflow(OverflowPolicy::DropNewest),
+        );
+        for name in ["a", "b", "c"] {
+            assert!(listener.deliver(&message(name)).await);
+        }
+        assert_eq!(drain(&mut newest).await, vec!["a", "b"]);
+    }
+
+    #[tokio::test]
+    async fn test_error_policy_closes_subscription() {
+        let (listener, mut subscription) = MessageListener::buffered(
+            SubscribeOptions::new().buffer(1).overflow(OverflowPolicy::Error),
+        );
+        assert!(listener.deliver(&message("a")).await);
+        assert!(!listener.deliver(&message("b")).await);
+
+        assert_eq!(subscription.next().await.and_then(|m| m.name), Some("a".to_string()));
+        assert!(subscription.next().await.is_none());
+        assert!(subscription.error().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_block_policy_waits_for_consumer() {
+        let (listener, mut subscription) = MessageListener::buffered(
+            SubscribeOptions::new().buffer(1).overflow(OverflowPolicy::Block),
+        );
+        let producer = tokio::spawn(async move {
+            for name in ["a", "b", "c"] {
+                listener.deliver(&message(name)).await;
+            }
+        });
+
+        let received: Vec<String> = subscription.by_ref().take(3).map(|m| m.name.unwrap()).collect().await;
+        producer.await.unwrap();
+        assert_eq!(received, vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn test_filter_by_name_and_header() {
+        let filter = MessageFilter::new().name("a").name("b").header("region", "eu");
+
+        let mut msg = message("a");
+        assert!(!filter.matches(&msg));
+
+        msg.extras = Some(HashMap::from([(
+            "headers".to_string(),
+            serde_json::json!({ "region": "eu" }),
+        )]));
+        assert!(filter.matches(&msg));
+
+        msg.name = Some("c".to_string());
+        assert!(!filter.matches(&msg));
+    }
+
+    #[test]
+    fn test_filter_by_action() {
+        let filter = MessageFilter::new().action(MessageAction::Create).action(MessageAction::Delete);
+
+        let mut msg = message("a");
+        assert!(filter.matches(&msg));
+
+        msg.action = Some(MessageAction::Update);
+        assert!(!filter.matches(&msg));
+        assert!(matches!(MessageEvent::from(msg.clone()), MessageEvent::Updated(_)));
+
+        msg.action = Some(MessageAction::Delete);
+        assert!(filter.matches(&msg));
+        assert_eq!(MessageEvent::from(msg.clone()).into_message().name, msg.name);
+
+        msg.action = Some(MessageAction::Unknown(15));
+        assert!(!filter.matches(&msg));
+        assert!(matches!(MessageEvent::from(msg), MessageEvent::Unknown(_)));
+    }
+}
@@ -0,0 +1,231 @@
+// Typed message payloads using serde
+
+use crate::client::subscription::{Subscription, SubscriptionHandle};
+use crate::error::{AblyError, AblyResult};
+use crate::protocol::messages::{Message, MessageAction, MessageData};
+use futures_util::Stream;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Message whose data has been deserialized into `T`, with its metadata preserved
+///
+/// Only fully decoded messages become typed messages, so there is no `encoding`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypedMessage<T> {
+    pub id: Option<String>,
+    pub name: Option<String>,
+    pub client_id: Option<String>,
+    pub connection_id: Option<String>,
+    pub timestamp: Option<i64>,
+    pub serial: Option<String>,
+    /// Whether the message was created, updated, deleted and so on
+    pub action: Option<MessageAction>,
+    /// Serial of this version of the message
+    pub version: Option<String>,
+    pub extras: Option<HashMap<String, Value>>,
+    pub data: T,
+}
+
+impl<T> TypedMessage<T> {
+    /// Pair already-decoded data with the message's metadata
+    pub(crate) fn with_data(message: Message, data: T) -> Self {
+        Self {
+            id: message.id,
+            name: message.name,
+            client_id: message.client_id,
+            connection_id: message.connection_id,
+            timestamp: message.timestamp,
+            serial: message.serial,
+            action: message.action,
+            version: message.version,
+            extras: message.extras,
+            data,
+        }
+    }
+}
+
+impl<T: DeserializeOwned> TypedMessage<T> {
+    /// Deserialize a decoded message's data
+    ///
+    /// JSON and string data deserialize directly; binary data is parsed as JSON
+    /// text and absent data as `null`. Messages that could not be decoded or
+    /// decrypted, and so still carry an encoding, are an error.
+    pub fn from_message(message: Message) -> AblyResult<Self> {
+        ensure_decoded(&message)?;
+        let data = deserialize_data(message.data.as_ref()).map_err(|e| {
+            AblyError::decode(format!(
+                "Failed to deserialize message {} ({}): {}",
+                message.id.as_deref().unwrap_or("<no id>"),
+                message.name.as_deref().unwrap_or("<no name>"),
+                e
+            ))
+        })?;
+        Ok(Self::with_data(message, data))
+    }
+}
+
+/// Fail for messages delivered with their encoding intact because decoding failed
+pub(crate) fn ensure_decoded(message: &Message) -> AblyResult<()> {
+    match &message.encoding {
+        Some(encoding) => Err(AblyError::decode(format!(
+            "Message {} ({}) was not decoded, encoding '{}' remains",
+            message.id.as_deref().unwrap_or("<no id>"),
+            message.name.as_deref().unwrap_or("<no name>"),
+            encoding
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Deserialize message data as JSON
+pub(crate) fn deserialize_data<T: DeserializeOwned>(data: Option<&MessageData>) -> serde_json::Result<T> {
+    match data {
+        None => serde_json::from_value(Value::Null),
+        Some(MessageData::String(s)) => serde_json::from_value(Value::String(s.clone())),
+        Some(MessageData::Json(v)) => serde_json::from_value(v.clone()),
+        Some(MessageData::Binary(b)) => serde_json::from_slice(b),
+    }
+}
+
+impl<T: DeserializeOwned> TryFrom<Message> for TypedMessage<T> {
+    type Error = AblyError;
+
+    fn try_from(message: Message) -> AblyResult<Self> {
+        Self::from_message(message)
+    }
+}
+
+/// Build a message carrying a serialized value
+pub(crate) fn typed_message<T: Serialize + ?Sized>(name: &str, value: &T) -> AblyResult<Message> {
+    let data = serde_json::to_value(value)
+        .map_err(|e| AblyError::encoding(format!("Failed to serialize message '{}': {}", name, e)))?;
+
+    Ok(Message {
+        name: Some(name.to_string()),
+        data: Some(data.into()),
+        ..Default::default()
+    })
+}
+
+/// Stream of messages deserialized into `T`
+///
+/// A message that fails to deserialize yields an error for that message only;
+/// the stream carries on with the next one.
+pub struct TypedSubscription<T> {
+    inner: Subscription,
+    decode: fn(Message) -> AblyResult<TypedMessage<T>>,
+}
+
+impl<T: DeserializeOwned> TypedSubscription<T> {
+    pub(crate) fn new(inner: Subscription) -> Self {
+        Self::with_decoder(inner, TypedMessage::from_message)
+    }
+}
+
+impl<T> TypedSubscription<T> {
+    pub(crate) fn with_decoder(inner: Subscription, decode: fn(Message) -> AblyResult<TypedMessage<T>>) -> Self {
+        Self { inner, decode }
+    }
+
+    /// Receive the next message, or None once unsubscribed
+    pub async fn recv(&mut self) -> Option<AblyResult<TypedMessage<T>>> {
+        futures_util::StreamExt::next(self).await
+    }
+
+    /// Get a handle that can unsubscribe from elsewhere
+    pub fn handle(&self) -> SubscriptionHandle {
+        self.inner.handle()
+    }
+
+    /// Stop delivering messages; already buffered messages can still be read
+    pub fn unsubscribe(&self) {
+        self.inner.unsubscribe();
+    }
+
+    /// Get the error that closed the subscription, if it overflowed
+    pub fn error(&self) -> Option<AblyError> {
+        self.inner.error()
+    }
+}
+
+impl<T> Stream for TypedSubscription<T> {
+    type Item = AblyResult<TypedMessage<T>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let decode = this.decode;
+        Pin::new(&mut this.inner).poll_next(cx).map(|message| message.map(decode))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Order {
+        id: String,
+        amount: u32,
+    }
+
+    #[test]
+    fn test_round_trip_preserves_metadata() {
+        let order = Order { id: "o-1".to_string(), amount: 3 };
+        let mut message = typed_message("created", &order).unwrap();
+        message.id = Some("msg-1".to_string());
+        message.client_id = Some("shop".to_string());
+
+        let typed: TypedMessage<Order> = message.try_into().unwrap();
+        assert_eq!(typed.data, order);
+        assert_eq!(typed.name.as_deref(), Some("created"));
+        assert_eq!(typed.id.as_deref(), Some("msg-1"));
+        assert_eq!(typed.client_id.as_deref(), Some("shop"));
+
+        let text: TypedMessage<String> = typed_message("note", "hello").unwrap().try_into().unwrap();
+        assert_eq!(text.data, "hello");
+    }
+
+    #[test]
+    fn test_decode_failure_names_message() {
+        let message = Message {
+            id: Some("msg-2".to_string()),
+            data: Some(serde_json::json!({"id": 1}).into()),
+            ..Default::default()
+        };
+
+        let err = TypedMessage::<Order>::from_message(message).unwrap_err();
+        assert!(matches!(err, AblyError::Decode { .. }));
+        assert!(err.to_string().contains("msg-2"));
+    }
+
+    #[test]
+    fn test_undecoded_message_is_an_error() {
+        // Delivered as-is after failing to decrypt
+        let message = Message {
+            id: Some("msg-3".to_string()),
+            data: Some("c2VjcmV0".into()),
+            encoding: Some("utf-8/cipher+aes-128-cbc/base64".to_string()),
+            ..Default::default()
+        };
+
+        let err = TypedMessage::<String>::from_message(message).unwrap_err();
+        assert!(matches!(err, AblyError::Decode { .. }));
+        assert!(err.to_string().contains("cipher+aes-128-cbc"));
+    }
+
+    #[test]
+    fn test_action_and_version_preserved() {
+        let mut message = typed_message("edited", "hello").unwrap();
+        message.action = Some(MessageAction::Update);
+        message.version = Some("version-2".to_string());
+
+        let typed = TypedMessage::<String>::from_message(message).unwrap();
+        assert_eq!(typed.action, Some(MessageAction::Update));
+        assert_eq!(typed.version.as_deref(), Some("version-2"));
+    }
+}
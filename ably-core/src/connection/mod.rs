@@ -3,5 +3,6 @@
 pub mod state_machine;
 
 pub use state_machine::{
-    ConnectionStateMachine, ConnectionState, ConnectionEvent, ConnectionDetails
+    ConnectionStateMachine, ConnectionState, ConnectionEvent, ConnectionDetails,
+    ConnectionStateChange,
 };
\ No newline at end of file
@@ -36,6 +36,20 @@ pub enum ConnectionEvent {
     StateChanged { from: ConnectionState, to: ConnectionState },
 }
 
+/// Connection state change as seen by listeners
+#[derive(Debug, Clone)]
+pub struct ConnectionStateChange {
+    pub previous: ConnectionState,
+    pub current: ConnectionState,
+    pub reason: Option<ErrorInfo>,
+}
+
+impl ConnectionStateChange {
+    fn new(previous: ConnectionState, current: ConnectionState, reason: Option<ErrorInfo>) -> Self {
+        Self { previous, current, reason }
+    }
+}
+
 /// Connection details
 #[derive(Debug, Clone, Default)]
 pub struct ConnectionDetails {
@@ -53,6 +67,7 @@ pub struct ConnectionStateMachine {
     event_tx: mpsc::UnboundedSender<ConnectionEvent>,
     event_rx: Arc<RwLock<mpsc::UnboundedReceiver<ConnectionEvent>>>,
     listeners: Arc<RwLock<Vec<StateChangeListener>>>,
+    change_listeners: Arc<RwLock<Vec<ChangeListener>>>,
     retry_count: Arc<RwLock<u32>>,
     last_activity: Arc<RwLock<Instant>>,
     state_change_tx: mpsc::UnboundedSender<ConnectionEvent>,
@@ -70,6 +85,9 @@ struct StateTransition {
 
 type StateChangeListener = Arc<dyn Fn(ConnectionState, ConnectionState) + Send + Sync>;
 
+/// Returns false once the listener should be removed
+type ChangeListener = Box<dyn Fn(&ConnectionStateChange) -> bool + Send + Sync>;
+
 impl ConnectionStateMachine {
     /// Create a new connection state machine
     pub fn new() -> Self {
@@ -84,6 +102,7 @@ impl ConnectionStateMachine {
             event_tx: tx,
             event_rx: Arc::new(RwLock::new(rx)),
             listeners: Arc::new(RwLock::new(Vec::new())),
+            change_listeners: Arc::new(RwLock::new(Vec::new())),
             retry_count: Arc::new(RwLock::new(0)),
             last_activity: Arc::new(RwLock::new(Instant::now())),
             state_change_tx: state_tx,
@@ -233,9 +252,15 @@ impl ConnectionStateMachine {
         if history.len() > 100 {
             history.drain(0..50);
         }
+        drop(history);
+        
+        let reason = match event {
+            ConnectionEvent::Error(e) | ConnectionEvent::Disconnected(Some(e)) => Some(e),
+            _ => None,
+        };
         
         // Notify listeners
-        self.notify_listeners(current_state, new_state).await;
+        self.notify_listeners(ConnectionStateChange::new(current_state, new_state, reason)).await;
     }
 
     /// Check if error is fatal
@@ -253,12 +278,25 @@ impl ConnectionStateMachine {
         listeners.push(Arc::new(listener));
     }
 
+    /// Add a listener receiving full state changes, kept until it returns false
+    pub(crate) async fn add_change_listener<F>(&self, listener: F)
+    where
+        F: Fn(&ConnectionStateChange) -> bool + Send + Sync + 'static,
+    {
+        let mut listeners = self.change_listeners.write().await;
+        listeners.push(Box::new(listener));
+    }
+
     /// Notify all listeners of state change
-    async fn notify_listeners(&self, from: ConnectionState, to: ConnectionState) {
+    async fn notify_listeners(&self, change: ConnectionStateChange) {
         let listeners = self.listeners.read().await;
         for listener in listeners.iter() {
-            listener(from, to);
+            listener(change.previous, change.current);
         }
+        drop(listeners);
+        
+        let mut change_listeners = self.change_listeners.write().await;
+        change_listeners.retain(|listener| listener(&change));
     }
 
     /// Record transition in history
@@ -360,6 +398,12 @@ impl ConnectionStateMachine {
         // Record the transition
         self.record_transition(current, new_state).await;
 
+        let reason = match event {
+            ConnectionEvent::Error(e) => Some(e),
+            _ => None,
+        };
+        self.notify_listeners(ConnectionStateChange::new(current, new_state, reason)).await;
+
         Ok(())
     }
 
@@ -0,0 +1,345 @@
+// Channel key sets for rotating encryption keys without downtime
+
+use super::{CipherParams, MessageCrypto};
+use crate::error::{AblyError, AblyResult};
+use crate::protocol::encoding::data_encoding::{self, EncodedPayload};
+use crate::protocol::encoding::EncodingFormat;
+use serde_json::Value;
+use std::fmt;
+use std::sync::{Arc, RwLock};
+
+/// Header in `extras.headers` naming the key a message was encrypted with
+pub const KEY_ID_HEADER: &str = "key-id";
+
+#[derive(Clone)]
+struct KeyEntry {
+    id: Option<String>,
+    crypto: MessageCrypto,
+}
+
+struct KeySetState {
+    primary: KeyEntry,
+    /// Decrypt-only keys, most recently added first
+    secondary: Vec<KeyEntry>,
+}
+
+/// Set of channel keys: encrypts with the primary key, decrypts with any key
+///
+/// Clones share the same keys, so rotating through any handle takes effect
+/// on every REST and realtime channel using the set.
+#[derive(Clone)]
+pub struct CipherKeySet {
+    state: Arc<RwLock<KeySetState>>,
+}
+
+impl CipherKeySet {
+    /// Create a key set with an identified primary key
+    pub fn new(primary_id: impl Into<String>, params: CipherParams) -> Self {
+        Self::with_primary(KeyEntry {
+            id: Some(primary_id.into()),
+            crypto: MessageCrypto::new(params),
+        })
+    }
+
+    fn with_primary(primary: KeyEntry) -> Self {
+        Self {
+            state: Arc::new(RwLock::new(KeySetState {
+                primary,
+                secondary: Vec::new(),
+            })),
+        }
+    }
+
+    /// Add a decrypt-only key, replacing any key with the same id
+    pub fn add_key(&self, id: impl Into<String>, params: CipherParams) {
+        let id = id.into();
+        let mut state = self.state.write().unwrap();
+        state.secondary.retain(|entry| entry.id.as_deref() != Some(id.as_str()));
+        state.secondary.insert(0, KeyEntry {
+            id: Some(id),
+            crypto: MessageCrypto::new(params),
+        });
+    }
+
+    /// Encrypt with a new primary key; the previous one stays available for decryption
+    pub fn rotate(&self, id: impl Into<String>, params: CipherParams) {
+        let id = id.into();
+        let mut state = self.state.write().unwrap();
+        state.secondary.retain(|entry| entry.id.as_deref() != Some(id.as_str()));
+        let previous = std::mem::replace(&mut state.primary, KeyEntry {
+            id: Some(id),
+            crypto: MessageCrypto::new(params),
+        });
+        state.secondary.insert(0, previous);
+    }
+
+    /// Promote an existing decrypt-only key to primary
+    pub fn set_primary(&self, id: &str) -> AblyResult<()> {
+        let mut state = self.state.write().unwrap();
+        let index = state
+            .secondary
+            .iter()
+            .position(|entry| entry.id.as_deref() == Some(id))
+            .ok_or_else(|| AblyError::invalid_request(format!("Unknown cipher key id '{}'", id)))?;
+
+        let promoted = state.secondary.remove(index);
+        let previous = std::mem::replace(&mut state.primary, promoted);
+        state.secondary.insert(0, previous);
+        Ok(())
+    }
+
+    /// Stop accepting a decrypt-only key
+    pub fn remove_key(&self, id: &str) -> AblyResult<()> {
+        let mut state = self.state.write().unwrap();
+        if state.primary.id.as_deref() == Some(id) {
+            return Err(AblyError::invalid_request("Cannot remove the primary cipher key"));
+        }
+
+        let before = state.secondary.len();
+        state.secondary.retain(|entry| entry.id.as_deref() != Some(id));
+        if state.secondary.len() == before {
+            return Err(AblyError::invalid_request(format!("Unknown cipher key id '{}'", id)));
+        }
+        Ok(())
+    }
+
+    /// Id of the key used for encryption
+    pub fn primary_id(&self) -> Option<String> {
+        self.state.read().unwrap().primary.id.clone()
+    }
+
+    /// Params of the key used for encryption
+    pub fn primary_params(&self) -> CipherParams {
+        self.state.read().unwrap().primary.crypto.params().clone()
+    }
+
+    /// Ids of all keys, primary first
+    pub fn key_ids(&self) -> Vec<String> {
+        let state = self.state.read().unwrap();
+        std::iter::once(&state.primary)
+            .chain(state.secondary.iter())
+            .filter_map(|entry| entry.id.clone())
+            .collect()
+    }
+
+    /// Encode and encrypt a payload with the primary key, tagging it with the key id
+    pub fn encode<P: EncodedPayload>(&self, item: &mut P, format: EncodingFormat) -> AblyResult<()> {
+        let primary = self.state.read().unwrap().primary.clone();
+        if item.payload_mut().0.is_none() {
+            return Ok(());
+        }
+
+        data_encoding::encode(item, Some(&primary.crypto), format)?;
+        if let (Some(id), Some(extras)) = (primary.id, item.extras_mut()) {
+            let headers = extras
+                .get_or_insert_with(Default::default)
+                .entry("headers".to_string())
+                .or_insert_with(|| Value::Object(Default::default()));
+            if let Value::Object(headers) = headers {
+                headers.insert(KEY_ID_HEADER.to_string(), Value::String(id));
+            }
+        }
+        Ok(())
+    }
+
+    /// Decode and decrypt a payload with the key named in its headers
+    ///
+    /// Untagged payloads are tried against each key only when their encoding
+    /// has a utf-8 or json step to reject a wrong key; a binary payload could
+    /// otherwise decrypt to garbage that looks valid.
+    pub fn decode<P: EncodedPayload>(&self, item: &mut P) -> AblyResult<()> {
+        let candidates: Vec<KeyEntry> = {
+            let state = self.state.read().unwrap();
+            std::iter::once(&state.primary)
+                .chain(state.secondary.iter())
+                .cloned()
+                .collect()
+        };
+
+        let key_id = item
+            .extras_mut()
+            .and_then(|extras| extras.as_ref())
+            .and_then(|extras| extras.get("headers"))
+            .and_then(|headers| headers.get(KEY_ID_HEADER))
+            .and_then(Value::as_str)
+            .map(str::to_string);
+        if let Some(key_id) = key_id {
+            return match candidates.iter().find(|entry| entry.id.as_deref() == Some(key_id.as_str())) {
+                Some(entry) => data_encoding::decode(item, Some(&entry.crypto)),
+                None => Err(AblyError::encryption(format!("Unknown cipher key id '{}'", key_id))),
+            };
+        }
+
+        if let [entry] = candidates.as_slice() {
+            return data_encoding::decode(item, Some(&entry.crypto));
+        }
+        if !has_checked_step(item.payload_mut().1.as_deref()) {
+            return Err(AblyError::encryption(
+                "Cannot tell which cipher key encrypted an untagged binary payload",
+            ));
+        }
+
+        let mut last_error = None;
+        for entry in &candidates {
+            match data_encoding::decode(item, Some(&entry.crypto)) {
+                Ok(()) => return Ok(()),
+                Err(e) => last_error = Some(e),
+            }
+        }
+        Err(last_error.unwrap_or_else(|| AblyError::encryption("No cipher keys configured")))
+    }
+}
+
+/// Whether a step applied after decryption, such as utf-8 or json, would reject a wrong key
+fn has_checked_step(encoding: Option<&str>) -> bool {
+    let Some(encoding) = encoding else {
+        return false;
+    };
+    encoding
+        .split('/')
+        .take_while(|step| !step.starts_with("cipher+"))
+        .any(|step| step == data_encoding::UTF8 || step == data_encoding::JSON)
+}
+
+/// Encode a payload, encrypting it with the primary key if keys are configured
+pub(crate) fn encode_payload<P: EncodedPayload>(
+    item: &mut P,
+    keys: Option<&CipherKeySet>,
+    format: EncodingFormat,
+) -> AblyResult<()> {
+    match keys {
+        Some(keys) => keys.encode(item, format),
+        None => data_encoding::encode(item, None, format),
+    }
+}
+
+/// Decode a payload, decrypting it if keys are configured
+pub(crate) fn decode_payload<P: EncodedPayload>(item: &mut P, keys: Option<&CipherKeySet>) -> AblyResult<()> {
+    match keys {
+        Some(keys) => keys.decode(item),
+        None => data_encoding::decode(item, None),
+    }
+}
+
+impl From<CipherParams> for CipherKeySet {
+    /// A key set holding a single, unidentified key
+    fn from(params: CipherParams) -> Self {
+        Self::with_primary(KeyEntry {
+            id: None,
+            crypto: MessageCrypto::new(params),
+        })
+    }
+}
+
+impl fmt::Debug for CipherKeySet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CipherKeySet")
+            .field("primary", &self.primary_id())
+            .field("keys", &self.key_ids())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::messages::{Message, PresenceMessage};
+
+    fn params(byte: u8) -> CipherParams {
+        CipherParams::from_key(vec![byte; 32]).unwrap()
+    }
+
+    fn encrypted(keys: &CipherKeySet, text: &str) -> Message {
+        let mut message = Message {
+            data: Some(text.into()),
+            ..Default::default()
+        };
+        keys.encode(&mut message, EncodingFormat::Json).unwrap();
+        message
+    }
+
+    #[test]
+    fn test_rotation_keeps_old_messages_readable() {
+        let keys = CipherKeySet::new("k1", params(1));
+        let old = encrypted(&keys, "before");
+        assert_eq!(old.extras.as_ref().unwrap()["headers"][KEY_ID_HEADER], "k1");
+
+        keys.rotate("k2", params(2));
+        let new = encrypted(&keys, "after");
+        assert_eq!(new.extras.as_ref().unwrap()["headers"][KEY_ID_HEADER], "k2");
+        assert_eq!(keys.key_ids(), vec!["k2", "k1"]);
+
+        for (mut message, text) in [(old, "before"), (new, "after")] {
+            keys.decode(&mut message).unwrap();
+            assert_eq!(message.data.unwrap().as_str(), Some(text));
+        }
+    }
+
+    #[test]
+    fn test_trial_decryption_without_key_id() {
+        let sender = CipherKeySet::from(params(3));
+        let mut message = encrypted(&sender, "untagged");
+        assert!(message.extras.is_none());
+
+        let keys = CipherKeySet::new("current", params(4));
+        keys.add_key("legacy", params(3));
+        keys.decode(&mut message).unwrap();
+        assert_eq!(message.data.unwrap().as_str(), Some("untagged"));
+
+        let mut presence = PresenceMessage {
+            data: Some("present".into()),
+            ..Default::default()
+        };
+        sender.encode(&mut presence, EncodingFormat::Json).unwrap();
+        keys.decode(&mut presence).unwrap();
+        assert_eq!(presence.data.unwrap().as_str(), Some("present"));
+    }
+
+    #[test]
+    fn test_removed_key_no_longer_decrypts() {
+        let keys = CipherKeySet::new("k1", params(5));
+        let mut message = encrypted(&keys, "secret");
+
+        keys.rotate("k2", params(6));
+        assert!(keys.remove_key("k2").is_err());
+        keys.remove_key("k1").unwrap();
+
+        assert!(keys.decode(&mut message).is_err());
+        assert!(message.encoding.unwrap().contains("cipher+aes-256-cbc"));
+
+        keys.add_key("k1", params(5));
+        keys.set_primary("k1").unwrap();
+        assert_eq!(keys.primary_id().as_deref(), Some("k1"));
+    }
+
+    #[test]
+    fn test_unknown_key_id_fails() {
+        let sender = CipherKeySet::new("retired", params(7));
+        let mut message = encrypted(&sender, "secret");
+
+        let keys = CipherKeySet::new("current", params(8));
+        keys.add_key("other", params(7));
+        assert!(keys.decode(&mut message).is_err());
+        assert!(message.encoding.unwrap().contains("cipher+aes-256-cbc"));
+    }
+
+    #[test]
+    fn test_untagged_binary_not_trial_decrypted() {
+        let sender = CipherKeySet::from(params(9));
+        let mut message = Message {
+            data: Some(vec![1u8, 2, 3, 4].into()),
+            ..Default::default()
+        };
+        sender.encode(&mut message, EncodingFormat::Json).unwrap();
+        assert_eq!(message.encoding.as_deref(), Some("cipher+aes-256-cbc/base64"));
+
+        let keys = CipherKeySet::new("current", params(10));
+        keys.add_key("legacy", params(9));
+        let mut untagged = message.clone();
+        assert!(keys.decode(&mut untagged).is_err());
+        assert_eq!(untagged.encoding, message.encoding);
+
+        sender.decode(&mut message).unwrap();
+        assert_eq!(message.data, Some(vec![1u8, 2, 3, 4].into()));
+    }
+}
@@ -2,12 +2,17 @@
 // AES-128/256-CBC encryption following Ably specification
 
 use crate::error::{AblyError, AblyResult};
+use crate::protocol::encoding::{data_encoding, EncodingFormat};
 use aes::cipher::{block_padding::Pkcs7, BlockDecryptMut, BlockEncryptMut, KeyIvInit};
 use base64::Engine;
 use rand::Rng;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
+pub mod key_set;
+
+pub use key_set::{CipherKeySet, KEY_ID_HEADER};
+
 type Aes128CbcEnc = cbc::Encryptor<aes::Aes128>;
 type Aes128CbcDec = cbc::Decryptor<aes::Aes128>;
 type Aes256CbcEnc = cbc::Encryptor<aes::Aes256>;
@@ -46,6 +51,83 @@ impl fmt::Display for CipherMode {
     }
 }
 
+impl CipherAlgorithm {
+    /// Pick the AES variant for a key length in bits
+    pub fn from_key_length(bits: usize) -> AblyResult<Self> {
+        match bits {
+            128 => Ok(CipherAlgorithm::Aes128),
+            256 => Ok(CipherAlgorithm::Aes256),
+            _ => Err(AblyError::invalid_request(format!(
+                "Unsupported key length {} bits. Must be 128 or 256 bits",
+                bits
+            ))),
+        }
+    }
+    
+    /// Key length in bits
+    pub fn key_length(&self) -> usize {
+        match self {
+            CipherAlgorithm::Aes128 => 128,
+            CipherAlgorithm::Aes256 => 256,
+        }
+    }
+}
+
+/// Key material accepted by `CipherParams::from_key`: raw bytes or a base64 string
+#[derive(Debug, Clone, PartialEq)]
+pub enum CipherKey {
+    Bytes(Vec<u8>),
+    Base64(String),
+}
+
+impl CipherKey {
+    /// Resolve the key to raw bytes
+    pub fn into_bytes(self) -> AblyResult<Vec<u8>> {
+        match self {
+            CipherKey::Bytes(bytes) => Ok(bytes),
+            CipherKey::Base64(text) => base64::engine::general_purpose::STANDARD
+                .decode(text.trim())
+                .map_err(|e| AblyError::invalid_request(format!("Invalid base64 key: {}", e))),
+        }
+    }
+}
+
+impl From<Vec<u8>> for CipherKey {
+    fn from(bytes: Vec<u8>) -> Self {
+        CipherKey::Bytes(bytes)
+    }
+}
+
+impl From<&[u8]> for CipherKey {
+    fn from(bytes: &[u8]) -> Self {
+        CipherKey::Bytes(bytes.to_vec())
+    }
+}
+
+impl<const N: usize> From<&[u8; N]> for CipherKey {
+    fn from(bytes: &[u8; N]) -> Self {
+        CipherKey::Bytes(bytes.to_vec())
+    }
+}
+
+impl From<String> for CipherKey {
+    fn from(text: String) -> Self {
+        CipherKey::Base64(text)
+    }
+}
+
+impl From<&String> for CipherKey {
+    fn from(text: &String) -> Self {
+        CipherKey::Base64(text.clone())
+    }
+}
+
+impl From<&str> for CipherKey {
+    fn from(text: &str) -> Self {
+        CipherKey::Base64(text.to_string())
+    }
+}
+
 /// Cipher parameters for channel encryption
 #[derive(Debug, Clone)]
 pub struct CipherParams {
@@ -115,31 +197,42 @@ impl CipherParams {
         )
     }
     
-    /// Create cipher params from a key string
-    pub fn from_key(key_str: &str) -> AblyResult<Self> {
-        // Decode base64 key
-        let key = base64::engine::general_purpose::STANDARD
-            .decode(key_str)
-            .map_err(|e| AblyError::unexpected(format!("Invalid base64 key: {}", e)))?;
-        
-        // Determine algorithm from key size
-        let algorithm = match key.len() {
-            16 => CipherAlgorithm::Aes128,
-            32 => CipherAlgorithm::Aes256,
-            _ => {
-                return Err(AblyError::unexpected(format!(
-                    "Invalid key size: {} bytes",
-                    key.len()
-                )))
-            }
-        };
-        
-        Ok(Self {
-            algorithm,
-            mode: CipherMode::Cbc,
-            key,
-            iv: None,
-        })
+    /// Create AES-CBC cipher params from raw or base64 key material (RSE1)
+    ///
+    /// The key length selects AES-128 or AES-256.
+    pub fn from_key(key: impl Into<CipherKey>) -> AblyResult<Self> {
+        let key = key.into().into_bytes()?;
+        let algorithm = CipherAlgorithm::from_key_length(key.len() * 8)?;
+        Self::new(algorithm, CipherMode::Cbc, key, None)
+    }
+    
+    /// Use a fixed IV instead of a random one per message
+    ///
+    /// Only intended for reproducible output such as test vectors.
+    pub fn with_iv(mut self, iv: Vec<u8>) -> AblyResult<Self> {
+        if iv.len() != 16 {
+            return Err(AblyError::invalid_request(format!(
+                "Invalid IV size: expected 16 bytes, got {}",
+                iv.len()
+            )));
+        }
+        self.iv = Some(iv);
+        Ok(self)
+    }
+    
+    /// Key length in bits
+    pub fn key_length(&self) -> usize {
+        self.algorithm.key_length()
+    }
+    
+    /// Algorithm name in Ably format, e.g. `aes-256-cbc`
+    pub fn algorithm_string(&self) -> String {
+        format!("{}-{}", self.algorithm, self.mode)
+    }
+    
+    /// Get the key as a base64 string
+    pub fn key_as_base64(&self) -> String {
+        base64::engine::general_purpose::STANDARD.encode(&self.key)
     }
     
     /// Generate a random IV if not provided
@@ -171,6 +264,11 @@ impl ChannelCipher {
         Self { params }
     }
     
+    /// Get the cipher params
+    pub fn params(&self) -> &CipherParams {
+        &self.params
+    }
+    
     /// Encrypt data
     pub fn encrypt(&self, plaintext: &[u8]) -> AblyResult<EncryptedData> {
         let iv = self.params.get_or_generate_iv();
@@ -326,81 +424,65 @@ impl MessageCrypto {
         }
     }
     
-    /// Encrypt a message
-    pub fn encrypt_message(&self, message: &mut crate::protocol::messages::Message) -> AblyResult<()> {
-        // Get message data as bytes
-        let plaintext = match &message.data {
-            Some(serde_json::Value::String(s)) => s.as_bytes().to_vec(),
-            Some(v) => serde_json::to_vec(v)
-                .map_err(|e| AblyError::unexpected(format!("Failed to serialize data: {}", e)))?,
-            None => return Ok(()), // Nothing to encrypt
-        };
-        
-        // Encrypt data
-        let encrypted = self.cipher.encrypt(&plaintext)?;
-        
-        // Update message with encrypted data
-        message.data = Some(serde_json::Value::String(encrypted.to_base64()));
-        
-        // Update encoding
-        let encoding = if let Some(ref existing) = message.encoding {
-            format!("{}/{}", encrypted.encoding, existing)
-        } else {
-            encrypted.encoding
-        };
-        message.encoding = Some(encoding);
+    /// Get the cipher params
+    pub fn params(&self) -> &CipherParams {
+        &self.cipher.params
+    }
+    
+    /// Cipher step name used in payload encodings, e.g. `cipher+aes-128-cbc`
+    pub fn cipher_encoding(&self) -> String {
+        format!("cipher+{}", self.cipher.params.algorithm_string())
+    }
+    
+    /// Encrypt bytes, returning the IV followed by the ciphertext
+    pub fn encrypt(&self, plaintext: &[u8]) -> AblyResult<Vec<u8>> {
+        let encrypted = self.cipher.encrypt(plaintext)?;
+        let mut combined = encrypted.iv;
+        combined.extend_from_slice(&encrypted.ciphertext);
+        Ok(combined)
+    }
+    
+    /// Decrypt bytes consisting of the IV followed by the ciphertext
+    pub fn decrypt(&self, data: &[u8]) -> AblyResult<Vec<u8>> {
+        if data.len() < 16 {
+            return Err(AblyError::encryption("Data too short for IV + ciphertext"));
+        }
         
-        Ok(())
+        let (iv, ciphertext) = data.split_at(16);
+        self.cipher.decrypt(&EncryptedData {
+            iv: iv.to_vec(),
+            ciphertext: ciphertext.to_vec(),
+            encoding: self.cipher_encoding(),
+        })
     }
     
-    /// Decrypt a message
+    /// Encode and encrypt a message for sending
+    pub fn encrypt_message(&self, message: &mut crate::protocol::messages::Message) -> AblyResult<()> {
+        data_encoding::encode(message, Some(self), EncodingFormat::Json)
+    }
+    
+    /// Decrypt and decode a received message
     pub fn decrypt_message(&self, message: &mut crate::protocol::messages::Message) -> AblyResult<()> {
-        // Check if message is encrypted
-        let encoding = match &message.encoding {
-            Some(e) if e.contains("cipher") => e.clone(),
-            _ => return Ok(()), // Not encrypted
-        };
-        
-        // Get encrypted data
-        let encrypted_str = match &message.data {
-            Some(serde_json::Value::String(s)) => s.clone(),
-            _ => return Err(AblyError::unexpected("Invalid encrypted data format")),
-        };
-        
-        // Parse encrypted data
-        let encrypted = EncryptedData::from_base64(&encrypted_str, encoding.clone())?;
-        
-        // Decrypt
-        let plaintext = self.cipher.decrypt(&encrypted)?;
-        
-        // Update message with decrypted data
-        let data_str = String::from_utf8(plaintext)
-            .map_err(|e| AblyError::unexpected(format!("Invalid UTF-8 in decrypted data: {}", e)))?;
-        
-        // Try to parse as JSON, otherwise keep as string
-        message.data = Some(
-            serde_json::from_str(&data_str)
-                .unwrap_or_else(|_| serde_json::Value::String(data_str))
-        );
-        
-        // Remove cipher from encoding
-        let new_encoding = encoding
-            .split('/')
-            .filter(|part| !part.contains("cipher"))
-            .collect::<Vec<_>>()
-            .join("/");
-        
-        message.encoding = if new_encoding.is_empty() {
-            None
-        } else {
-            Some(new_encoding)
-        };
-        
-        Ok(())
+        data_encoding::decode(message, Some(self))
+    }
+    
+    /// Encode and encrypt presence message data
+    pub fn encrypt_presence(&self, message: &mut crate::protocol::messages::PresenceMessage) -> AblyResult<()> {
+        data_encoding::encode(message, Some(self), EncodingFormat::Json)
+    }
+    
+    /// Decrypt and decode presence message data
+    pub fn decrypt_presence(&self, message: &mut crate::protocol::messages::PresenceMessage) -> AblyResult<()> {
+        data_encoding::decode(message, Some(self))
     }
 }
 
-/// Generate a random key
+/// Get default AES-CBC cipher params for a key (RSE1)
+pub fn get_default_params(key: impl Into<CipherKey>) -> AblyResult<CipherParams> {
+    CipherParams::from_key(key)
+}
+
+/// Generate a random key (RSE2)
 pub fn generate_random_key(algorithm: CipherAlgorithm) -> Vec<u8> {
     let key_size = match algorithm {
         CipherAlgorithm::Aes128 => 16,
@@ -416,4 +498,82 @@ pub fn generate_random_key(algorithm: CipherAlgorithm) -> Vec<u8> {
 pub fn generate_random_key_string(algorithm: CipherAlgorithm) -> String {
     let key = generate_random_key(algorithm);
     base64::engine::general_purpose::STANDARD.encode(&key)
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::messages::{Message, MessageData};
+    use serde_json::json;
+
+    fn crypto() -> MessageCrypto {
+        MessageCrypto::new(CipherParams::aes128_cbc(b"0123456789abcdef".to_vec()).unwrap())
+    }
+
+    #[test]
+    fn test_encrypt_decrypt_string_and_json() {
+        let crypto = crypto();
+        
+        for data in [json!("hello"), json!({ "a": 1 }), json!("123")] {
+            let mut message = Message {
+                data: Some(data.clone().into()),
+                ..Default::default()
+            };
+            
+            crypto.encrypt_message(&mut message).unwrap();
+            let expected = if data.is_string() { "utf-8" } else { "json/utf-8" };
+            assert_eq!(message.encoding, Some(format!("{}/cipher+aes-128-cbc/base64", expected)));
+            
+            crypto.decrypt_message(&mut message).unwrap();
+            assert_eq!(message.data, Some(data.into()));
+            assert!(message.encoding.is_none());
+        }
+    }
+
+    #[test]
+    fn test_binary_payload_round_trip() {
+        let crypto = crypto();
+        let mut message = Message {
+            data: Some(json!("AAEC/w==").into()),
+            encoding: Some("base64".to_string()),
+            ..Default::default()
+        };
+        
+        crypto.encrypt_message(&mut message).unwrap();
+        assert_eq!(message.encoding.as_deref(), Some("cipher+aes-128-cbc/base64"));
+        
+        crypto.decrypt_message(&mut message).unwrap();
+        assert_eq!(message.data, Some(MessageData::Binary(vec![0, 1, 2, 255])));
+        assert!(message.encoding.is_none());
+    }
+
+    #[test]
+    fn test_params_from_bytes_and_base64_key() {
+        let from_bytes = CipherParams::from_key(&[5u8; 16]).unwrap();
+        assert_eq!(from_bytes.key_length(), 128);
+        assert_eq!(from_bytes.algorithm_string(), "aes-128-cbc");
+        
+        let from_base64 = get_default_params(from_bytes.key_as_base64()).unwrap();
+        assert_eq!(from_base64.key, from_bytes.key);
+        
+        let random = CipherParams::from_key(generate_random_key(CipherAlgorithm::Aes256)).unwrap();
+        assert_eq!(random.key_length(), 256);
+        
+        assert!(CipherParams::from_key(vec![0u8; 24]).is_err());
+        assert!(CipherParams::from_key("not base64!").is_err());
+        assert!(from_bytes.with_iv(vec![0u8; 8]).is_err());
+    }
+
+    #[test]
+    fn test_decrypt_with_wrong_key_fails() {
+        let mut message = Message {
+            data: Some("secret".into()),
+            ..Default::default()
+        };
+        crypto().encrypt_message(&mut message).unwrap();
+        
+        let other = MessageCrypto::new(CipherParams::aes256_cbc(vec![7u8; 32]).unwrap());
+        assert!(other.decrypt_message(&mut message).is_err());
+        assert_eq!(message.encoding.as_deref(), Some("utf-8/cipher+aes-128-cbc/base64"));
+    }
+}
@@ -58,9 +58,26 @@ impl ChannelDeltaHandler {
         
         let mut processor = self.processor.write().unwrap();
         
+        // Messages are discarded until the recovery reattach completes
+        if processor.is_recovery_in_progress() {
+            debug!(
+                channel = %self.channel_name,
+                "Discarding message received during delta decode failure recovery"
+            );
+            return Ok(Vec::new());
+        }
+        
+        let has_deltas = message.messages.as_ref()
+            .map(|messages| messages.iter().any(|m| {
+                m.extras.as_ref().map(|extras| extras.contains_key("delta")).unwrap_or(false)
+            }))
+            .unwrap_or(false);
+        
         match processor.process_message(message) {
             Ok(messages) => {
-                self.decode_stats.successful_decodes += 1;
+                if has_deltas {
+                    self.decode_stats.successful_decodes += 1;
+                }
                 debug!(
                     channel = %self.channel_name,
                     message_count = messages.len(),
@@ -103,9 +120,13 @@ impl ChannelDeltaHandler {
     }
     
     /// Complete decode failure recovery
-    pub fn complete_recovery(&self) {
+    pub fn complete_recovery(&mut self) {
         let mut processor = self.processor.write().unwrap();
+        if !processor.is_recovery_in_progress() {
+            return;
+        }
         processor.complete_decode_failure_recovery();
+        self.decode_stats.successful_recoveries += 1;
         
         debug!(
             channel = %self.channel_name,
@@ -137,21 +158,22 @@ impl ChannelDeltaHandler {
     
     /// Update base payload for next delta decode
     pub fn update_base_payload(&self, payload: Vec<u8>) {
-        // This would be called when a non-delta message is received
-        // to update the base for future delta messages
+        let payload_size = payload.len();
+        let mut processor = self.processor.write().unwrap();
+        processor.context.set_base_payload(payload);
+        
         debug!(
             channel = %self.channel_name,
-            payload_size = payload.len(),
+            payload_size,
             "Updated base payload for delta decoding"
         );
     }
     
     /// Check if error is recoverable through channel reattachment
     fn is_recoverable_delta_error(&self, error: &AblyError) -> bool {
-        // Check if error message indicates recoverable delta failure
+        // Any failure to decode a delta is recovered by reattaching (RTL18)
         let error_msg = format!("{}", error);
-        error_msg.contains("Delta message decode failure") ||
-        error_msg.contains("previous message not available")
+        matches!(error, AblyError::Decode { .. }) && !error_msg.contains("Missing Vcdiff decoder")
     }
     
     /// Generate channel attach message with delta recovery info
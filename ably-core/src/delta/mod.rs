@@ -3,7 +3,7 @@
 // Integration-First - real VCDIFF decoding for delta message handling
 
 use crate::error::{AblyError, AblyResult};
-use crate::protocol::messages::{Message, ProtocolMessage};
+use crate::protocol::messages::{Message, MessageData, ProtocolMessage};
 use serde_json::Value;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
@@ -12,7 +12,7 @@ pub mod vcdiff;
 pub mod channel_delta;
 
 pub use vcdiff::VcdiffDecoder;
-pub use channel_delta::ChannelDeltaHandler;
+pub use channel_delta::{ChannelDeltaHandler, DeltaChannelConfig, DeltaDecodeStats};
 
 /// Plugin interface for delta compression
 pub trait DeltaDecoder: Send + Sync + std::fmt::Debug {
@@ -123,73 +123,88 @@ impl DeltaProcessor {
     
     /// Process protocol message with potential delta encoding
     pub fn process_message(&mut self, message: &ProtocolMessage) -> AblyResult<Vec<Message>> {
-        // Handle messages array if present
-        if let Some(messages) = &message.messages {
-            if messages.is_empty() {
-                return Ok(Vec::new());
-            }
-            
-            let first_message = &messages[0];
-            let last_message = &messages[messages.len() - 1];
+        let messages = match &message.messages {
+            Some(messages) if !messages.is_empty() => messages,
+            _ => return Ok(Vec::new()),
+        };
+        
+        let mut decoded_messages = Vec::with_capacity(messages.len());
+        
+        for (index, message_in) in messages.iter().enumerate() {
+            let mut current = message_in.clone();
             
-            // Check for delta encoding
-            if let Some(extras) = &first_message.extras {
-                if let Some(delta) = extras.get("delta") {
-                    return self.process_delta_message(messages, delta, message);
-                }
+            // Messages without an id inherit "<protocol message id>:<index>"
+            if current.id.is_none() {
+                current.id = message.id.as_ref().map(|id| format!("{}:{}", id, index));
             }
             
-            // Non-delta message - update tracking
-            self.last_message_id = last_message.id.clone();
-            self.last_channel_serial = message.channel_serial.clone();
+            let decoded = match Self::delta_from(&current)? {
+                Some(delta_from) => {
+                    // Each delta must be relative to the message delivered just before it
+                    if Some(delta_from.as_str()) != self.last_message_id.as_deref() {
+                        let error_msg = format!(
+                            "Delta message decode failure - previous message not available for message \"{}\"",
+                            current.id.as_deref().unwrap_or("unknown")
+                        );
+                        return Err(AblyError::decoding(error_msg));
+                    }
+                    
+                    self.decode_delta_message(&current)?
+                }
+                None => {
+                    let base_payload = Self::base_payload(&current)?;
+                    self.context.set_base_payload(base_payload);
+                    current
+                }
+            };
             
-            return Ok(messages.clone());
+            self.last_message_id = decoded.id.clone();
+            decoded_messages.push(decoded);
         }
         
-        Ok(Vec::new())
+        // Update tracking
+        self.last_channel_serial = message.channel_serial.clone();
+        
+        Ok(decoded_messages)
     }
     
-    /// Process delta-encoded message
-    fn process_delta_message(
-        &mut self, 
-        messages: &[Message], 
-        delta: &Value,
-        protocol_message: &ProtocolMessage
-    ) -> AblyResult<Vec<Message>> {
-        let first_message = &messages[0];
+    /// Extract `extras.delta.from` if the message is delta encoded
+    fn delta_from(message: &Message) -> AblyResult<Option<String>> {
+        let delta = match message.extras.as_ref().and_then(|extras| extras.get("delta")) {
+            Some(delta) => delta,
+            None => return Ok(None),
+        };
         
-        // Extract delta information
-        let delta_from = delta.get("from")
+        delta.get("from")
             .and_then(|v| v.as_str())
-            .ok_or_else(|| AblyError::decoding("Missing delta.from field".to_string()))?;
-        
-        // Check if we have the required base message
-        if Some(delta_from) != self.last_message_id.as_deref() {
-            let error_msg = format!(
-                "Delta message decode failure - previous message not available for message \"{}\"",
-                first_message.id.as_deref().unwrap_or("unknown")
-            );
-            return Err(AblyError::decoding(error_msg));
-        }
-        
-        // Decode delta messages
-        let mut decoded_messages = Vec::new();
+            .map(|from| Some(from.to_string()))
+            .ok_or_else(|| AblyError::decoding("Missing delta.from field".to_string()))
+    }
+    
+    /// Payload of a plain message as seen by the vcdiff layer (transport base64 removed)
+    fn base_payload(message: &Message) -> AblyResult<Vec<u8>> {
+        let is_base64 = message.encoding.as_deref()
+            .map(|encoding| encoding.ends_with("base64"))
+            .unwrap_or(false);
         
-        for message in messages {
-            let decoded_message = self.decode_delta_message(message)?;
-            decoded_messages.push(decoded_message);
+        match &message.data {
+            Some(MessageData::String(s)) if is_base64 => {
+                use base64::{Engine, engine::general_purpose::STANDARD};
+                STANDARD.decode(s)
+                    .map_err(|e| AblyError::decoding(format!("Failed to decode base64 payload: {}", e)))
+            }
+            Some(MessageData::String(s)) => Ok(s.as_bytes().to_vec()),
+            Some(MessageData::Binary(b)) => Ok(b.clone()),
+            Some(MessageData::Json(v)) => serde_json::to_vec(v)
+                .map_err(|e| AblyError::decoding(format!("Failed to serialize payload: {}", e))),
+            None => Ok(Vec::new()),
         }
-        
-        // Update tracking
-        let last_message = &messages[messages.len() - 1];
-        self.last_message_id = last_message.id.clone();
-        self.last_channel_serial = protocol_message.channel_serial.clone();
-        
-        Ok(decoded_messages)
     }
     
     /// Decode individual delta message
-    fn decode_delta_message(&self, message: &Message) -> AblyResult<Message> {
+    fn decode_delta_message(&mut self, message: &Message) -> AblyResult<Message> {
+        use base64::{Engine, engine::general_purpose::STANDARD};
+        
         // Get delta plugin
         let plugin = self.context.plugins.get("vcdiff")
             .ok_or_else(|| AblyError::decoding(
@@ -200,43 +215,69 @@ impl DeltaProcessor {
         let base_payload = self.context.base_encoded_previous_payload.as_ref()
             .ok_or_else(|| AblyError::decoding("No base payload available for delta decode".to_string()))?;
         
+        let mut encodings: Vec<&str> = message.encoding.as_deref()
+            .unwrap_or("")
+            .split('/')
+            .filter(|e| !e.is_empty())
+            .collect();
+        
         // Extract delta data from message
-        let delta_data = if let Some(data) = &message.data {
-            match data {
-                Value::String(s) => {
-                    // Assume base64 encoded delta data
-                    use base64::{Engine, engine::general_purpose::STANDARD};
-                    STANDARD.decode(s)
-                        .map_err(|e| AblyError::decoding(format!("Failed to decode base64 delta data: {}", e)))?
-                }
-                _ => {
-                    return Err(AblyError::decoding("Delta data must be base64 string".to_string()));
+        let delta_data = match &message.data {
+            Some(MessageData::String(s)) => {
+                // Deltas are binary, so a JSON transport always delivers them base64 encoded
+                if encodings.last() == Some(&"base64") {
+                    encodings.pop();
                 }
+                STANDARD.decode(s)
+                    .map_err(|e| AblyError::decoding(format!("Failed to decode base64 delta data: {}", e)))?
+            }
+            Some(MessageData::Binary(b)) => b.clone(),
+            Some(_) => {
+                return Err(AblyError::decoding("Delta data must be base64 string".to_string()));
+            }
+            None => {
+                return Err(AblyError::decoding("No delta data in message".to_string()));
             }
-        } else {
-            return Err(AblyError::decoding("No delta data in message".to_string()));
         };
         
+        if encodings.last() == Some(&"vcdiff") {
+            encodings.pop();
+        }
+        
         // Decode delta
         let decoded_data = plugin.decode_message(&delta_data, base_payload)
             .map_err(|e| AblyError::decoding(format!("Vcdiff delta decode failed: {}", e)))?;
         
+        // The reconstructed payload is the base for the next delta
+        self.context.set_base_payload(decoded_data.clone());
+        
+        // Unwind the encodings that were applied before the delta
+        let data = match encodings.last() {
+            Some(&"utf-8") | Some(&"json") => {
+                if encodings.last() == Some(&"utf-8") {
+                    encodings.pop();
+                }
+                let text = String::from_utf8(decoded_data)
+                    .map_err(|e| AblyError::decoding(format!("Invalid UTF-8 in delta payload: {}", e)))?;
+                
+                if encodings.last() == Some(&"json") {
+                    encodings.pop();
+                    serde_json::from_str::<Value>(&text)
+                        .map_err(|e| AblyError::decoding(format!("Invalid JSON in delta payload: {}", e)))?
+                        .into()
+                } else {
+                    MessageData::String(text)
+                }
+            }
+            // Binary, or still encrypted; remaining steps are left for the payload decoder
+            _ => MessageData::Binary(decoded_data),
+        };
+        
         // Create decoded message
         let decoded_message = Message {
-            id: message.id.clone(),
-            name: message.name.clone(),
-            data: Some(Value::String(String::from_utf8_lossy(&decoded_data).to_string())),
-            encoding: message.encoding.clone(),
-            timestamp: message.timestamp,
-            client_id: message.client_id.clone(),
-            connection_id: message.connection_id.clone(),
-            connection_key: message.connection_key.clone(),
-            extras: message.extras.clone(),
-            serial: message.serial.clone(),
-            created_at: message.created_at,
-            version: message.version.clone(),
-            action: message.action,
-            operation: message.operation.clone(),
+            data: Some(data),
+            encoding: if encodings.is_empty() { None } else { Some(encodings.join("/")) },
+            ..message.clone()
         };
         
         Ok(decoded_message)
@@ -311,6 +352,76 @@ mod tests {
         assert!(processor.last_channel_serial().is_none());
     }
     
+    /// Test decoder whose "delta" is appended to the source
+    #[derive(Debug)]
+    struct AppendDecoder;
+    
+    impl DeltaDecoder for AppendDecoder {
+        fn decode(&self, delta: &[u8], source: &[u8]) -> AblyResult<Vec<u8>> {
+            let mut output = source.to_vec();
+            output.extend_from_slice(delta);
+            Ok(output)
+        }
+    }
+    
+    fn delta_message(from: &str, delta: &str) -> Message {
+        use base64::{Engine, engine::general_purpose::STANDARD};
+        
+        let mut extras = HashMap::new();
+        extras.insert("delta".to_string(), json!({"from": from, "format": "vcdiff"}));
+        
+        Message {
+            data: Some(STANDARD.encode(delta).into()),
+            encoding: Some("utf-8/vcdiff/base64".to_string()),
+            extras: Some(extras),
+            ..Default::default()
+        }
+    }
+    
+    #[test]
+    fn test_delta_chain_decoding() {
+        let mut context = DeltaContext::new();
+        context.add_plugin("vcdiff", DeltaPlugin::new(AppendDecoder));
+        let mut processor = DeltaProcessor::new(context);
+        
+        let message = ProtocolMessage {
+            id: Some("conn:1".to_string()),
+            channel_serial: Some("serial-1".to_string()),
+            messages: Some(vec![
+                Message {
+                    data: Some("foo".into()),
+                    ..Default::default()
+                },
+                delta_message("conn:1:0", "bar"),
+                delta_message("conn:1:1", "baz"),
+            ]),
+            ..Default::default()
+        };
+        
+        let decoded = processor.process_message(&message).unwrap();
+        assert_eq!(decoded.len(), 3);
+        assert_eq!(decoded[1].id.as_deref(), Some("conn:1:1"));
+        assert_eq!(decoded[1].data, Some("foobar".into()));
+        assert_eq!(decoded[2].data, Some("foobarbaz".into()));
+        assert!(decoded[2].encoding.is_none());
+        assert_eq!(processor.last_channel_serial(), Some("serial-1"));
+    }
+    
+    #[test]
+    fn test_delta_without_base_message_fails() {
+        let mut context = DeltaContext::new();
+        context.add_plugin("vcdiff", DeltaPlugin::new(AppendDecoder));
+        let mut processor = DeltaProcessor::new(context);
+        
+        let message = ProtocolMessage {
+            messages: Some(vec![delta_message("missing:0", "bar")]),
+            ..Default::default()
+        };
+        
+        let error = processor.process_message(&message).unwrap_err();
+        assert!(error.to_string().contains("previous message not available"));
+    }
+    
     #[test]
     fn test_decode_failure_recovery() {
         let context = DeltaContext::new();
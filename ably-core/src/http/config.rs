@@ -2,6 +2,26 @@
 
 use std::time::Duration;
 
+/// Fallback hosts used with the default production endpoint (RSC15a)
+pub const DEFAULT_FALLBACK_HOSTS: &[&str] = &[
+    "a.ably-realtime.com",
+    "b.ably-realtime.com",
+    "c.ably-realtime.com",
+    "d.ably-realtime.com",
+    "e.ably-realtime.com",
+];
+
+/// Default fallback hosts for an environment (RSC15g)
+pub fn default_fallback_hosts(environment: &str) -> Vec<String> {
+    match environment {
+        "production" => DEFAULT_FALLBACK_HOSTS.iter().map(|host| host.to_string()).collect(),
+        env => ["a", "b", "c", "d", "e"]
+            .iter()
+            .map(|letter| format!("{}-{}-fallback.ably-realtime.com", env, letter))
+            .collect(),
+    }
+}
+
 /// HTTP client configuration
 #[derive(Debug, Clone)]
 pub struct HttpConfig {
@@ -13,10 +33,16 @@ pub struct HttpConfig {
     pub pool_idle_timeout: Option<Duration>,
     /// Maximum idle connections per host
     pub pool_max_idle_per_host: usize,
-    /// Maximum number of retries
+    /// Maximum number of retries against fallback hosts (`http_max_retry_count`)
     pub max_retries: u32,
     /// Base URL for API requests
     pub base_url: String,
+    /// Hosts tried, in random order, when the base URL fails
+    pub fallback_hosts: Vec<String>,
+    /// How long to keep using a fallback host that succeeded
+    pub fallback_retry_timeout: Duration,
+    /// Cumulative time allowed for a request and its retries (`http_max_retry_duration`)
+    pub max_retry_duration: Duration,
 }
 
 impl Default for HttpConfig {
@@ -28,6 +54,9 @@ impl Default for HttpConfig {
             pool_max_idle_per_host: 32,
             max_retries: 3,
             base_url: "https://rest.ably.io".to_string(),
+            fallback_hosts: default_fallback_hosts("production"),
+            fallback_retry_timeout: Duration::from_secs(600),
+            max_retry_duration: Duration::from_secs(15),
         }
     }
 }
@@ -48,6 +77,9 @@ pub struct HttpConfigBuilder {
     pool_max_idle_per_host: Option<usize>,
     max_retries: Option<u32>,
     base_url: Option<String>,
+    fallback_hosts: Option<Vec<String>>,
+    fallback_retry_timeout: Option<Duration>,
+    max_retry_duration: Option<Duration>,
 }
 
 impl HttpConfigBuilder {
@@ -81,15 +113,38 @@ impl HttpConfigBuilder {
         self
     }
 
-    /// Set base URL
+    /// Set base URL; unless fallback hosts are also given, a custom URL is not retried elsewhere (RSC15b)
     pub fn base_url(mut self, url: impl Into<String>) -> Self {
         self.base_url = Some(url.into());
         self
     }
 
+    /// Set fallback hosts; an empty list disables fallback
+    pub fn fallback_hosts(mut self, hosts: Vec<String>) -> Self {
+        self.fallback_hosts = Some(hosts);
+        self
+    }
+
+    /// Set how long a working fallback host is preferred
+    pub fn fallback_retry_timeout(mut self, timeout: Duration) -> Self {
+        self.fallback_retry_timeout = Some(timeout);
+        self
+    }
+
+    /// Set the cumulative time allowed for retries
+    pub fn max_retry_duration(mut self, duration: Duration) -> Self {
+        self.max_retry_duration = Some(duration);
+        self
+    }
+
     /// Build the configuration
     pub fn build(self) -> HttpConfig {
         let default = HttpConfig::default();
+        let fallback_hosts = match (self.fallback_hosts, &self.base_url) {
+            (Some(hosts), _) => hosts,
+            (None, Some(url)) if *url != default.base_url => Vec::new(),
+            (None, _) => default.fallback_hosts,
+        };
         HttpConfig {
             timeout: self.timeout.unwrap_or(default.timeout),
             connect_timeout: self.connect_timeout.unwrap_or(default.connect_timeout),
@@ -97,6 +152,27 @@ impl HttpConfigBuilder {
             pool_max_idle_per_host: self.pool_max_idle_per_host.unwrap_or(default.pool_max_idle_per_host),
             max_retries: self.max_retries.unwrap_or(default.max_retries),
             base_url: self.base_url.unwrap_or(default.base_url),
+            fallback_hosts,
+            fallback_retry_timeout: self.fallback_retry_timeout.unwrap_or(default.fallback_retry_timeout),
+            max_retry_duration: self.max_retry_duration.unwrap_or(default.max_retry_duration),
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_custom_base_url_has_no_fallbacks() {
+        let custom = HttpConfig::builder().base_url("https://proxy.example.com").build();
+        assert!(custom.fallback_hosts.is_empty());
+
+        let explicit = HttpConfig::builder()
+            .base_url("https://proxy.example.com")
+            .fallback_hosts(vec!["backup.example.com".to_string()])
+            .build();
+        assert_eq!(explicit.fallback_hosts, vec!["backup.example.com"]);
+
+        assert_eq!(HttpConfig::builder().build().fallback_hosts, default_fallback_hosts("production"));
+    }
+}
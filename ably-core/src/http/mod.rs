@@ -3,17 +3,19 @@
 
 use crate::auth::AuthMode;
 use crate::error::{AblyError, AblyResult};
+use crate::protocol::messages::ErrorInfo;
 use crate::retry::{RetryPolicy, RetryableError};
+use rand::seq::SliceRandom;
 use reqwest::{Client, RequestBuilder, Response};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 use thiserror::Error;
 use uuid::Uuid;
 use tracing::{debug, info, warn, error, instrument};
 
-pub use self::config::HttpConfig;
+pub use self::config::{default_fallback_hosts, HttpConfig, DEFAULT_FALLBACK_HOSTS};
 pub use self::resilience::{CircuitBreaker, RateLimiter, ConnectionMetrics};
 
 mod config;
@@ -72,11 +74,18 @@ pub struct AblyHttpClient {
     circuit_breaker: Option<Arc<CircuitBreaker>>,
     rate_limiter: Option<Arc<RateLimiter>>,
     metrics: Arc<ConnectionMetrics>,
+    fallback_hosts: Vec<String>,
+    fallback_retry_timeout: Duration,
+    max_retry_count: u32,
+    max_retry_duration: Duration,
+    /// Fallback base URL that last succeeded, and when it was first used (RSC15f)
+    preferred_fallback: Mutex<Option<(String, Instant)>>,
 }
 
 impl AblyHttpClient {
     /// Create new HTTP client with API key for integration tests
     pub fn new(api_key: &str) -> Self {
+        let config = HttpConfig::default();
         let client = Client::builder()
             .timeout(Duration::from_secs(30))
             .connect_timeout(Duration::from_secs(10))
@@ -94,11 +103,17 @@ impl AblyHttpClient {
             circuit_breaker: Some(Arc::new(CircuitBreaker::new(5, Duration::from_secs(60)))),
             rate_limiter: Some(Arc::new(RateLimiter::new(100, Duration::from_secs(1)))),
             metrics: Arc::new(ConnectionMetrics::default()),
+            fallback_hosts: config.fallback_hosts,
+            fallback_retry_timeout: config.fallback_retry_timeout,
+            max_retry_count: config.max_retries,
+            max_retry_duration: config.max_retry_duration,
+            preferred_fallback: Mutex::new(None),
         }
     }
 
     /// Create new HTTP client with custom timeout
     pub fn with_timeout(api_key: &str, timeout: Duration) -> Self {
+        let config = HttpConfig::default();
         let client = Client::builder()
             .timeout(timeout)
             .connect_timeout(Duration::from_secs(10))
@@ -116,6 +131,11 @@ impl AblyHttpClient {
             circuit_breaker: Some(Arc::new(CircuitBreaker::new(5, Duration::from_secs(60)))),
             rate_limiter: Some(Arc::new(RateLimiter::new(100, Duration::from_secs(1)))),
             metrics: Arc::new(ConnectionMetrics::default()),
+            fallback_hosts: config.fallback_hosts,
+            fallback_retry_timeout: config.fallback_retry_timeout,
+            max_retry_count: config.max_retries,
+            max_retry_duration: config.max_retry_duration,
+            preferred_fallback: Mutex::new(None),
         }
     }
 
@@ -139,6 +159,11 @@ impl AblyHttpClient {
             circuit_breaker: Some(Arc::new(CircuitBreaker::new(5, Duration::from_secs(60)))),
             rate_limiter: Some(Arc::new(RateLimiter::new(100, Duration::from_secs(1)))),
             metrics: Arc::new(ConnectionMetrics::default()),
+            fallback_hosts: config.fallback_hosts,
+            fallback_retry_timeout: config.fallback_retry_timeout,
+            max_retry_count: config.max_retries,
+            max_retry_duration: config.max_retry_duration,
+            preferred_fallback: Mutex::new(None),
         }
     }
 
@@ -159,24 +184,19 @@ impl AblyHttpClient {
         self.default_headers.push((key.into(), value.into()));
     }
 
+    /// Create a request builder for any method
+    pub fn request(&self, method: HttpMethod, url: &str) -> HttpRequestBuilder<'_> {
+        HttpRequestBuilder::new(self, method, url)
+    }
+
     /// Create a GET request builder
     pub fn get(&self, url: &str) -> HttpRequestBuilder {
-        let full_url = if url.starts_with("http") {
-            url.to_string()
-        } else {
-            format!("{}{}", self.base_url, url)
-        };
-        HttpRequestBuilder::new(self, HttpMethod::Get, &full_url)
+        HttpRequestBuilder::new(self, HttpMethod::Get, url)
     }
 
     /// Create a POST request builder
     pub fn post(&self, url: &str) -> HttpRequestBuilder {
-        let full_url = if url.starts_with("http") {
-            url.to_string()
-        } else {
-            format!("{}{}", self.base_url, url)
-        };
-        HttpRequestBuilder::new(self, HttpMethod::Post, &full_url)
+        HttpRequestBuilder::new(self, HttpMethod::Post, url)
     }
 
     /// Simplified async GET method for integration tests
@@ -289,34 +309,65 @@ impl AblyHttpClient {
 
     /// Create a PUT request builder
     pub fn put(&self, url: &str) -> HttpRequestBuilder {
-        let full_url = if url.starts_with("http") {
-            url.to_string()
-        } else {
-            format!("{}{}", self.base_url, url)
-        };
-        HttpRequestBuilder::new(self, HttpMethod::Put, &full_url)
+        HttpRequestBuilder::new(self, HttpMethod::Put, url)
     }
 
     /// Create a DELETE request builder
     pub fn delete(&self, url: &str) -> HttpRequestBuilder {
-        let full_url = if url.starts_with("http") {
-            url.to_string()
-        } else {
-            format!("{}{}", self.base_url, url)
-        };
-        HttpRequestBuilder::new(self, HttpMethod::Delete, &full_url)
+        HttpRequestBuilder::new(self, HttpMethod::Delete, url)
     }
 
     /// Create a PATCH request builder
     pub fn patch(&self, url: &str) -> HttpRequestBuilder {
-        let full_url = if url.starts_with("http") {
-            url.to_string()
-        } else {
-            format!("{}{}", self.base_url, url)
-        };
-        HttpRequestBuilder::new(self, HttpMethod::Patch, &full_url)
+        HttpRequestBuilder::new(self, HttpMethod::Patch, url)
     }
 
+    /// Base URLs to try for a request: the preferred or primary host first, then shuffled fallbacks (RSC15a)
+    fn request_hosts(&self) -> Vec<String> {
+        let scheme = self.base_url.split("://").next().unwrap_or("https");
+        let mut fallbacks: Vec<String> = self.fallback_hosts
+            .iter()
+            .map(|host| format!("{}://{}", scheme, host))
+            .collect();
+        fallbacks.shuffle(&mut rand::thread_rng());
+        
+        let mut hosts = vec![self.base_url.clone()];
+        if let Some(preferred) = self.preferred_fallback() {
+            fallbacks.retain(|host| *host != preferred);
+            hosts.insert(0, preferred);
+        }
+        hosts.extend(fallbacks);
+        hosts
+    }
+    
+    /// Fallback that recently succeeded, until `fallback_retry_timeout` expires (RSC15f)
+    fn preferred_fallback(&self) -> Option<String> {
+        let mut preferred = self.preferred_fallback.lock().unwrap();
+        match preferred.as_ref() {
+            Some((host, since)) if since.elapsed() < self.fallback_retry_timeout => Some(host.clone()),
+            _ => {
+                *preferred = None;
+                None
+            }
+        }
+    }
+    
+    fn record_host_success(&self, host: &str) {
+        let mut preferred = self.preferred_fallback.lock().unwrap();
+        if host == self.base_url {
+            *preferred = None;
+        } else if preferred.as_ref().map(|(current, _)| current.as_str()) != Some(host) {
+            *preferred = Some((host.to_string(), Instant::now()));
+        }
+    }
+    
+    fn record_host_failure(&self, host: &str) {
+        let mut preferred = self.preferred_fallback.lock().unwrap();
+        if preferred.as_ref().map(|(current, _)| current.as_str()) == Some(host) {
+            *preferred = None;
+        }
+    }
+    
     /// Apply authentication to request
     fn apply_auth(&self, request: RequestBuilder) -> RequestBuilder {
         match &self.auth_mode {
@@ -339,6 +390,8 @@ pub struct HttpRequestBuilder<'a> {
     client: &'a AblyHttpClient,
     method: HttpMethod,
     url: String,
+    /// Path relative to the client's hosts; None for absolute URLs, which never fall back
+    path: Option<String>,
     headers: Vec<(String, String)>,
     query_params: Vec<(String, String)>,
     body: Option<Vec<u8>>,
@@ -347,10 +400,16 @@ pub struct HttpRequestBuilder<'a> {
 impl<'a> HttpRequestBuilder<'a> {
     fn new(client: &'a AblyHttpClient, method: HttpMethod, url: &str) -> Self {
         let headers = client.default_headers.clone();
+        let (url, path) = if url.starts_with("http") {
+            (url.to_string(), None)
+        } else {
+            (format!("{}{}", client.base_url, url), Some(url.to_string()))
+        };
         Self {
             client,
             method,
-            url: url.to_string(),
+            url,
+            path,
             headers,
             query_params: Vec::new(),
             body: None,
@@ -384,22 +443,70 @@ impl<'a> HttpRequestBuilder<'a> {
         self
     }
 
-    /// Send the request and parse response as JSON
-    pub async fn send_json<T: DeserializeOwned>(self) -> AblyResult<T> {
+    /// Send the request, retrying against fallback hosts on network errors and 5xx responses (RSC15)
+    async fn execute(&self) -> AblyResult<Response> {
+        let hosts = match &self.path {
+            Some(_) => self.client.request_hosts(),
+            None => vec![self.url.clone()],
+        };
+        let started = Instant::now();
+        let mut last_result = None;
+        
+        for (attempt, host) in hosts.iter().enumerate() {
+            if attempt > 0 {
+                if attempt as u32 > self.client.max_retry_count || started.elapsed() >= self.client.max_retry_duration {
+                    break;
+                }
+                debug!("Retrying request on fallback host {}", host);
+            }
+            
+            let url = match &self.path {
+                Some(path) => format!("{}{}", host, path),
+                None => self.url.clone(),
+            };
+            let result = self.attempt(&url).await;
+            let retryable = match &result {
+                Ok(response) => is_retryable_status(response),
+                Err(e) => e.is_retryable(),
+            };
+            
+            if self.path.is_some() {
+                if retryable {
+                    self.client.record_host_failure(host);
+                } else if result.is_ok() {
+                    self.client.record_host_success(host);
+                }
+            }
+            if !retryable {
+                return result;
+            }
+            
+            match &result {
+                Ok(response) => warn!("Request to {} failed with status {}", host, response.status()),
+                Err(e) => warn!("Request to {} failed: {}", host, e),
+            }
+            last_result = Some(result);
+        }
+        
+        last_result.unwrap_or_else(|| Err(AblyError::network("No hosts available for request")))
+    }
+    
+    /// Send a single attempt of the request to the given URL
+    async fn attempt(&self, url: &str) -> AblyResult<Response> {
         let mut request = match self.method {
-            HttpMethod::Get => self.client.client.get(&self.url),
-            HttpMethod::Post => self.client.client.post(&self.url),
-            HttpMethod::Put => self.client.client.put(&self.url),
-            HttpMethod::Delete => self.client.client.delete(&self.url),
-            HttpMethod::Patch => self.client.client.patch(&self.url),
+            HttpMethod::Get => self.client.client.get(url),
+            HttpMethod::Post => self.client.client.post(url),
+            HttpMethod::Put => self.client.client.put(url),
+            HttpMethod::Delete => self.client.client.delete(url),
+            HttpMethod::Patch => self.client.client.patch(url),
         };
 
         // Apply authentication
         request = self.client.apply_auth(request);
 
         // Add headers
-        for (key, value) in self.headers {
-            request = request.header(&key, &value);
+        for (key, value) in &self.headers {
+            request = request.header(key, value);
         }
 
         // Add query parameters
@@ -408,12 +515,12 @@ impl<'a> HttpRequestBuilder<'a> {
         }
 
         // Add body if present
-        if let Some(body) = self.body {
-            request = request.body(body);
+        if let Some(body) = &self.body {
+            request = request.body(body.clone());
         }
 
         // Send request
-        let response = request.send().await.map_err(|e| {
+        request.send().await.map_err(|e| {
             if e.is_timeout() {
                 AblyError::timeout(format!("Request timeout: {}", e))
             } else if e.is_connect() {
@@ -421,7 +528,12 @@ impl<'a> HttpRequestBuilder<'a> {
             } else {
                 AblyError::network(format!("Network error: {}", e))
             }
-        })?;
+        })
+    }
+
+    /// Send the request and parse response as JSON
+    pub async fn send_json<T: DeserializeOwned>(self) -> AblyResult<T> {
+        let response = self.execute().await?;
 
         // Parse response
         let status = response.status();
@@ -437,47 +549,22 @@ impl<'a> HttpRequestBuilder<'a> {
 
     /// Send the request and get raw response
     pub async fn send(self) -> AblyResult<HttpResponse> {
-        let mut request = match self.method {
-            HttpMethod::Get => self.client.client.get(&self.url),
-            HttpMethod::Post => self.client.client.post(&self.url),
-            HttpMethod::Put => self.client.client.put(&self.url),
-            HttpMethod::Delete => self.client.client.delete(&self.url),
-            HttpMethod::Patch => self.client.client.patch(&self.url),
-        };
-
-        // Apply authentication
-        request = self.client.apply_auth(request);
-
-        // Add headers
-        for (key, value) in self.headers {
-            request = request.header(&key, &value);
-        }
-
-        // Add query parameters
-        if !self.query_params.is_empty() {
-            request = request.query(&self.query_params);
-        }
-
-        // Add body if present
-        if let Some(body) = self.body {
-            request = request.body(body);
-        }
-
-        // Send request
-        let response = request.send().await.map_err(|e| {
-            if e.is_timeout() {
-                AblyError::timeout(format!("Request timeout: {}", e))
-            } else if e.is_connect() {
-                AblyError::connection_failed(format!("Connection failed: {}", e))
-            } else {
-                AblyError::network(format!("Network error: {}", e))
-            }
-        })?;
+        let response = self.execute().await?;
 
         Ok(HttpResponse { inner: response })
     }
 }
 
+/// Whether a response should be retried on a fallback host (RSC15l)
+fn is_retryable_status(response: &Response) -> bool {
+    let status = response.status().as_u16();
+    let from_cloudfront = response.headers()
+        .get("server")
+        .and_then(|server| server.to_str().ok())
+        .is_some_and(|server| server.contains("CloudFront"));
+    (500..=504).contains(&status) || (status >= 400 && from_cloudfront)
+}
+
 /// HTTP response wrapper
 #[derive(Debug)]
 pub struct HttpResponse {
@@ -495,6 +582,25 @@ impl HttpResponse {
         self.inner.headers()
     }
 
+    /// Fail on a non-2xx status, with the error described by the `ErrorInfo` body
+    pub async fn error_for_status(self) -> AblyResult<Self> {
+        let status = self.status();
+        if status.is_success() {
+            return Ok(self);
+        }
+        
+        let body = self.inner.bytes().await.unwrap_or_default();
+        let error = serde_json::from_slice::<serde_json::Value>(&body)
+            .ok()
+            .and_then(|mut body| serde_json::from_value::<ErrorInfo>(body["error"].take()).ok())
+            .unwrap_or_default();
+        let message = error.message.unwrap_or_else(|| format!("Request failed with status {}", status));
+        Err(match error.code {
+            0 => AblyError::api(status.as_u16(), message),
+            code => AblyError::from_ably_code(code, &message),
+        })
+    }
+
     /// Parse response as JSON
     pub async fn json<T: DeserializeOwned>(self) -> AblyResult<T> {
         self.inner
@@ -519,4 +625,81 @@ impl HttpResponse {
             .map(|b| b.to_vec())
             .map_err(|e| AblyError::network(format!("Failed to read response: {}", e)))
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+pub(crate) mod mock;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mock::MockServer;
+
+    fn client(primary: &str, fallbacks: Vec<String>, max_retries: u32) -> AblyHttpClient {
+        let config = HttpConfig::builder()
+            .base_url(format!("http://{}", primary))
+            .fallback_hosts(fallbacks)
+            .max_retries(max_retries)
+            .build();
+        AblyHttpClient::from_config(config)
+    }
+
+    #[tokio::test]
+    async fn test_falls_back_on_server_error_and_prefers_working_host() {
+        let primary = MockServer::status(500).await;
+        let fallback = MockServer::status(200).await;
+        let client = client(&primary.host, vec![fallback.host.clone()], 3);
+
+        let response = client.get("/time").send().await.unwrap();
+        assert_eq!(response.status().as_u16(), 200);
+        assert_eq!(primary.hits(), 1);
+        assert_eq!(fallback.hits(), 1);
+
+        // The working fallback is used first until fallback_retry_timeout expires
+        client.get("/time").send().await.unwrap();
+        assert_eq!(primary.hits(), 1);
+        assert_eq!(fallback.hits(), 2);
+        assert_eq!(client.preferred_fallback(), Some(fallback.url()));
+    }
+
+    #[tokio::test]
+    async fn test_falls_back_on_connection_failure() {
+        let primary = MockServer::closed_host().await;
+        let fallback = MockServer::status(200).await;
+        let client = client(&primary, vec![fallback.host.clone()], 3);
+
+        let response = client.post("/channels/test/messages").json(&serde_json::json!({})).send().await.unwrap();
+        assert_eq!(response.status().as_u16(), 200);
+        assert_eq!(fallback.hits(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_client_errors_are_not_retried() {
+        let primary = MockServer::status(401).await;
+        let fallback = MockServer::status(200).await;
+        let client = client(&primary.host, vec![fallback.host.clone()], 3);
+
+        let response = client.get("/time").send().await.unwrap();
+        assert_eq!(response.status().as_u16(), 401);
+        assert_eq!(primary.hits(), 1);
+        assert_eq!(fallback.hits(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_retries_bounded_by_max_retry_count() {
+        let primary = MockServer::status(503).await;
+        let mut fallbacks = Vec::new();
+        for _ in 0..3 {
+            fallbacks.push(MockServer::status(503).await);
+        }
+        let hosts: Vec<String> = fallbacks.iter().map(|server| server.host.clone()).collect();
+        let total_hits = || fallbacks.iter().map(MockServer::hits).sum::<usize>();
+
+        let response = client(&primary.host, hosts.clone(), 2).get("/time").send().await.unwrap();
+        assert_eq!(response.status().as_u16(), 503);
+        assert_eq!(total_hits(), 2);
+
+        client(&primary.host, hosts, 0).get("/time").send().await.unwrap();
+        assert_eq!(total_hits(), 2);
+    }
+}
@@ -3,7 +3,7 @@
 
 use crate::client::rest::RestClient;
 use crate::client::realtime::RealtimeClient;
-use crate::error::{AblyError, AblyResult};
+use crate::error::AblyResult;
 use crate::protocol::messages::{Message, ProtocolMessage};
 use async_trait::async_trait;
 use std::any::Any;
@@ -11,6 +11,12 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
+pub mod schema;
+pub mod signing;
+
+pub use schema::{ReceiveValidation, SchemaValidationPlugin};
+pub use signing::{SigningPlugin, SignatureStatus, VerificationFailure};
+
 /// Plugin trait for extending Ably functionality
 #[async_trait]
 pub trait Plugin: Send + Sync {
@@ -38,6 +44,16 @@ pub trait Plugin: Send + Sync {
         Ok(())
     }
     
+    /// Process incoming message delivered on a channel (defaults to `process_inbound`)
+    async fn process_channel_inbound(&self, _channel: &str, message: &mut Message) -> AblyResult<()> {
+        self.process_inbound(message).await
+    }
+    
+    /// Process outgoing message published on a channel (defaults to `process_outbound`)
+    async fn process_channel_outbound(&self, _channel: &str, message: &mut Message) -> AblyResult<()> {
+        self.process_outbound(message).await
+    }
+    
     /// Handle protocol message
     async fn handle_protocol(&self, message: &ProtocolMessage) -> AblyResult<()> {
         // Default: no-op
@@ -65,6 +81,13 @@ impl Default for PluginConfig {
 }
 
 /// Plugin manager for coordinating plugins
+///
+/// Clients run outgoing messages through plugins in registration order, before
+/// encoding and encryption. Delivered messages pass through them in reverse
+/// order, after decoding and decryption, so each plugin sees data the way it
+/// left it. Received protocol frames are handled in registration order before
+/// the client acts on them. Clones share the same plugins.
+#[derive(Clone)]
 pub struct PluginManager {
     plugins: Arc<RwLock<Vec<Box<dyn Plugin>>>>,
     configs: Arc<RwLock<HashMap<String, PluginConfig>>>,
@@ -101,11 +124,16 @@ impl PluginManager {
         Ok(())
     }
     
-    /// Process inbound message through all plugins
+    /// Check whether any plugins are registered
+    pub async fn is_empty(&self) -> bool {
+        self.plugins.read().await.is_empty()
+    }
+    
+    /// Process inbound message through all plugins, last registered first
     pub async fn process_inbound(&self, message: &mut Message) -> AblyResult<()> {
         let plugins = self.plugins.read().await;
         
-        for plugin in plugins.iter() {
+        for plugin in plugins.iter().rev() {
             plugin.process_inbound(message).await?;
         }
         
@@ -123,6 +151,28 @@ impl PluginManager {
         Ok(())
     }
     
+    /// Process a message delivered on a channel through all plugins, last registered first
+    pub async fn process_channel_inbound(&self, channel: &str, message: &mut Message) -> AblyResult<()> {
+        let plugins = self.plugins.read().await;
+        
+        for plugin in plugins.iter().rev() {
+            plugin.process_channel_inbound(channel, message).await?;
+        }
+        
+        Ok(())
+    }
+    
+    /// Process a message published on a channel through all plugins
+    pub async fn process_channel_outbound(&self, channel: &str, message: &mut Message) -> AblyResult<()> {
+        let plugins = self.plugins.read().await;
+        
+        for plugin in plugins.iter() {
+            plugin.process_channel_outbound(channel, message).await?;
+        }
+        
+        Ok(())
+    }
+    
     /// Handle protocol message with all plugins
     pub async fn handle_protocol(&self, message: &ProtocolMessage) -> AblyResult<()> {
         let plugins = self.plugins.read().await;
@@ -164,6 +214,12 @@ impl PluginManager {
     }
 }
 
+impl Default for PluginManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Built-in logging plugin
 pub struct LoggingPlugin {
     level: String,
@@ -274,12 +330,14 @@ impl Plugin for MetricsPlugin {
 
 /// Built-in encryption plugin wrapper
 pub struct EncryptionPlugin {
-    cipher: crate::crypto::ChannelCipher,
+    crypto: crate::crypto::MessageCrypto,
 }
 
 impl EncryptionPlugin {
-    pub fn new(cipher: crate::crypto::ChannelCipher) -> Self {
-        Self { cipher }
+    pub fn new(params: crate::crypto::CipherParams) -> Self {
+        Self {
+            crypto: crate::crypto::MessageCrypto::new(params),
+        }
     }
 }
 
@@ -303,35 +361,12 @@ impl Plugin for EncryptionPlugin {
     
     async fn process_outbound(&self, message: &mut Message) -> AblyResult<()> {
         // Encrypt message data before sending
-        if let Some(data) = &message.data {
-            let json_str = serde_json::to_string(data)
-                .map_err(|e| AblyError::unexpected(format!("Failed to serialize: {}", e)))?;
-            let encrypted = self.cipher.encrypt(json_str.as_bytes())?;
-            let encoded = encrypted.to_base64();
-            
-            message.data = Some(serde_json::Value::String(encoded));
-            message.encoding = Some("utf-8/cipher+aes-128-cbc/base64".to_string());
-        }
-        Ok(())
+        self.crypto.encrypt_message(message)
     }
     
     async fn process_inbound(&self, message: &mut Message) -> AblyResult<()> {
         // Decrypt message data after receiving
-        if let Some(encoding) = &message.encoding {
-            if encoding.contains("cipher") {
-                if let Some(serde_json::Value::String(encoded)) = &message.data {
-                    use crate::crypto::EncryptedData;
-                    let encrypted = EncryptedData::from_base64(encoded, encoding.clone())?;
-                    let decrypted = self.cipher.decrypt(&encrypted)?;
-                    let json_str = String::from_utf8(decrypted)
-                        .map_err(|e| AblyError::unexpected(format!("Failed to decode UTF-8: {}", e)))?;
-                    message.data = Some(serde_json::from_str(&json_str)
-                        .map_err(|e| AblyError::unexpected(format!("Failed to parse JSON: {}", e)))?);
-                    message.encoding = None; // Clear encoding after decryption
-                }
-            }
-        }
-        Ok(())
+        self.crypto.decrypt_message(message)
     }
     
     fn as_any(&self) -> &dyn Any {
@@ -351,6 +386,18 @@ pub trait PluginClient {
     }
 }
 
+impl PluginClient for RestClient {
+    fn plugin_manager(&self) -> &PluginManager {
+        self.plugins()
+    }
+}
+
+impl PluginClient for RealtimeClient {
+    fn plugin_manager(&self) -> &PluginManager {
+        self.plugins()
+    }
+}
+
 // Tests for plugin system
 #[cfg(test)]
 mod tests {
@@ -373,7 +420,7 @@ mod tests {
         
         let mut message = Message {
             name: Some("test".to_string()),
-            data: Some(serde_json::json!({"key": "value"})),
+            data: Some(serde_json::json!({"key": "value"}).into()),
             ..Default::default()
         };
         
@@ -0,0 +1,347 @@
+// JSON Schema validation plugin
+// Checks message payloads against schemas registered per channel and message name
+
+use super::{Plugin, PluginConfig};
+use crate::error::{AblyError, AblyResult};
+use crate::protocol::messages::{Message, MessageData};
+use async_trait::async_trait;
+use jsonschema::Validator;
+use serde_json::{Map, Value};
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// Header set on received messages that fail validation under `ReceiveValidation::Flag`
+pub const SCHEMA_VIOLATION_HEADER: &str = "schema-violation";
+
+/// What to do with received messages that fail validation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReceiveValidation {
+    /// Only validate on publish
+    #[default]
+    Off,
+    /// Deliver the message with the violation recorded in `extras.headers`
+    Flag,
+    /// Reject the message so it is not delivered
+    Drop,
+}
+
+struct SchemaRule {
+    channel: String,
+    name: String,
+    validator: Validator,
+}
+
+impl SchemaRule {
+    fn applies_to(&self, channel: &str, message: &Message) -> bool {
+        glob_match(&self.channel, channel) && glob_match(&self.name, message.name.as_deref().unwrap_or_default())
+    }
+}
+
+/// Built-in JSON Schema validation plugin
+///
+/// Schemas are registered against channel and message name patterns, where `*`
+/// matches any run of characters. A message is checked against every matching
+/// schema; messages matching none pass untouched. Clones share rules and
+/// counters, so keep one to read metrics after registering the plugin.
+#[derive(Clone)]
+pub struct SchemaValidationPlugin {
+    rules: Vec<Arc<SchemaRule>>,
+    on_receive: ReceiveValidation,
+    validated_count: Arc<RwLock<u64>>,
+    outbound_violations: Arc<RwLock<u64>>,
+    inbound_violations: Arc<RwLock<u64>>,
+    violations_by_name: Arc<RwLock<HashMap<String, u64>>>,
+}
+
+impl SchemaValidationPlugin {
+    pub fn new() -> Self {
+        Self {
+            rules: Vec::new(),
+            on_receive: ReceiveValidation::default(),
+            validated_count: Arc::new(RwLock::new(0)),
+            outbound_violations: Arc::new(RwLock::new(0)),
+            inbound_violations: Arc::new(RwLock::new(0)),
+            violations_by_name: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Require messages matching the channel and name patterns to conform to a schema
+    pub fn schema(mut self, channel: &str, name: &str, schema: &Value) -> AblyResult<Self> {
+        let validator = jsonschema::validator_for(schema)
+            .map_err(|e| AblyError::invalid_request(format!("Invalid JSON Schema for {}/{}: {}", channel, name, e)))?;
+        self.rules.push(Arc::new(SchemaRule {
+            channel: channel.to_string(),
+            name: name.to_string(),
+            validator,
+        }));
+        Ok(self)
+    }
+
+    /// Set how received messages are validated
+    pub fn on_receive(mut self, on_receive: ReceiveValidation) -> Self {
+        self.on_receive = on_receive;
+        self
+    }
+
+    /// Number of messages checked against at least one schema
+    pub async fn get_validated_count(&self) -> u64 {
+        *self.validated_count.read().await
+    }
+
+    /// Number of publishes rejected for violating a schema
+    pub async fn get_outbound_violation_count(&self) -> u64 {
+        *self.outbound_violations.read().await
+    }
+
+    /// Number of received messages that violated a schema
+    pub async fn get_inbound_violation_count(&self) -> u64 {
+        *self.inbound_violations.read().await
+    }
+
+    /// Violations in both directions, keyed by `channel/name`
+    pub async fn get_violations_by_name(&self) -> HashMap<String, u64> {
+        self.violations_by_name.read().await.clone()
+    }
+
+    /// Check a message against every schema matching its channel and name
+    pub fn validate(&self, channel: &str, message: &Message) -> Result<(), String> {
+        let data = match &message.data {
+            None => Value::Null,
+            Some(MessageData::String(s)) => Value::String(s.clone()),
+            Some(MessageData::Json(v)) => v.clone(),
+            Some(MessageData::Binary(_)) if self.matches(channel, message) => {
+                return Err("binary payloads cannot be validated against a JSON Schema".to_string());
+            }
+            Some(MessageData::Binary(_)) => return Ok(()),
+        };
+
+        for rule in self.rules.iter().filter(|rule| rule.applies_to(channel, message)) {
+            if let Err(e) = rule.validator.validate(&data) {
+                return Err(format!("{} at '{}'", e, e.instance_path));
+            }
+        }
+        Ok(())
+    }
+
+    fn matches(&self, channel: &str, message: &Message) -> bool {
+        self.rules.iter().any(|rule| rule.applies_to(channel, message))
+    }
+
+    async fn record(&self, channel: &str, message: &Message, counter: Option<&RwLock<u64>>) {
+        *self.validated_count.write().await += 1;
+        if let Some(counter) = counter {
+            *counter.write().await += 1;
+            let key = format!("{}/{}", channel, message.name.as_deref().unwrap_or_default());
+            *self.violations_by_name.write().await.entry(key).or_insert(0) += 1;
+        }
+    }
+}
+
+impl Default for SchemaValidationPlugin {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Match `value` against a pattern where `*` matches any run of characters
+fn glob_match(pattern: &str, value: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = value.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        // No wildcard: the pattern must match exactly
+        return rest.is_empty();
+    };
+
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+#[async_trait]
+impl Plugin for SchemaValidationPlugin {
+    fn name(&self) -> &str {
+        "schema-validation"
+    }
+
+    fn version(&self) -> &str {
+        "1.0.0"
+    }
+
+    async fn initialize(&mut self, config: PluginConfig) -> AblyResult<()> {
+        if let Some(on_receive) = config.settings.get("on_receive") {
+            self.on_receive = match on_receive.as_str() {
+                "off" => ReceiveValidation::Off,
+                "flag" => ReceiveValidation::Flag,
+                "drop" => ReceiveValidation::Drop,
+                other => {
+                    return Err(AblyError::invalid_request(format!("Unknown on_receive setting '{}'", other)))
+                }
+            };
+        }
+        Ok(())
+    }
+
+    async fn shutdown(&mut self) -> AblyResult<()> {
+        Ok(())
+    }
+
+    async fn process_channel_outbound(&self, channel: &str, message: &mut Message) -> AblyResult<()> {
+        if !self.matches(channel, message) {
+            return Ok(());
+        }
+
+        match self.validate(channel, message) {
+            Ok(()) => {
+                self.record(channel, message, None).await;
+                Ok(())
+            }
+            Err(violation) => {
+                self.record(channel, message, Some(&self.outbound_violations)).await;
+                Err(AblyError::invalid_request(format!(
+                    "Message '{}' on channel '{}' violates its schema: {}",
+                    message.name.as_deref().unwrap_or_default(),
+                    channel,
+                    violation
+                )))
+            }
+        }
+    }
+
+    async fn process_channel_inbound(&self, channel: &str, message: &mut Message) -> AblyResult<()> {
+        if self.on_receive == ReceiveValidation::Off || !self.matches(channel, message) {
+            return Ok(());
+        }
+
+        let violation = match self.validate(channel, message) {
+            Ok(()) => {
+                self.record(channel, message, None).await;
+                return Ok(());
+            }
+            Err(violation) => violation,
+        };
+        self.record(channel, message, Some(&self.inbound_violations)).await;
+
+        if self.on_receive == ReceiveValidation::Drop {
+            return Err(AblyError::invalid_request(format!(
+                "Message {} on channel '{}' violates its schema: {}",
+                message.id.as_deref().unwrap_or("<no id>"),
+                channel,
+                violation
+            )));
+        }
+
+        let headers = message
+            .extras
+            .get_or_insert_with(Default::default)
+            .entry("headers".to_string())
+            .or_insert_with(|| Value::Object(Map::new()));
+        if let Value::Object(headers) = headers {
+            headers.insert(SCHEMA_VIOLATION_HEADER.to_string(), Value::String(violation));
+        }
+        Ok(())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn order_schema() -> Value {
+        json!({
+            "type": "object",
+            "required": ["id", "amount"],
+            "properties": {
+                "id": { "type": "string" },
+                "amount": { "type": "number", "minimum": 0 }
+            }
+        })
+    }
+
+    fn order(data: Value) -> Message {
+        Message {
+            name: Some("created".to_string()),
+            data: Some(data.into()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("orders:*", "orders:eu"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("a*b*c", "a-b-b-c"));
+        assert!(glob_match("created", "created"));
+        assert!(!glob_match("created", "created2"));
+        assert!(!glob_match("orders:*", "payments:eu"));
+        assert!(!glob_match("a*a", "a"));
+    }
+
+    #[tokio::test]
+    async fn test_publish_rejected_before_network() {
+        let plugin = SchemaValidationPlugin::new()
+            .schema("orders:*", "created", &order_schema())
+            .unwrap();
+
+        let mut valid = order(json!({"id": "o-1", "amount": 5}));
+        plugin.process_channel_outbound("orders:eu", &mut valid).await.unwrap();
+
+        let mut invalid = order(json!({"id": "o-2", "amount": -1}));
+        let err = plugin.process_channel_outbound("orders:eu", &mut invalid).await.unwrap_err();
+        assert!(matches!(err, AblyError::BadRequest { .. }));
+        assert!(err.to_string().contains("/amount"));
+
+        // Other channels and names are not constrained
+        plugin.process_channel_outbound("payments", &mut invalid).await.unwrap();
+        invalid.name = Some("deleted".to_string());
+        plugin.process_channel_outbound("orders:eu", &mut invalid).await.unwrap();
+
+        assert_eq!(plugin.get_validated_count().await, 2);
+        assert_eq!(plugin.get_outbound_violation_count().await, 1);
+        assert_eq!(plugin.get_violations_by_name().await.get("orders:eu/created"), Some(&1));
+    }
+
+    #[tokio::test]
+    async fn test_receive_flag_and_drop() {
+        let flagging = SchemaValidationPlugin::new()
+            .schema("orders:*", "*", &order_schema())
+            .unwrap()
+            .on_receive(ReceiveValidation::Flag);
+        let metrics = flagging.clone();
+
+        let mut malformed = order(json!({"id": 7}));
+        flagging.process_channel_inbound("orders:us", &mut malformed).await.unwrap();
+        let headers = &malformed.extras.as_ref().unwrap()["headers"];
+        assert!(headers[SCHEMA_VIOLATION_HEADER].as_str().is_some());
+        assert_eq!(metrics.get_inbound_violation_count().await, 1);
+
+        let dropping = flagging.clone().on_receive(ReceiveValidation::Drop);
+        let mut malformed = order(json!("not an object"));
+        assert!(dropping.process_channel_inbound("orders:us", &mut malformed).await.is_err());
+        assert_eq!(metrics.get_inbound_violation_count().await, 2);
+
+        let ignoring = flagging.on_receive(ReceiveValidation::Off);
+        ignoring.process_channel_inbound("orders:us", &mut malformed).await.unwrap();
+    }
+
+    #[test]
+    fn test_invalid_schema_rejected() {
+        let result = SchemaValidationPlugin::new().schema("*", "*", &json!({"type": 12}));
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,524 @@
+// Ed25519 message signing plugin
+// Signs outgoing payloads and verifies incoming ones against publisher keys
+
+use super::{Plugin, PluginConfig};
+use crate::error::{AblyError, AblyResult};
+use crate::protocol::messages::{Message, MessageData};
+use async_trait::async_trait;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine as _;
+use ed25519_dalek::{Signature, Signer, Verifier};
+use serde_json::{Map, Value};
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+pub use ed25519_dalek::{SigningKey, VerifyingKey};
+
+/// Header in `extras.headers` carrying the base64 Ed25519 signature
+pub const SIGNATURE_HEADER: &str = "signature";
+
+/// Header in `extras.headers` naming the key a message was signed with
+pub const SIGNATURE_KEY_ID_HEADER: &str = "signature-key-id";
+
+/// Header in `extras.headers` carrying the signer's clock, in milliseconds since the epoch
+pub const SIGNATURE_TIMESTAMP_HEADER: &str = "signature-timestamp";
+
+/// Header set on received messages with the verification outcome
+pub const SIGNATURE_STATUS_HEADER: &str = "signature-status";
+
+/// Domain separator so signatures cannot be replayed for other protocols
+const SIGNING_CONTEXT: &[u8] = b"ably-message-signature-v2";
+
+/// Default allowed gap between the signed timestamp and the server's receive time
+const DEFAULT_MAX_CLOCK_SKEW: Duration = Duration::from_secs(120);
+
+/// Looks up the public key for a signing key id
+pub trait PublicKeyResolver: Send + Sync {
+    fn resolve(&self, key_id: &str) -> Option<VerifyingKey>;
+}
+
+impl PublicKeyResolver for HashMap<String, VerifyingKey> {
+    fn resolve(&self, key_id: &str) -> Option<VerifyingKey> {
+        self.get(key_id).copied()
+    }
+}
+
+impl<F> PublicKeyResolver for F
+where
+    F: Fn(&str) -> Option<VerifyingKey> + Send + Sync,
+{
+    fn resolve(&self, key_id: &str) -> Option<VerifyingKey> {
+        self(key_id)
+    }
+}
+
+/// Outcome of verifying a received message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureStatus {
+    Valid,
+    Invalid,
+    Missing,
+    UnknownKey,
+    /// Signed too long before the server received it, e.g. a replayed message
+    Expired,
+}
+
+impl SignatureStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SignatureStatus::Valid => "valid",
+            SignatureStatus::Invalid => "invalid",
+            SignatureStatus::Missing => "missing",
+            SignatureStatus::UnknownKey => "unknown-key",
+            SignatureStatus::Expired => "expired",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "valid" => Some(SignatureStatus::Valid),
+            "invalid" => Some(SignatureStatus::Invalid),
+            "missing" => Some(SignatureStatus::Missing),
+            "unknown-key" => Some(SignatureStatus::UnknownKey),
+            "expired" => Some(SignatureStatus::Expired),
+            _ => None,
+        }
+    }
+
+    /// Read the status recorded on a message by the signing plugin
+    ///
+    /// The plugin strips any status header a publisher set before recording
+    /// its own, so this is only meaningful when a `SigningPlugin` is registered.
+    pub fn of(message: &Message) -> Option<Self> {
+        header(message, SIGNATURE_STATUS_HEADER).and_then(Self::parse)
+    }
+}
+
+/// What to do with received messages that fail verification
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VerificationFailure {
+    /// Deliver the message with its status recorded in `extras.headers`
+    #[default]
+    Mark,
+    /// Reject the message with an error
+    Reject,
+}
+
+/// Built-in Ed25519 signing plugin
+///
+/// With a signing key, outgoing messages are signed over their channel, id,
+/// a signing timestamp, name and data, so a signed message does not verify
+/// on another channel or when re-sent later. With a resolver, incoming
+/// messages are verified and marked or rejected. Plugin settings `on_failure`
+/// (`mark`/`reject`), `require_signature` (`true`/`false`) and
+/// `max_clock_skew_ms` override the builder values.
+pub struct SigningPlugin {
+    signer: Option<(String, SigningKey)>,
+    resolver: Option<Arc<dyn PublicKeyResolver>>,
+    on_failure: VerificationFailure,
+    require_signature: bool,
+    max_clock_skew: Duration,
+}
+
+impl SigningPlugin {
+    /// Create a plugin that signs outgoing messages with the given key
+    pub fn new(key_id: impl Into<String>, key: SigningKey) -> Self {
+        Self {
+            signer: Some((key_id.into(), key)),
+            resolver: None,
+            on_failure: VerificationFailure::default(),
+            require_signature: false,
+            max_clock_skew: DEFAULT_MAX_CLOCK_SKEW,
+        }
+    }
+
+    /// Create a plugin that only verifies incoming messages
+    pub fn verifier(resolver: impl PublicKeyResolver + 'static) -> Self {
+        Self {
+            signer: None,
+            resolver: Some(Arc::new(resolver)),
+            on_failure: VerificationFailure::default(),
+            require_signature: false,
+            max_clock_skew: DEFAULT_MAX_CLOCK_SKEW,
+        }
+    }
+
+    /// Verify incoming messages with the given resolver
+    pub fn with_resolver(mut self, resolver: impl PublicKeyResolver + 'static) -> Self {
+        self.resolver = Some(Arc::new(resolver));
+        self
+    }
+
+    /// Set how messages that fail verification are handled
+    pub fn on_failure(mut self, on_failure: VerificationFailure) -> Self {
+        self.on_failure = on_failure;
+        self
+    }
+
+    /// Treat unsigned messages as failures
+    pub fn require_signature(mut self, require: bool) -> Self {
+        self.require_signature = require;
+        self
+    }
+
+    /// Allowed gap between the signed timestamp and the server's receive time (default 2 minutes)
+    pub fn max_clock_skew(mut self, skew: Duration) -> Self {
+        self.max_clock_skew = skew;
+        self
+    }
+
+    /// Public key matching the signing key, for sharing with subscribers
+    pub fn verifying_key(&self) -> Option<VerifyingKey> {
+        self.signer.as_ref().map(|(_, key)| key.verifying_key())
+    }
+
+    /// Sign a message for a channel, storing the signature, key id and timestamp in `extras.headers`
+    pub fn sign(&self, channel: &str, message: &mut Message) -> AblyResult<()> {
+        let (key_id, key) = self
+            .signer
+            .as_ref()
+            .ok_or_else(|| AblyError::invalid_request("Signing plugin has no signing key"))?;
+
+        let timestamp = chrono::Utc::now().timestamp_millis();
+        let signature = key.sign(&signing_payload(channel, message.id.as_deref(), timestamp, message)?);
+        let headers = headers_mut(message);
+        headers.insert(SIGNATURE_HEADER.to_string(), Value::String(BASE64.encode(signature.to_bytes())));
+        headers.insert(SIGNATURE_KEY_ID_HEADER.to_string(), Value::String(key_id.clone()));
+        headers.insert(SIGNATURE_TIMESTAMP_HEADER.to_string(), Value::String(timestamp.to_string()));
+        Ok(())
+    }
+
+    /// Check a message's signature for the channel it was received on, without modifying it
+    ///
+    /// Messages published without an id may be given one by the server, so the
+    /// signature is also accepted over the message without its id.
+    pub fn verify(&self, channel: &str, message: &Message) -> SignatureStatus {
+        let (Some(signature), Some(key_id), Some(timestamp)) = (
+            header(message, SIGNATURE_HEADER),
+            header(message, SIGNATURE_KEY_ID_HEADER),
+            header(message, SIGNATURE_TIMESTAMP_HEADER),
+        ) else {
+            return SignatureStatus::Missing;
+        };
+        let Ok(timestamp) = timestamp.parse::<i64>() else {
+            return SignatureStatus::Invalid;
+        };
+
+        let Some(public_key) = self.resolver.as_ref().and_then(|r| r.resolve(key_id)) else {
+            return SignatureStatus::UnknownKey;
+        };
+
+        let signature = match BASE64.decode(signature).ok().and_then(|b| Signature::from_slice(&b).ok()) {
+            Some(signature) => signature,
+            None => return SignatureStatus::Invalid,
+        };
+
+        let verifies = |id: Option<&str>| {
+            signing_payload(channel, id, timestamp, message)
+                .map(|payload| public_key.verify(&payload, &signature).is_ok())
+                .unwrap_or(false)
+        };
+        if !verifies(message.id.as_deref()) && (message.id.is_none() || !verifies(None)) {
+            return SignatureStatus::Invalid;
+        }
+
+        // Compare against the server's receive time, or the local clock for messages without one
+        let received = message.timestamp.unwrap_or_else(|| chrono::Utc::now().timestamp_millis());
+        if received.abs_diff(timestamp) > self.max_clock_skew.as_millis() as u64 {
+            return SignatureStatus::Expired;
+        }
+        SignatureStatus::Valid
+    }
+
+    fn is_failure(&self, status: SignatureStatus) -> bool {
+        match status {
+            SignatureStatus::Valid => false,
+            SignatureStatus::Missing => self.require_signature,
+            SignatureStatus::Invalid | SignatureStatus::UnknownKey | SignatureStatus::Expired => true,
+        }
+    }
+}
+
+/// Canonical bytes covered by the signature: context, channel, optional id,
+/// signing timestamp, name and typed data, with variable-length fields
+/// length-prefixed so field boundaries are unambiguous
+fn signing_payload(channel: &str, id: Option<&str>, timestamp: i64, message: &Message) -> AblyResult<Vec<u8>> {
+    let (tag, data): (u8, Vec<u8>) = match &message.data {
+        None => (0, Vec::new()),
+        Some(MessageData::String(s)) => (1, s.as_bytes().to_vec()),
+        Some(MessageData::Binary(b)) => (2, b.clone()),
+        Some(MessageData::Json(v)) => (3, serde_json::to_vec(v).map_err(|e| AblyError::encoding(e.to_string()))?),
+    };
+    let name = message.name.as_deref().unwrap_or_default().as_bytes();
+    let id = id.unwrap_or_default().as_bytes();
+
+    let mut payload = Vec::with_capacity(
+        SIGNING_CONTEXT.len() + channel.len() + id.len() + name.len() + data.len() + 29,
+    );
+    payload.extend_from_slice(SIGNING_CONTEXT);
+    for field in [channel.as_bytes(), id] {
+        payload.extend_from_slice(&(field.len() as u32).to_be_bytes());
+        payload.extend_from_slice(field);
+    }
+    payload.extend_from_slice(&timestamp.to_be_bytes());
+    payload.extend_from_slice(&(name.len() as u32).to_be_bytes());
+    payload.extend_from_slice(name);
+    payload.push(tag);
+    payload.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    payload.extend_from_slice(&data);
+    Ok(payload)
+}
+
+fn header<'a>(message: &'a Message, name: &str) -> Option<&'a str> {
+    message
+        .extras
+        .as_ref()
+        .and_then(|extras| extras.get("headers"))
+        .and_then(|headers| headers.get(name))
+        .and_then(Value::as_str)
+}
+
+fn remove_header(message: &mut Message, name: &str) {
+    if let Some(Value::Object(headers)) = message.extras.as_mut().and_then(|extras| extras.get_mut("headers")) {
+        headers.remove(name);
+    }
+}
+
+fn headers_mut(message: &mut Message) -> &mut Map<String, Value> {
+    let headers = message
+        .extras
+        .get_or_insert_with(Default::default)
+        .entry("headers".to_string())
+        .or_insert_with(|| Value::Object(Map::new()));
+    if !headers.is_object() {
+        *headers = Value::Object(Map::new());
+    }
+    headers.as_object_mut().unwrap()
+}
+
+#[async_trait]
+impl Plugin for SigningPlugin {
+    fn name(&self) -> &str {
+        "signing"
+    }
+
+    fn version(&self) -> &str {
+        "1.0.0"
+    }
+
+    async fn initialize(&mut self, config: PluginConfig) -> AblyResult<()> {
+        if let Some(on_failure) = config.settings.get("on_failure") {
+            self.on_failure = match on_failure.as_str() {
+                "mark" => VerificationFailure::Mark,
+                "reject" => VerificationFailure::Reject,
+                other => {
+                    return Err(AblyError::invalid_request(format!("Unknown on_failure setting '{}'", other)))
+                }
+            };
+        }
+        if let Some(require) = config.settings.get("require_signature") {
+            self.require_signature = require == "true";
+        }
+        if let Some(skew) = config.settings.get("max_clock_skew_ms") {
+            let millis = skew
+                .parse()
+                .map_err(|_| AblyError::invalid_request(format!("Invalid max_clock_skew_ms setting '{}'", skew)))?;
+            self.max_clock_skew = Duration::from_millis(millis);
+        }
+        Ok(())
+    }
+
+    async fn shutdown(&mut self) -> AblyResult<()> {
+        Ok(())
+    }
+
+    /// Signatures are bound to a channel, so messages sent without one cannot be signed
+    async fn process_outbound(&self, _message: &mut Message) -> AblyResult<()> {
+        if self.signer.is_some() {
+            return Err(AblyError::invalid_request(
+                "Signed messages must be published on a single channel",
+            ));
+        }
+        Ok(())
+    }
+
+    async fn process_inbound(&self, message: &mut Message) -> AblyResult<()> {
+        remove_header(message, SIGNATURE_STATUS_HEADER);
+        Ok(())
+    }
+
+    async fn process_channel_outbound(&self, channel: &str, message: &mut Message) -> AblyResult<()> {
+        if self.signer.is_some() {
+            self.sign(channel, message)?;
+        }
+        Ok(())
+    }
+
+    async fn process_channel_inbound(&self, channel: &str, message: &mut Message) -> AblyResult<()> {
+        // Publishers can set any header, so a status only ever comes from this plugin
+        remove_header(message, SIGNATURE_STATUS_HEADER);
+        if self.resolver.is_none() {
+            return Ok(());
+        }
+
+        let status = self.verify(channel, message);
+        if self.is_failure(status) && self.on_failure == VerificationFailure::Reject {
+            return Err(AblyError::Forbidden {
+                message: format!(
+                    "Message {} failed signature verification: {}",
+                    message.id.as_deref().unwrap_or("<no id>"),
+                    status.as_str()
+                ),
+            });
+        }
+
+        headers_mut(message).insert(SIGNATURE_STATUS_HEADER.to_string(), Value::String(status.as_str().to_string()));
+        Ok(())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const CHANNEL: &str = "payments";
+
+    fn key(byte: u8) -> SigningKey {
+        SigningKey::from_bytes(&[byte; 32])
+    }
+
+    fn resolver() -> HashMap<String, VerifyingKey> {
+        HashMap::from([("publisher".to_string(), key(1).verifying_key())])
+    }
+
+    fn signed(data: MessageData) -> Message {
+        let mut message = Message {
+            name: Some("event".to_string()),
+            data: Some(data),
+            ..Default::default()
+        };
+        SigningPlugin::new("publisher", key(1)).sign(CHANNEL, &mut message).unwrap();
+        message
+    }
+
+    #[tokio::test]
+    async fn test_signed_messages_verify() {
+        let plugin = SigningPlugin::new("publisher", key(1)).with_resolver(resolver());
+
+        for data in [
+            MessageData::from("text"),
+            MessageData::Binary(vec![0, 1, 2]),
+            MessageData::Json(json!({"b": 2, "a": [1, 2]})),
+        ] {
+            let mut message = Message {
+                name: Some("event".to_string()),
+                data: Some(data),
+                ..Default::default()
+            };
+            plugin.process_channel_outbound(CHANNEL, &mut message).await.unwrap();
+            assert_eq!(header(&message, SIGNATURE_KEY_ID_HEADER), Some("publisher"));
+
+            // The server assigns an id to messages published without one
+            message.id = Some("connection:0:0".to_string());
+            plugin.process_channel_inbound(CHANNEL, &mut message).await.unwrap();
+            assert_eq!(SignatureStatus::of(&message), Some(SignatureStatus::Valid));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tampered_message_is_marked() {
+        let plugin = SigningPlugin::verifier(resolver());
+
+        let mut tampered = signed("original".into());
+        tampered.data = Some("altered".into());
+        plugin.process_channel_inbound(CHANNEL, &mut tampered).await.unwrap();
+        assert_eq!(SignatureStatus::of(&tampered), Some(SignatureStatus::Invalid));
+
+        let mut renamed = signed("original".into());
+        renamed.name = Some("other".to_string());
+        assert_eq!(plugin.verify(CHANNEL, &renamed), SignatureStatus::Invalid);
+
+        let mut unsigned = Message::default();
+        plugin.process_channel_inbound(CHANNEL, &mut unsigned).await.unwrap();
+        assert_eq!(SignatureStatus::of(&unsigned), Some(SignatureStatus::Missing));
+    }
+
+    #[tokio::test]
+    async fn test_replayed_messages_do_not_verify() {
+        let plugin = SigningPlugin::verifier(resolver());
+        let message = signed("transfer".into());
+
+        // Re-published on another channel
+        assert_eq!(plugin.verify("other-channel", &message), SignatureStatus::Invalid);
+
+        // Re-published later, so the server receives it long after it was signed
+        let mut resent = message.clone();
+        resent.timestamp = Some(chrono::Utc::now().timestamp_millis() + 10 * 60 * 1000);
+        assert_eq!(plugin.verify(CHANNEL, &resent), SignatureStatus::Expired);
+
+        // An id bound at signing time cannot be swapped for another
+        let mut with_id = Message {
+            id: Some("original:0".to_string()),
+            name: Some("event".to_string()),
+            ..Default::default()
+        };
+        SigningPlugin::new("publisher", key(1)).sign(CHANNEL, &mut with_id).unwrap();
+        assert_eq!(plugin.verify(CHANNEL, &with_id), SignatureStatus::Valid);
+        with_id.id = Some("replayed:0".to_string());
+        assert_eq!(plugin.verify(CHANNEL, &with_id), SignatureStatus::Invalid);
+    }
+
+    #[tokio::test]
+    async fn test_forged_status_header_is_stripped() {
+        let forge = |message: &mut Message| {
+            headers_mut(message).insert(SIGNATURE_STATUS_HEADER.to_string(), json!("valid"));
+        };
+
+        let mut unsigned = Message::default();
+        forge(&mut unsigned);
+        SigningPlugin::new("publisher", key(1))
+            .process_channel_inbound(CHANNEL, &mut unsigned)
+            .await
+            .unwrap();
+        assert_eq!(SignatureStatus::of(&unsigned), None);
+
+        let mut unsigned = Message::default();
+        forge(&mut unsigned);
+        SigningPlugin::verifier(resolver()).process_channel_inbound(CHANNEL, &mut unsigned).await.unwrap();
+        assert_eq!(SignatureStatus::of(&unsigned), Some(SignatureStatus::Missing));
+    }
+
+    #[tokio::test]
+    async fn test_messages_without_channel_are_not_signed() {
+        let plugin = SigningPlugin::new("publisher", key(1));
+        assert!(plugin.process_outbound(&mut Message::default()).await.is_err());
+        SigningPlugin::verifier(resolver()).process_outbound(&mut Message::default()).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_reject_policy() {
+        let plugin = SigningPlugin::verifier(|key_id: &str| (key_id == "publisher").then(|| key(2).verifying_key()))
+            .on_failure(VerificationFailure::Reject);
+
+        // Signed with a key that does not match the registered public key
+        let mut forged = signed(MessageData::Json(json!({"amount": 100})));
+        let err = plugin.process_channel_inbound(CHANNEL, &mut forged).await.unwrap_err();
+        assert!(matches!(err, AblyError::Forbidden { .. }));
+
+        // Unsigned messages pass unless signatures are required
+        let mut unsigned = Message::default();
+        plugin.process_channel_inbound(CHANNEL, &mut unsigned).await.unwrap();
+
+        let strict = SigningPlugin::verifier(resolver())
+            .on_failure(VerificationFailure::Reject)
+            .require_signature(true);
+        assert!(strict.process_channel_inbound(CHANNEL, &mut Message::default()).await.is_err());
+    }
+}
@@ -0,0 +1,255 @@
+// Annotation wire types and typed annotation summaries
+
+use super::messages::MessageData;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// An annotation, such as a reaction, on a published message (TAN1)
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Annotation {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub action: Option<AnnotationAction>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+
+    /// Kind of annotation and how it is summarized, e.g. `reaction:distinct.v1`
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub annotation_type: Option<String>,
+
+    /// Value being annotated with, e.g. the emoji of a reaction
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    /// Weight of the annotation, used by `multiple.v1` summaries
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub count: Option<u64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<MessageData>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extras: Option<HashMap<String, Value>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<i64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub serial: Option<String>,
+
+    /// Serial of the message this annotation belongs to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_serial: Option<String>,
+}
+
+impl Annotation {
+    pub fn new(annotation_type: impl Into<String>) -> Self {
+        Self {
+            annotation_type: Some(annotation_type.into()),
+            ..Default::default()
+        }
+    }
+
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    pub fn count(mut self, count: u64) -> Self {
+        self.count = Some(count);
+        self
+    }
+
+    pub fn data(mut self, data: impl Into<MessageData>) -> Self {
+        self.data = Some(data.into());
+        self
+    }
+}
+
+/// Whether an annotation is being added or removed (TAN2b)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum AnnotationAction {
+    #[default]
+    Create,
+    Delete,
+    /// An action added to the protocol after this library
+    Unknown(u64),
+}
+
+impl AnnotationAction {
+    pub fn from_u64(value: u64) -> Self {
+        match value {
+            0 => AnnotationAction::Create,
+            1 => AnnotationAction::Delete,
+            other => AnnotationAction::Unknown(other),
+        }
+    }
+
+    pub fn as_u64(&self) -> u64 {
+        match self {
+            AnnotationAction::Create => 0,
+            AnnotationAction::Delete => 1,
+            AnnotationAction::Unknown(value) => *value,
+        }
+    }
+}
+
+impl Serialize for AnnotationAction {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(self.as_u64())
+    }
+}
+
+impl<'de> Deserialize<'de> for AnnotationAction {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        u64::deserialize(deserializer).map(AnnotationAction::from_u64)
+    }
+}
+
+/// Annotations attached to a message, as carried by `summary` messages (TM2u)
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct MessageAnnotations {
+    /// Raw summary for each annotation type
+    #[serde(default)]
+    pub summary: HashMap<String, Value>,
+}
+
+impl MessageAnnotations {
+    /// Summaries decoded according to the method named in each annotation type
+    pub fn summaries(&self) -> HashMap<String, AnnotationSummary> {
+        self.summary
+            .iter()
+            .map(|(annotation_type, summary)| (annotation_type.clone(), AnnotationSummary::parse(annotation_type, summary)))
+            .collect()
+    }
+}
+
+/// Summary of all annotations of one type on a message
+#[derive(Debug, Clone, PartialEq)]
+pub enum AnnotationSummary {
+    /// `total.v1`: how many annotations there are
+    Total(SummaryTotal),
+    /// `flag.v1`: which clients have set the flag
+    Flag(SummaryClientIds),
+    /// `distinct.v1`: which clients used each name
+    Distinct(HashMap<String, SummaryClientIds>),
+    /// `unique.v1`: like distinct, but each client counts towards one name only
+    Unique(HashMap<String, SummaryClientIds>),
+    /// `multiple.v1`: counts each client contributed to each name
+    Multiple(HashMap<String, SummaryClientCounts>),
+    /// A summary method this library does not know, or one that failed to parse
+    Unknown(Value),
+}
+
+impl AnnotationSummary {
+    fn parse(annotation_type: &str, summary: &Value) -> Self {
+        let method = annotation_type.split_once(':').map_or("", |(_, method)| method);
+        let parsed = match method {
+            "total.v1" => serde_json::from_value(summary.clone()).map(AnnotationSummary::Total),
+            "flag.v1" => serde_json::from_value(summary.clone()).map(AnnotationSummary::Flag),
+            "distinct.v1" => serde_json::from_value(summary.clone()).map(AnnotationSummary::Distinct),
+            "unique.v1" => serde_json::from_value(summary.clone()).map(AnnotationSummary::Unique),
+            "multiple.v1" => serde_json::from_value(summary.clone()).map(AnnotationSummary::Multiple),
+            _ => return AnnotationSummary::Unknown(summary.clone()),
+        };
+        parsed.unwrap_or_else(|_| AnnotationSummary::Unknown(summary.clone()))
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct SummaryTotal {
+    pub total: u64,
+}
+
+/// Clients that contributed to a summary entry
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SummaryClientIds {
+    pub total: u64,
+    pub client_ids: Vec<String>,
+    /// Whether `client_ids` was cut short; `total` is still exact
+    pub clipped: bool,
+}
+
+/// Per-client counts for a `multiple.v1` summary entry
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SummaryClientCounts {
+    pub total: u64,
+    pub client_ids: HashMap<String, u64>,
+    /// Count contributed by clients without a client id
+    pub total_unidentified: u64,
+    pub total_client_ids: u64,
+    /// Whether `client_ids` was cut short; the totals are still exact
+    pub clipped: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_summaries_typed_by_method() {
+        let annotations: MessageAnnotations = serde_json::from_value(json!({
+            "summary": {
+                "reaction:distinct.v1": {"👍": {"total": 2, "clientIds": ["ana", "bob"]}},
+                "vote:multiple.v1": {"yes": {"total": 3, "clientIds": {"ana": 2}, "totalUnidentified": 1, "totalClientIds": 1}},
+                "flag:flag.v1": {"total": 1, "clientIds": ["ana"], "clipped": true},
+                "views:total.v1": {"total": 10},
+                "custom:other.v1": {"anything": true},
+                "broken:total.v1": {"total": "many"}
+            }
+        }))
+        .unwrap();
+
+        let summaries = annotations.summaries();
+        match &summaries["reaction:distinct.v1"] {
+            AnnotationSummary::Distinct(names) => assert_eq!(names["👍"].client_ids, vec!["ana", "bob"]),
+            other => panic!("unexpected summary {:?}", other),
+        }
+        match &summaries["vote:multiple.v1"] {
+            AnnotationSummary::Multiple(names) => {
+                assert_eq!(names["yes"].client_ids["ana"], 2);
+                assert_eq!(names["yes"].total_unidentified, 1);
+            }
+            other => panic!("unexpected summary {:?}", other),
+        }
+        assert!(matches!(&summaries["flag:flag.v1"], AnnotationSummary::Flag(flag) if flag.clipped));
+        assert_eq!(summaries["views:total.v1"], AnnotationSummary::Total(SummaryTotal { total: 10 }));
+        assert_eq!(summaries["custom:other.v1"], AnnotationSummary::Unknown(json!({"anything": true})));
+        assert!(matches!(summaries["broken:total.v1"], AnnotationSummary::Unknown(_)));
+    }
+
+    #[test]
+    fn test_annotation_wire_format() {
+        let annotation = Annotation {
+            action: Some(AnnotationAction::Delete),
+            message_serial: Some("01:0".to_string()),
+            ..Annotation::new("reaction:distinct.v1").name("👍")
+        };
+        let value = serde_json::to_value(&annotation).unwrap();
+        assert_eq!(value, json!({"action": 1, "type": "reaction:distinct.v1", "name": "👍", "messageSerial": "01:0"}));
+    }
+
+    #[test]
+    fn test_unknown_action_keeps_frame() {
+        let annotations: Vec<Annotation> = serde_json::from_value(json!([
+            {"action": 7, "type": "reaction:distinct.v1"},
+            {"action": 0, "type": "reaction:distinct.v1"},
+        ]))
+        .unwrap();
+        assert_eq!(annotations[0].action, Some(AnnotationAction::Unknown(7)));
+        assert_eq!(annotations[1].action, Some(AnnotationAction::Create));
+        assert_eq!(serde_json::to_value(&annotations[0]).unwrap()["action"], json!(7));
+    }
+}
@@ -71,8 +71,13 @@ impl ProtocolCodec {
     }
 }
 
-/// Data encoding for message payloads
+/// Data encoding for message and presence payloads (RSL4, RSL6)
 pub mod data_encoding {
+    use super::EncodingFormat;
+    use crate::crypto::MessageCrypto;
+    use crate::error::{AblyError, AblyResult};
+    use crate::protocol::annotations::Annotation;
+    use crate::protocol::messages::{Message, MessageData, PresenceMessage};
     use base64::Engine;
     use serde_json::Value;
     use std::collections::HashMap;
@@ -84,60 +89,167 @@ pub mod data_encoding {
     pub const CIPHER_AES128: &str = "cipher+aes-128-cbc";
     pub const CIPHER_AES256: &str = "cipher+aes-256-cbc";
 
-    /// Encode data based on encoding string
-    pub fn encode_data(data: &Value, encoding: &str) -> Result<Vec<u8>, String> {
-        let encodings: Vec<&str> = encoding.split('/').collect();
-        let mut result = serde_json::to_vec(data)
-            .map_err(|e| format!("Failed to serialize data: {}", e))?;
-
-        for enc in encodings {
-            result = match enc {
-                UTF8 => result, // Already UTF-8
-                JSON => result, // Already JSON
-                BASE64 => {
-                    let engine = base64::engine::general_purpose::STANDARD;
-                    engine.encode(&result).into_bytes()
-                }
-                enc if enc.starts_with("cipher+") => {
-                    // Cipher encoding would be implemented here
-                    return Err(format!("Cipher encoding not yet implemented: {}", enc));
-                }
-                _ => {
-                    return Err(format!("Unknown encoding: {}", enc));
+    /// Types carrying an encoded payload
+    pub trait EncodedPayload {
+        fn payload_mut(&mut self) -> (&mut Option<MessageData>, &mut Option<String>);
+
+        /// Message extras, for types that carry them
+        fn extras_mut(&mut self) -> Option<&mut Option<HashMap<String, Value>>> {
+            None
+        }
+    }
+
+    impl EncodedPayload for Message {
+        fn payload_mut(&mut self) -> (&mut Option<MessageData>, &mut Option<String>) {
+            (&mut self.data, &mut self.encoding)
+        }
+
+        fn extras_mut(&mut self) -> Option<&mut Option<HashMap<String, Value>>> {
+            Some(&mut self.extras)
+        }
+    }
+
+    impl EncodedPayload for PresenceMessage {
+        fn payload_mut(&mut self) -> (&mut Option<MessageData>, &mut Option<String>) {
+            (&mut self.data, &mut self.encoding)
+        }
+    }
+
+    impl EncodedPayload for Annotation {
+        fn payload_mut(&mut self) -> (&mut Option<MessageData>, &mut Option<String>) {
+            (&mut self.data, &mut self.encoding)
+        }
+
+        fn extras_mut(&mut self) -> Option<&mut Option<HashMap<String, Value>>> {
+            Some(&mut self.extras)
+        }
+    }
+
+    /// Encode a payload for sending, encrypting it if a cipher is given
+    pub fn encode<P: EncodedPayload>(
+        item: &mut P,
+        cipher: Option<&MessageCrypto>,
+        format: EncodingFormat,
+    ) -> AblyResult<()> {
+        let (data, encoding) = item.payload_mut();
+        let payload = match data.as_ref() {
+            Some(payload) => payload.clone(),
+            None => return Ok(()),
+        };
+
+        let (encoded, new_encoding) = encode_data(payload, encoding.as_deref(), cipher, format)?;
+        *data = Some(encoded);
+        *encoding = new_encoding;
+        Ok(())
+    }
+
+    /// Decode a received payload; on failure it is left untouched
+    pub fn decode<P: EncodedPayload>(item: &mut P, cipher: Option<&MessageCrypto>) -> AblyResult<()> {
+        let (data, encoding) = item.payload_mut();
+        let payload = match data.as_ref() {
+            Some(payload) => payload.clone(),
+            None => return Ok(()),
+        };
+
+        let (decoded, remaining) = decode_data(payload, encoding.as_deref(), cipher)?;
+        *data = Some(decoded);
+        *encoding = remaining;
+        Ok(())
+    }
+
+    /// Apply the encoding chain `[json][/utf-8/cipher+<alg>][/base64]` to a payload
+    pub fn encode_data(
+        data: MessageData,
+        encoding: Option<&str>,
+        cipher: Option<&MessageCrypto>,
+        format: EncodingFormat,
+    ) -> AblyResult<(MessageData, Option<String>)> {
+        let mut steps = split_encoding(encoding);
+
+        let mut data = match data {
+            MessageData::Json(value) => {
+                steps.push(JSON.to_string());
+                let text = serde_json::to_string(&value)
+                    .map_err(|e| AblyError::encoding(format!("Failed to serialize JSON data: {}", e)))?;
+                MessageData::String(text)
+            }
+            // Binary data the caller already base64 encoded
+            MessageData::String(text) if steps.last().map(String::as_str) == Some(BASE64) => {
+                steps.pop();
+                MessageData::Binary(base64_decode(text.as_bytes())?)
+            }
+            other => other,
+        };
+
+        if let Some(cipher) = cipher {
+            let plaintext = match data {
+                MessageData::String(text) => {
+                    steps.push(UTF8.to_string());
+                    text.into_bytes()
                 }
+                MessageData::Binary(bytes) => bytes,
+                MessageData::Json(_) => unreachable!("JSON payloads are stringified above"),
             };
+            data = MessageData::Binary(cipher.encrypt(&plaintext)?);
+            steps.push(cipher.cipher_encoding());
+        }
+
+        // Text transports carry binary as base64
+        if format == EncodingFormat::Json {
+            if let MessageData::Binary(bytes) = &data {
+                data = MessageData::String(base64::engine::general_purpose::STANDARD.encode(bytes));
+                steps.push(BASE64.to_string());
+            }
         }
 
-        Ok(result)
+        Ok((data, join_encoding(steps)))
     }
 
-    /// Decode data based on encoding string
-    pub fn decode_data(data: &[u8], encoding: &str) -> Result<Value, String> {
-        let encodings: Vec<&str> = encoding.split('/').rev().collect();
-        let mut result = data.to_vec();
+    /// Unwind an encoding chain, last step first
+    pub fn decode_data(
+        data: MessageData,
+        encoding: Option<&str>,
+        cipher: Option<&MessageCrypto>,
+    ) -> AblyResult<(MessageData, Option<String>)> {
+        let mut steps = split_encoding(encoding);
+        let mut data = data;
 
-        for enc in encodings {
-            result = match enc {
-                UTF8 => result, // Already UTF-8
-                JSON => result, // Will parse as JSON at the end
-                BASE64 => {
-                    let engine = base64::engine::general_purpose::STANDARD;
-                    engine.decode(&result)
-                        .map_err(|e| format!("Base64 decoding failed: {}", e))?
-                }
-                enc if enc.starts_with("cipher+") => {
-                    // Cipher decoding would be implemented here
-                    return Err(format!("Cipher decoding not yet implemented: {}", enc));
+        while let Some(step) = steps.last() {
+            data = match (step.as_str(), data) {
+                (BASE64, MessageData::String(text)) => MessageData::Binary(base64_decode(text.as_bytes())?),
+                (BASE64, MessageData::Binary(bytes)) => MessageData::Binary(base64_decode(&bytes)?),
+                (UTF8, MessageData::Binary(bytes)) => MessageData::String(
+                    String::from_utf8(bytes)
+                        .map_err(|e| AblyError::decoding(format!("Invalid UTF-8 payload: {}", e)))?,
+                ),
+                (UTF8, text @ MessageData::String(_)) => text,
+                (JSON, MessageData::String(text)) => serde_json::from_str::<serde_json::Value>(&text)
+                    .map_err(|e| AblyError::decoding(format!("Invalid JSON payload: {}", e)))?
+                    .into(),
+                (JSON, MessageData::Binary(bytes)) => serde_json::from_slice::<serde_json::Value>(&bytes)
+                    .map_err(|e| AblyError::decoding(format!("Invalid JSON payload: {}", e)))?
+                    .into(),
+                (step, MessageData::Binary(bytes)) if step.starts_with("cipher+") => {
+                    let cipher = cipher.ok_or_else(|| {
+                        AblyError::encryption(format!("Payload encrypted with {} but no cipher is configured", step))
+                    })?;
+                    if step != cipher.cipher_encoding() {
+                        return Err(AblyError::encryption(format!(
+                            "Payload encrypted with {} but channel cipher is {}",
+                            step,
+                            cipher.cipher_encoding()
+                        )));
+                    }
+                    MessageData::Binary(cipher.decrypt(&bytes)?)
                 }
-                _ => {
-                    return Err(format!("Unknown encoding: {}", enc));
+                (step, _) => {
+                    return Err(AblyError::decoding(format!("Unable to decode encoding step '{}'", step)));
                 }
             };
+            steps.pop();
         }
 
-        // Parse as JSON
-        serde_json::from_slice(&result)
-            .map_err(|e| format!("JSON parsing failed: {}", e))
+        Ok((data, None))
     }
 
     /// Check if encoding includes encryption
@@ -155,6 +267,26 @@ pub mod data_encoding {
             None
         }
     }
+
+    fn split_encoding(encoding: Option<&str>) -> Vec<String> {
+        encoding
+            .map(|e| e.split('/').filter(|s| !s.is_empty()).map(String::from).collect())
+            .unwrap_or_default()
+    }
+
+    fn join_encoding(steps: Vec<String>) -> Option<String> {
+        if steps.is_empty() {
+            None
+        } else {
+            Some(steps.join("/"))
+        }
+    }
+
+    fn base64_decode(data: &[u8]) -> AblyResult<Vec<u8>> {
+        base64::engine::general_purpose::STANDARD
+            .decode(data)
+            .map_err(|e| AblyError::decoding(format!("Base64 decoding failed: {}", e)))
+    }
 }
 
 /// Protocol version negotiation
@@ -216,12 +348,56 @@ mod tests {
 
     #[test]
     fn test_base64_encoding() {
+        use crate::protocol::MessageData;
         use data_encoding::*;
         
-        let data = json!("hello world");
-        let encoded = encode_data(&data, BASE64).unwrap();
-        let decoded = decode_data(&encoded, BASE64).unwrap();
+        let data = MessageData::Binary(b"hello world".to_vec());
+        let (encoded, encoding) = encode_data(data.clone(), None, None, EncodingFormat::Json).unwrap();
+        assert_eq!(encoding.as_deref(), Some(BASE64));
         
+        let (decoded, remaining) = decode_data(encoded, encoding.as_deref(), None).unwrap();
         assert_eq!(decoded, data);
+        assert!(remaining.is_none());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_encoding_chains() {
+        use crate::crypto::{CipherParams, MessageCrypto};
+        use crate::protocol::MessageData;
+        use data_encoding::*;
+        
+        let crypto = MessageCrypto::new(CipherParams::aes256_cbc(vec![3u8; 32]).unwrap());
+        let cases = [
+            (MessageData::from(json!({ "a": [1, 2] })), "json/utf-8/cipher+aes-256-cbc/base64", "json/utf-8/cipher+aes-256-cbc"),
+            (MessageData::from("text"), "utf-8/cipher+aes-256-cbc/base64", "utf-8/cipher+aes-256-cbc"),
+            (MessageData::Binary(vec![0, 255]), "cipher+aes-256-cbc/base64", "cipher+aes-256-cbc"),
+        ];
+        
+        for (data, json_encoding, msgpack_encoding) in cases {
+            let (encoded, encoding) = encode_data(data.clone(), None, Some(&crypto), EncodingFormat::Json).unwrap();
+            assert_eq!(encoding.as_deref(), Some(json_encoding));
+            assert!(encoded.as_str().is_some());
+            assert_eq!(decode_data(encoded, encoding.as_deref(), Some(&crypto)).unwrap().0, data);
+            
+            let (encoded, encoding) = encode_data(data.clone(), None, Some(&crypto), EncodingFormat::MessagePack).unwrap();
+            assert_eq!(encoding.as_deref(), Some(msgpack_encoding));
+            assert!(encoded.as_bytes().is_some());
+            assert_eq!(decode_data(encoded, encoding.as_deref(), Some(&crypto)).unwrap().0, data);
+        }
+    }
+
+    #[test]
+    fn test_decode_failure_leaves_payload() {
+        use data_encoding::*;
+        
+        let mut message = Message {
+            data: Some("not base64!".into()),
+            encoding: Some("utf-8/base64".to_string()),
+            ..Default::default()
+        };
+        
+        assert!(decode(&mut message, None).is_err());
+        assert_eq!(message.data, Some("not base64!".into()));
+        assert_eq!(message.encoding.as_deref(), Some("utf-8/base64"));
+    }
+}
@@ -108,7 +108,7 @@ mod tests {
     fn test_message_with_data() {
         let msg = Message {
             name: Some("test".to_string()),
-            data: Some(serde_json::json!("hello world")),
+            data: Some(serde_json::json!("hello world").into()),
             ..Default::default()
         };
 
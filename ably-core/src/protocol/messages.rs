@@ -1,10 +1,14 @@
 // Protocol message types implementation
-// Comprehensive support for all 22 Ably protocol actions
+// Comprehensive support for all 23 Ably protocol actions
 
-use serde::{Serialize, Deserialize};
+use base64::Engine;
+use super::annotations::{Annotation, MessageAnnotations};
+use serde::{Serialize, Deserialize, Serializer, Deserializer};
+use serde::de::{self, MapAccess, SeqAccess, Visitor};
 use serde_repr::{Serialize_repr, Deserialize_repr};
 use serde_json::Value;
 use std::collections::HashMap;
+use std::fmt;
 
 /// Complete protocol message structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -51,14 +55,20 @@ pub struct ProtocolMessage {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub presence: Option<Vec<PresenceMessage>>,
     
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub annotations: Option<Vec<Annotation>>,
+    
     #[serde(skip_serializing_if = "Option::is_none")]
     pub auth: Option<AuthDetails>,
     
     #[serde(skip_serializing_if = "Option::is_none")]
     pub connection_details: Option<ConnectionDetails>,
+    
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub params: Option<HashMap<String, String>>,
 }
 
-/// Action types (all 22 protocol actions)
+/// Action types (all 23 protocol actions)
 #[derive(Debug, Clone, Copy, PartialEq, Serialize_repr, Deserialize_repr)]
 #[repr(u8)]
 pub enum Action {
@@ -84,6 +94,7 @@ pub enum Action {
     MessageAck = 19,
     PresenceAck = 20,
     PushAdmin = 21,
+    Annotation = 22,
 }
 
 /// Message structure
@@ -106,7 +117,7 @@ pub struct Message {
     pub name: Option<String>,
     
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub data: Option<Value>,
+    pub data: Option<MessageData>,
     
     #[serde(skip_serializing_if = "Option::is_none")]
     pub encoding: Option<String>,
@@ -128,10 +139,101 @@ pub struct Message {
     pub version: Option<String>,
     
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub action: Option<i64>,
+    pub action: Option<MessageAction>,
     
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub operation: Option<Value>,
+    pub operation: Option<MessageOperation>,
+    
+    /// Summaries of the message's annotations, set on `MessageAction::Summary` messages
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub annotations: Option<MessageAnnotations>,
+}
+
+/// What a message does to the message identified by its serial (TM5)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum MessageAction {
+    #[default]
+    Create,
+    Update,
+    Delete,
+    Meta,
+    Summary,
+    Append,
+    /// An action added to the protocol after this library
+    Unknown(u64),
+}
+
+impl MessageAction {
+    pub fn from_u64(value: u64) -> Self {
+        match value {
+            0 => MessageAction::Create,
+            1 => MessageAction::Update,
+            2 => MessageAction::Delete,
+            3 => MessageAction::Meta,
+            4 => MessageAction::Summary,
+            5 => MessageAction::Append,
+            other => MessageAction::Unknown(other),
+        }
+    }
+    
+    pub fn as_u64(&self) -> u64 {
+        match self {
+            MessageAction::Create => 0,
+            MessageAction::Update => 1,
+            MessageAction::Delete => 2,
+            MessageAction::Meta => 3,
+            MessageAction::Summary => 4,
+            MessageAction::Append => 5,
+            MessageAction::Unknown(value) => *value,
+        }
+    }
+}
+
+impl Serialize for MessageAction {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(self.as_u64())
+    }
+}
+
+impl<'de> Deserialize<'de> for MessageAction {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        u64::deserialize(deserializer).map(MessageAction::from_u64)
+    }
+}
+
+/// Who changed a message and why, sent with an update, delete or append
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageOperation {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+    
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<HashMap<String, String>>,
+}
+
+impl MessageOperation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    pub fn client_id(mut self, client_id: impl Into<String>) -> Self {
+        self.client_id = Some(client_id.into());
+        self
+    }
+    
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+    
+    pub fn metadata(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.metadata.get_or_insert_with(HashMap::new).insert(key.into(), value.into());
+        self
+    }
 }
 
 /// Presence message structure
@@ -151,7 +253,7 @@ pub struct PresenceMessage {
     pub connection_id: Option<String>,
     
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub data: Option<Value>,
+    pub data: Option<MessageData>,
     
     #[serde(skip_serializing_if = "Option::is_none")]
     pub encoding: Option<String>,
@@ -229,30 +331,34 @@ pub struct ConnectionDetails {
     pub server_id: Option<String>,
 }
 
-/// Channel flags
+/// Channel flags (bit positions as defined by the Ably protocol)
 pub mod flags {
-    pub const PRESENCE: u32 = 1 << 0;
-    pub const PUBLISH: u32 = 1 << 1;
-    pub const SUBSCRIBE: u32 = 1 << 2;
-    pub const PRESENCE_SUBSCRIBE: u32 = 1 << 3;
-    pub const HAS_PRESENCE: u32 = 1 << 16;
-    pub const HAS_BACKLOG: u32 = 1 << 17;
-    pub const RESUMED: u32 = 1 << 18;
-    pub const TRANSIENT: u32 = 1 << 19;
-    pub const ATTACH_RESUME: u32 = 1 << 20;
-}
-
-/// Message flags enum for type safety
+    pub const HAS_PRESENCE: u32 = 1 << 0;
+    pub const HAS_BACKLOG: u32 = 1 << 1;
+    pub const RESUMED: u32 = 1 << 2;
+    pub const TRANSIENT: u32 = 1 << 4;
+    pub const ATTACH_RESUME: u32 = 1 << 5;
+    pub const PRESENCE: u32 = 1 << 16;
+    pub const PUBLISH: u32 = 1 << 17;
+    pub const SUBSCRIBE: u32 = 1 << 18;
+    pub const PRESENCE_SUBSCRIBE: u32 = 1 << 19;
+    pub const ANNOTATION_PUBLISH: u32 = 1 << 21;
+    pub const ANNOTATION_SUBSCRIBE: u32 = 1 << 22;
+}
+
+/// Message flags enum for type safety, with the bit values of `flags`
+#[allow(non_camel_case_types)]
+#[repr(u32)]
 pub enum MessageFlags {
-    PRESENCE = 1,
-    PUBLISH = 2,
-    SUBSCRIBE = 4,
-    PRESENCE_SUBSCRIBE = 8,
-    HAS_PRESENCE = 16,
-    HAS_BACKLOG = 32,
-    RESUMED = 64,
-    TRANSIENT = 256,
-    ATTACH_RESUME = 512,
+    PRESENCE = flags::PRESENCE,
+    PUBLISH = flags::PUBLISH,
+    SUBSCRIBE = flags::SUBSCRIBE,
+    PRESENCE_SUBSCRIBE = flags::PRESENCE_SUBSCRIBE,
+    HAS_PRESENCE = flags::HAS_PRESENCE,
+    HAS_BACKLOG = flags::HAS_BACKLOG,
+    RESUMED = flags::RESUMED,
+    TRANSIENT = flags::TRANSIENT,
+    ATTACH_RESUME = flags::ATTACH_RESUME,
 }
 
 /// Channel details structure
@@ -310,8 +416,171 @@ pub struct ChannelMetrics {
     pub subscribers: Option<i64>,
 }
 
-/// Type alias for MessageData (same as Message)
-pub type MessageData = Message;
+/// Message or presence payload
+///
+/// On the wire, binary data is sent as msgpack `bin` or, on text transports, base64
+/// with a `base64` encoding step; see `protocol::encoding::data_encoding`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MessageData {
+    String(String),
+    Binary(Vec<u8>),
+    Json(Value),
+}
+
+impl MessageData {
+    /// Get string data
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            MessageData::String(s) => Some(s),
+            _ => None,
+        }
+    }
+    
+    /// Get binary data
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            MessageData::Binary(b) => Some(b),
+            _ => None,
+        }
+    }
+    
+    /// Get JSON data
+    pub fn as_json(&self) -> Option<&Value> {
+        match self {
+            MessageData::Json(v) => Some(v),
+            _ => None,
+        }
+    }
+    
+    /// Convert to a JSON value, with binary data as a base64 string
+    pub fn to_json_value(&self) -> Value {
+        match self {
+            MessageData::String(s) => Value::String(s.clone()),
+            MessageData::Binary(b) => Value::String(base64::engine::general_purpose::STANDARD.encode(b)),
+            MessageData::Json(v) => v.clone(),
+        }
+    }
+    
+    /// Size of the payload in bytes
+    pub fn len(&self) -> usize {
+        match self {
+            MessageData::String(s) => s.len(),
+            MessageData::Binary(b) => b.len(),
+            MessageData::Json(v) => v.to_string().len(),
+        }
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl From<String> for MessageData {
+    fn from(s: String) -> Self {
+        MessageData::String(s)
+    }
+}
+
+impl From<&str> for MessageData {
+    fn from(s: &str) -> Self {
+        MessageData::String(s.to_string())
+    }
+}
+
+impl From<Vec<u8>> for MessageData {
+    fn from(b: Vec<u8>) -> Self {
+        MessageData::Binary(b)
+    }
+}
+
+impl From<&[u8]> for MessageData {
+    fn from(b: &[u8]) -> Self {
+        MessageData::Binary(b.to_vec())
+    }
+}
+
+impl From<Value> for MessageData {
+    fn from(v: Value) -> Self {
+        match v {
+            Value::String(s) => MessageData::String(s),
+            other => MessageData::Json(other),
+        }
+    }
+}
+
+impl Serialize for MessageData {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            MessageData::String(s) => serializer.serialize_str(s),
+            // Text formats can't carry raw bytes; the encoder marks these with `base64`
+            MessageData::Binary(b) if serializer.is_human_readable() => {
+                serializer.serialize_str(&base64::engine::general_purpose::STANDARD.encode(b))
+            }
+            MessageData::Binary(b) => serializer.serialize_bytes(b),
+            MessageData::Json(v) => v.serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for MessageData {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(MessageDataVisitor)
+    }
+}
+
+struct MessageDataVisitor;
+
+impl<'de> Visitor<'de> for MessageDataVisitor {
+    type Value = MessageData;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a string, binary or JSON payload")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<MessageData, E> {
+        Ok(MessageData::String(v.to_string()))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<MessageData, E> {
+        Ok(MessageData::String(v))
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<MessageData, E> {
+        Ok(MessageData::Binary(v.to_vec()))
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<MessageData, E> {
+        Ok(MessageData::Binary(v))
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<MessageData, E> {
+        Ok(MessageData::Json(Value::Bool(v)))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<MessageData, E> {
+        Ok(MessageData::Json(Value::from(v)))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<MessageData, E> {
+        Ok(MessageData::Json(Value::from(v)))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<MessageData, E> {
+        Ok(MessageData::Json(Value::from(v)))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<MessageData, E> {
+        Ok(MessageData::Json(Value::Null))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<MessageData, A::Error> {
+        Value::deserialize(de::value::SeqAccessDeserializer::new(seq)).map(MessageData::Json)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<MessageData, A::Error> {
+        Value::deserialize(de::value::MapAccessDeserializer::new(map)).map(MessageData::Json)
+    }
+}
 
 impl ProtocolMessage {
     /// Create a connect message
@@ -423,8 +692,10 @@ impl Default for ProtocolMessage {
             timestamp: None,
             messages: None,
             presence: None,
+            annotations: None,
             auth: None,
             connection_details: None,
+            params: None,
         }
     }
 }
@@ -468,12 +739,17 @@ impl MessageBuilder {
     }
     
     pub fn data(mut self, data: impl Into<String>) -> Self {
-        self.message.data = Some(Value::String(data.into()));
+        self.message.data = Some(MessageData::String(data.into()));
         self
     }
     
     pub fn json_data(mut self, data: Value) -> Self {
-        self.message.data = Some(data);
+        self.message.data = Some(data.into());
+        self
+    }
+    
+    pub fn binary_data(mut self, data: impl Into<Vec<u8>>) -> Self {
+        self.message.data = Some(MessageData::Binary(data.into()));
         self
     }
     
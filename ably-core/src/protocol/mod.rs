@@ -2,6 +2,7 @@
 // Comprehensive implementation of Ably protocol v3
 
 pub mod messages;
+pub mod annotations;
 pub mod encoding;
 pub mod messagepack;
 
@@ -10,7 +11,12 @@ pub use messages::{
     ProtocolMessage, Action, Message, PresenceMessage, PresenceAction,
     ErrorInfo, AuthDetails, ConnectionDetails, flags,
     MessageFlags, ChannelDetails, ChannelStatus, ChannelOccupancy,
-    ChannelMetrics, MessageData
+    ChannelMetrics, MessageData, MessageAction, MessageOperation
+};
+
+pub use annotations::{
+    Annotation, AnnotationAction, AnnotationSummary, MessageAnnotations,
+    SummaryClientCounts, SummaryClientIds, SummaryTotal
 };
 
 pub use messagepack::{
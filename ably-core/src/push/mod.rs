@@ -1,7 +1,7 @@
 // 🟡 YELLOW Phase: Push notification system implementation
 // Enables push notifications to mobile devices and web browsers
 
-use crate::client::rest::RestClient;
+use crate::client::rest::{PaginatedResult, RestClient};
 use crate::error::{AblyError, AblyResult};
 use crate::http::AblyHttpClient;
 use serde::{Deserialize, Serialize};
@@ -50,12 +50,8 @@ impl<'a> PushClient<'a> {
     }
     
     /// List all registered devices
-    pub async fn list_devices(&self) -> AblyResult<Vec<PushDevice>> {
-        let response = self.http_client
-            .get("/push/deviceRegistrations")
-            .send()
-            .await?;
-        response.json().await
+    pub async fn list_devices(&self) -> AblyResult<PaginatedResult<'a, PushDevice>> {
+        PaginatedResult::fetch(self.http_client, "/push/deviceRegistrations", &HashMap::new(), None).await
     }
     
     /// Subscribe to a channel
@@ -94,16 +90,11 @@ impl<'a> PushClient<'a> {
     }
     
     /// List channel subscriptions for a device
-    pub async fn list_channel_subscriptions(&self, device_id: &str) -> AblyResult<Vec<PushChannel>> {
+    pub async fn list_channel_subscriptions(&self, device_id: &str) -> AblyResult<PaginatedResult<'a, PushChannel>> {
         let mut params = HashMap::new();
         params.insert("deviceId".to_string(), device_id.to_string());
         
-        let response = self.http_client
-            .get("/push/channelSubscriptions")
-            .query(&params)
-            .send()
-            .await?;
-        response.json().await
+        PaginatedResult::fetch(self.http_client, "/push/channelSubscriptions", &params, None).await
     }
     
     /// Publish a push notification
@@ -4,7 +4,7 @@
 use super::{ReplayOptions, ReplayResult, StateSnapshot};
 use crate::client::rest::RestClient;
 use crate::error::{AblyError, AblyResult};
-use crate::protocol::messages::Message;
+use crate::protocol::messages::{Message, MessageData};
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, BTreeMap};
 use tracing::{debug, info, warn};
@@ -220,7 +220,7 @@ impl ChannelReplay {
             }
             MessageFilter::DataContains(content) => {
                 if let Some(data) = &message.data {
-                    data.to_string().contains(content)
+                    data.to_json_value().to_string().contains(content)
                 } else {
                     false
                 }
@@ -242,7 +242,7 @@ impl ChannelReplay {
     ) -> AblyResult<()> {
         if let Some(name) = &message.name {
             if let Some(data) = &message.data {
-                state.insert(name.clone(), data.clone());
+                state.insert(name.clone(), data.to_json_value());
             }
         }
         Ok(())
@@ -336,11 +336,11 @@ impl ChannelReplay {
                     let change = ValueChange {
                         timestamp: message.timestamp.unwrap_or(0),
                         old_value: last_value.clone(),
-                        new_value: data.clone(),
+                        new_value: data.to_json_value(),
                         client_id: message.client_id.clone(),
                     };
                     timeline.push(change);
-                    last_value = Some(data.clone());
+                    last_value = Some(data.to_json_value());
                 }
             }
         }
@@ -381,8 +381,8 @@ impl ChannelReplay {
                                 window[1].client_id.clone().unwrap_or_default(),
                             ],
                             values: vec![
-                                window[0].data.clone().unwrap_or_default(),
-                                window[1].data.clone().unwrap_or_default(),
+                                window[0].data.as_ref().map(MessageData::to_json_value).unwrap_or_default(),
+                                window[1].data.as_ref().map(MessageData::to_json_value).unwrap_or_default(),
                             ],
                         };
                         analysis.state_conflicts.push(conflict);
@@ -466,7 +466,7 @@ mod tests {
         let message = Message {
             id: Some("msg1".to_string()),
             name: Some("state:temperature".to_string()),
-            data: Some(json!(25.5)),
+            data: Some(json!(25.5).into()),
             client_id: Some("sensor1".to_string()),
             connection_id: None,
             connection_key: None,
@@ -478,6 +478,7 @@ mod tests {
             version: None,
             action: None,
             operation: None,
+            annotations: None,
         };
         
         let filter = MessageFilter::NamePattern("state:".to_string());
@@ -495,7 +496,7 @@ mod tests {
         let message = Message {
             id: Some("msg1".to_string()),
             name: Some("temperature".to_string()),
-            data: Some(json!(25.5)),
+            data: Some(json!(25.5).into()),
             client_id: Some("sensor1".to_string()),
             connection_id: None,
             connection_key: None,
@@ -507,6 +508,7 @@ mod tests {
             version: None,
             action: None,
             operation: None,
+            annotations: None,
         };
         
         let filter = MessageFilter::ClientId("sensor1".to_string());
@@ -524,7 +526,7 @@ mod tests {
         let message = Message {
             id: Some("msg1".to_string()),
             name: Some("temperature".to_string()),
-            data: Some(json!(25.5)),
+            data: Some(json!(25.5).into()),
             client_id: Some("sensor1".to_string()),
             connection_id: None,
             connection_key: None,
@@ -536,6 +538,7 @@ mod tests {
             version: None,
             action: None,
             operation: None,
+            annotations: None,
         };
         
         let filter = MessageFilter::TimeRange {
@@ -200,6 +200,8 @@ impl ReplayManager {
     ) -> AblyResult<()> {
         if let Some(name) = &message.name {
             if let Some(data) = &message.data {
+                let data = data.to_json_value();
+                
                 // Store the latest value for each message name
                 state.insert(name.clone(), data.clone());
                 
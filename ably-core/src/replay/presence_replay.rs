@@ -4,7 +4,7 @@
 use super::{ReplayOptions, ReplayResult};
 use crate::client::rest::RestClient;
 use crate::error::{AblyError, AblyResult};
-use crate::protocol::messages::{PresenceMessage, PresenceAction};
+use crate::protocol::messages::{MessageData, PresenceMessage, PresenceAction};
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, BTreeMap};
 use tracing::{debug, info, warn};
@@ -72,8 +72,8 @@ pub struct PresenceSession {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DataChange {
     pub timestamp: i64,
-    pub old_data: Option<serde_json::Value>,
-    pub new_data: Option<serde_json::Value>,
+    pub old_data: Option<MessageData>,
+    pub new_data: Option<MessageData>,
 }
 
 /// Presence patterns and statistics
@@ -594,7 +594,7 @@ mod tests {
                 DataChange {
                     timestamp: 1500,
                     old_data: None,
-                    new_data: Some(serde_json::json!({"status": "active"})),
+                    new_data: Some(serde_json::json!({"status": "active"}).into()),
                 }
             ],
             is_complete: true,
@@ -0,0 +1,224 @@
+//! Cross-SDK conformance tests using the ably-common fixture vectors
+//! Every vector is checked in both directions so output is byte-for-byte
+//! compatible with the other Ably SDKs
+
+use ably_core::crypto::{self, CipherParams, ChannelCipher, EncryptedData, MessageCrypto};
+use ably_core::protocol::encoding::{data_encoding, EncodingFormat};
+use ably_core::protocol::messages::{Message, MessageData};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine as _;
+use serde::Deserialize;
+use serde_json::Value;
+
+const CRYPTO_DATA_128: &str = include_str!("fixtures/crypto-data-128.json");
+const CRYPTO_DATA_256: &str = include_str!("fixtures/crypto-data-256.json");
+const MESSAGES_ENCODING: &str = include_str!("fixtures/messages-encoding.json");
+
+#[derive(Deserialize)]
+struct CryptoFixture {
+    algorithm: String,
+    mode: String,
+    keylength: usize,
+    key: String,
+    iv: String,
+    items: Vec<CryptoItem>,
+}
+
+#[derive(Deserialize)]
+struct CryptoItem {
+    encoded: Message,
+    encrypted: Message,
+}
+
+#[derive(Deserialize)]
+struct EncodingFixture {
+    messages: Vec<EncodingItem>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EncodingItem {
+    data: String,
+    encoding: String,
+    expected_type: String,
+    expected_value: Option<Value>,
+    expected_hex_value: Option<String>,
+    /// Base64 of the message as sent over a msgpack transport
+    msgpack: String,
+}
+
+impl CryptoFixture {
+    fn load(json: &str) -> Self {
+        let fixture: Self = serde_json::from_str(json).expect("Invalid crypto fixture");
+        assert_eq!(fixture.algorithm, "aes");
+        assert_eq!(fixture.mode, "cbc");
+        fixture
+    }
+
+    fn key(&self) -> Vec<u8> {
+        BASE64.decode(&self.key).unwrap()
+    }
+
+    fn iv(&self) -> Vec<u8> {
+        BASE64.decode(&self.iv).unwrap()
+    }
+
+    /// Cipher params pinned to the fixture IV so encryption is deterministic
+    fn cipher_params(&self) -> CipherParams {
+        let params = CipherParams::from_key(self.key()).unwrap();
+        assert_eq!(params.key_length(), self.keylength);
+        params.with_iv(self.iv()).unwrap()
+    }
+}
+
+/// Raw plaintext bytes carried by an `encoded` fixture item
+fn plaintext(item: &Message) -> Vec<u8> {
+    let data = item.data.as_ref().and_then(MessageData::as_str).unwrap();
+    match item.encoding.as_deref() {
+        Some("base64") => BASE64.decode(data).unwrap(),
+        _ => data.as_bytes().to_vec(),
+    }
+}
+
+/// Raw IV + ciphertext bytes carried by an `encrypted` fixture item
+fn ciphertext(item: &Message) -> Vec<u8> {
+    BASE64.decode(item.data.as_ref().and_then(MessageData::as_str).unwrap()).unwrap()
+}
+
+fn check_channel_cipher(fixture: &CryptoFixture) {
+    let cipher = ChannelCipher::new(fixture.cipher_params());
+
+    for item in &fixture.items {
+        let encrypted = cipher.encrypt(&plaintext(&item.encoded)).unwrap();
+        assert_eq!(encrypted.to_base64(), item.encrypted.data.as_ref().unwrap().as_str().unwrap());
+
+        let received = EncryptedData::from_base64(
+            item.encrypted.data.as_ref().unwrap().as_str().unwrap(),
+            item.encrypted.encoding.clone().unwrap(),
+        )
+        .unwrap();
+        assert_eq!(cipher.decrypt(&received).unwrap(), plaintext(&item.encoded));
+    }
+}
+
+fn check_default_params(fixture: &CryptoFixture) {
+    let params = crypto::get_default_params(fixture.key.as_str()).unwrap();
+    assert_eq!(params.key_length(), fixture.keylength);
+    assert_eq!(params.algorithm_string(), format!("aes-{}-cbc", fixture.keylength));
+
+    let pinned = MessageCrypto::new(params.clone().with_iv(fixture.iv()).unwrap());
+    let random_iv = MessageCrypto::new(params);
+    for item in &fixture.items {
+        assert_eq!(pinned.encrypt(&plaintext(&item.encoded)).unwrap(), ciphertext(&item.encrypted));
+        assert_eq!(random_iv.decrypt(&ciphertext(&item.encrypted)).unwrap(), plaintext(&item.encoded));
+    }
+}
+
+fn check_message_encoding(fixture: &CryptoFixture) {
+    let crypto = MessageCrypto::new(fixture.cipher_params());
+
+    for item in &fixture.items {
+        // Outbound: encoded fixture -> encrypted wire form
+        let mut outbound = item.encoded.clone();
+        data_encoding::encode(&mut outbound, Some(&crypto), EncodingFormat::Json).unwrap();
+        assert_eq!(outbound.data, item.encrypted.data);
+        assert_eq!(outbound.encoding, item.encrypted.encoding);
+
+        // Inbound: encrypted wire form decodes to the same value as the encoded fixture
+        let mut inbound = item.encrypted.clone();
+        data_encoding::decode(&mut inbound, Some(&crypto)).unwrap();
+        let mut expected = item.encoded.clone();
+        data_encoding::decode(&mut expected, None).unwrap();
+        assert_eq!(inbound.data, expected.data);
+        assert_eq!(inbound.encoding, None);
+    }
+}
+
+#[test]
+fn test_crypto_data_128_channel_cipher() {
+    check_channel_cipher(&CryptoFixture::load(CRYPTO_DATA_128));
+}
+
+#[test]
+fn test_crypto_data_256_channel_cipher() {
+    check_channel_cipher(&CryptoFixture::load(CRYPTO_DATA_256));
+}
+
+#[test]
+fn test_crypto_data_128_default_params() {
+    check_default_params(&CryptoFixture::load(CRYPTO_DATA_128));
+}
+
+#[test]
+fn test_crypto_data_256_default_params() {
+    check_default_params(&CryptoFixture::load(CRYPTO_DATA_256));
+}
+
+#[test]
+fn test_crypto_data_128_message_encoding() {
+    check_message_encoding(&CryptoFixture::load(CRYPTO_DATA_128));
+}
+
+#[test]
+fn test_crypto_data_256_message_encoding() {
+    check_message_encoding(&CryptoFixture::load(CRYPTO_DATA_256));
+}
+
+impl EncodingItem {
+    /// Check a decoded value against the expected type and value
+    fn check_decoded(&self, decoded: &MessageData) {
+        match self.expected_type.as_str() {
+            "string" => assert_eq!(decoded.as_str(), self.expected_value.as_ref().and_then(Value::as_str)),
+            "binary" => {
+                let hex: String = decoded.as_bytes().unwrap().iter().map(|b| format!("{:02x}", b)).collect();
+                assert_eq!(Some(hex), self.expected_hex_value);
+            }
+            "jsonObject" | "jsonArray" => assert_eq!(decoded.as_json(), self.expected_value.as_ref()),
+            other => panic!("Unknown expected type {}", other),
+        }
+    }
+}
+
+#[test]
+fn test_messages_encoding_fixtures() {
+    let fixture: EncodingFixture = serde_json::from_str(MESSAGES_ENCODING).expect("Invalid encoding fixture");
+
+    for item in fixture.messages {
+        let encoding = Some(item.encoding.clone()).filter(|e| !e.is_empty());
+
+        // Inbound: wire form decodes to the expected type and value
+        let (decoded, remaining) =
+            data_encoding::decode_data(MessageData::String(item.data.clone()), encoding.as_deref(), None).unwrap();
+        assert_eq!(remaining, None);
+        item.check_decoded(&decoded);
+
+        // Outbound: decoded value encodes back to the same wire form
+        let (encoded, encoded_encoding) =
+            data_encoding::encode_data(decoded.clone(), None, None, EncodingFormat::Json).unwrap();
+        assert_eq!(encoded, MessageData::String(item.data.clone()));
+        assert_eq!(encoded_encoding, encoding);
+
+        // Msgpack inbound: the packed message decodes to the same value
+        let packed_bytes = BASE64.decode(&item.msgpack).unwrap();
+        let mut packed_message: Message = rmp_serde::from_slice(&packed_bytes).expect("Invalid msgpack fixture");
+        let packed_wire_encoding = packed_message.encoding.take().filter(|e| !e.is_empty());
+        let (unpacked, remaining) =
+            data_encoding::decode_data(packed_message.data.clone().unwrap(), packed_wire_encoding.as_deref(), None).unwrap();
+        assert_eq!(remaining, None);
+        item.check_decoded(&unpacked);
+
+        // Msgpack outbound: binary transports carry bytes natively, with no base64 step
+        let (packed, packed_encoding) =
+            data_encoding::encode_data(decoded.clone(), None, None, EncodingFormat::MessagePack).unwrap();
+        assert_eq!(Some(packed.clone()), packed_message.data);
+        assert_eq!(packed_encoding, packed_wire_encoding);
+
+        let repacked = rmp_serde::to_vec_named(&Message {
+            data: Some(packed),
+            encoding: Some(packed_encoding.unwrap_or_default()),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(repacked, packed_bytes);
+    }
+}
@@ -17,7 +17,7 @@ async fn debug_history_response() {
     // Publish a message
     let msg = Message {
         name: Some("test-event".to_string()),
-        data: Some(json!("test data")),
+        data: Some(json!("test data").into()),
         ..Default::default()
     };
     
@@ -0,0 +1,82 @@
+//! Tests for `#[derive(AblyEvent)]` channel event enums
+
+use ably_core::client::AblyEvent;
+use ably_core::error::AblyError;
+use ably_core::protocol::messages::{Message, MessageData};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Address {
+    city: String,
+}
+
+#[derive(Debug, Clone, PartialEq, AblyEvent)]
+#[ably(rename_all = "snake_case")]
+enum OrderEvent {
+    OrderPlaced { id: String, total: u32 },
+    Shipped(Address),
+    #[ably(name = "order.cancelled", encoding = "string")]
+    Cancelled(u64),
+    #[ably(encoding = "binary")]
+    Receipt(Vec<u8>),
+    Heartbeat,
+}
+
+#[test]
+fn test_variant_names() {
+    assert_eq!(
+        OrderEvent::NAMES,
+        &["order_placed", "shipped", "order.cancelled", "receipt", "heartbeat"]
+    );
+    assert_eq!(OrderEvent::Heartbeat.event_name(), "heartbeat");
+}
+
+#[test]
+fn test_round_trip_each_encoding() {
+    let events = vec![
+        OrderEvent::OrderPlaced { id: "o-1".to_string(), total: 42 },
+        OrderEvent::Shipped(Address { city: "Leeds".to_string() }),
+        OrderEvent::Cancelled(7),
+        OrderEvent::Receipt(vec![1, 2, 3]),
+        OrderEvent::Heartbeat,
+    ];
+
+    for event in events {
+        let message = event.to_message().unwrap();
+        assert_eq!(message.name.as_deref(), Some(event.event_name()));
+        assert_eq!(OrderEvent::from_message(&message).unwrap(), Some(event));
+    }
+}
+
+#[test]
+fn test_payload_encodings() {
+    let placed = OrderEvent::OrderPlaced { id: "o-1".to_string(), total: 42 }.to_message().unwrap();
+    assert_eq!(placed.data, Some(MessageData::Json(json!({"id": "o-1", "total": 42}))));
+
+    let cancelled = OrderEvent::Cancelled(7).to_message().unwrap();
+    assert_eq!(cancelled.data, Some(MessageData::String("7".to_string())));
+
+    let receipt = OrderEvent::Receipt(vec![9]).to_message().unwrap();
+    assert_eq!(receipt.data, Some(MessageData::Binary(vec![9])));
+
+    assert_eq!(OrderEvent::Heartbeat.to_message().unwrap().data, None);
+}
+
+#[test]
+fn test_unknown_and_malformed_messages() {
+    let other = Message {
+        name: Some("refunded".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(OrderEvent::from_message(&other).unwrap(), None);
+
+    let malformed = Message {
+        name: Some("order_placed".to_string()),
+        data: Some(json!({"id": "o-1", "total": "many"}).into()),
+        ..Default::default()
+    };
+    let err = OrderEvent::from_message(&malformed).unwrap_err();
+    assert!(matches!(err, AblyError::Decode { .. }));
+    assert!(err.to_string().contains("total"));
+}
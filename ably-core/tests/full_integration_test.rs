@@ -47,7 +47,7 @@ async fn test_full_websocket_lifecycle() {
     println!("6️⃣ Publishing message...");
     let mut message = Message::default();
     message.name = Some("test".to_string());
-    message.data = Some(serde_json::json!("Hello from Rust"));
+    message.data = Some(serde_json::json!("Hello from Rust").into());
     
     channel.publish(message).await.expect("Failed to publish message");
     println!("   ✅ Message published");
@@ -185,7 +185,7 @@ async fn test_concurrent_channels() {
     
     let mut msg = Message::default();
     msg.name = Some("test".to_string());
-    msg.data = Some(serde_json::json!("concurrent test"));
+    msg.data = Some(serde_json::json!("concurrent test").into());
     
     let (p1, p2, p3) = tokio::join!(
         channel1.publish(msg.clone()),
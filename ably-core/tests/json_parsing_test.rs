@@ -93,7 +93,7 @@ async fn test_actual_api_response_formats() {
     let channels_result = client.channels().list().limit(5).execute().await;
     match channels_result {
         Ok(channels) => {
-            println!("Channel metadata parsed successfully, {} channels", channels.len());
+            println!("Channel metadata parsed successfully, {} channels", channels.items.len());
         }
         Err(e) => {
             println!("Channel metadata parsing failed: {}", e);
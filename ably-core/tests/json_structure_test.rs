@@ -1,5 +1,5 @@
 use serde_json::json;
-use ably_core::protocol::messages::Message;
+use ably_core::protocol::messages::{Message, MessageAction};
 
 #[test]
 fn test_message_deserialization_with_actual_api_format() {
@@ -24,7 +24,7 @@ fn test_message_deserialization_with_actual_api_format() {
     let messages = result.unwrap();
     assert_eq!(messages.len(), 1);
     assert_eq!(messages[0].name.as_deref(), Some("test"));
-    assert_eq!(messages[0].action, Some(0));
+    assert_eq!(messages[0].action, Some(MessageAction::Create));
 }
 
 #[test]
@@ -40,6 +40,7 @@ fn test_message_with_non_optional_action() {
     
     let result: Result<Message, _> = serde_json::from_value(json);
     assert!(result.is_ok(), "Should handle non-optional action field");
+    assert_eq!(result.unwrap().action, Some(MessageAction::Unknown(15)));
 }
 
 #[tokio::test]
@@ -58,7 +59,7 @@ async fn test_actual_api_history_format() {
     // Publish a message
     let msg = Message {
         name: Some("test-event".to_string()),
-        data: Some(json!("test data")),
+        data: Some(json!("test data").into()),
         ..Default::default()
     };
     
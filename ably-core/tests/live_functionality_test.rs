@@ -32,7 +32,7 @@ async fn test_live_ably_connection() {
     
     let message = Message {
         name: Some("test-event".to_string()),
-        data: Some(json!("Hello from Rust SDK!")),
+        data: Some(json!("Hello from Rust SDK!").into()),
         ..Default::default()
     };
     
@@ -69,10 +69,10 @@ async fn test_live_ably_connection() {
         .execute()
         .await
         .expect("Failed to list channels");
-    println!("   ✅ Found {} active channels", channels_list.len());
+    println!("   ✅ Found {} active channels", channels_list.items.len());
     
     // Find our test channel
-    let our_channel = channels_list.iter().find(|c| c.channel_id == channel_name);
+    let our_channel = channels_list.items.iter().find(|c| c.channel_id == channel_name);
     assert!(our_channel.is_some(), "Our test channel should be in the list");
     
     println!("\n✨ All tests passed!");
@@ -96,7 +96,7 @@ async fn test_batch_publish() {
     for i in 1..=5 {
         messages.push(Message {
             name: Some("msg".to_string()),
-            data: Some(json!(format!("Message #{}", i))),
+            data: Some(json!(format!("Message #{}", i)).into()),
             ..Default::default()
         });
     }
@@ -16,7 +16,7 @@ fn test_messagepack_round_trip_protocol_message() {
         channel: Some("test-channel".to_string()),
         messages: Some(vec![Message {
             name: Some("event".to_string()),
-            data: Some(serde_json::json!("test data")),
+            data: Some(serde_json::json!("test data").into()),
             ..Default::default()
         }]),
         ..Default::default()
@@ -58,7 +58,7 @@ fn test_messagepack_binary_data() {
     let binary_data = vec![0u8, 1, 2, 3, 255];
     let msg = Message {
         name: Some("binary-msg".to_string()),
-        data: Some(serde_json::json!(BASE64.encode(&binary_data))),
+        data: Some(serde_json::json!(BASE64.encode(&binary_data)).into()),
         encoding: Some("base64".to_string()),
         ..Default::default()
     };
@@ -1,9 +1,9 @@
 //! RED Phase: Protocol Message Structure Tests
-//! Tests for all 22 Ably protocol action types
+//! Tests for all 23 Ably protocol action types
 
 use ably_core::protocol::{
-    ProtocolMessage, Action, ErrorInfo, MessageFlags,
-    ConnectionDetails, ChannelDetails, MessageData
+    ProtocolMessage, Action, ErrorInfo, MessageFlags, flags,
+    ConnectionDetails, ChannelDetails, Message
 };
 use serde_json;
 
@@ -14,10 +14,10 @@ fn test_protocol_message_serialization() {
         channel: Some("test-channel".to_string()),
         id: Some("msg-123".to_string()),
         timestamp: Some(1234567890),
-        messages: Some(vec![MessageData {
+        messages: Some(vec![Message {
             id: Some("data-1".to_string()),
             name: Some("event.name".to_string()),
-            data: Some(serde_json::json!("test data")),
+            data: Some(serde_json::json!("test data").into()),
             encoding: None,
             extras: None,
             timestamp: Some(1234567890),
@@ -29,8 +29,10 @@ fn test_protocol_message_serialization() {
             version: None,
             action: None,
             operation: None,
+            annotations: None,
         }]),
         presence: None,
+        annotations: None,
         flags: None,
         connection_id: None,
         connection_key: None,
@@ -41,6 +43,7 @@ fn test_protocol_message_serialization() {
         auth: None,
         connection_details: None,
         channel_serial: None,
+        params: None,
     };
 
     let json = serde_json::to_string(&msg).expect("Serialization failed");
@@ -50,7 +53,7 @@ fn test_protocol_message_serialization() {
 
 #[test]
 fn test_all_action_types() {
-    // Test all 22 action types are defined
+    // Test all 23 action types are defined
     let actions = vec![
         Action::Heartbeat,
         Action::Ack,
@@ -74,14 +77,16 @@ fn test_all_action_types() {
         Action::MessageAck,
         Action::PresenceAck,
         Action::PushAdmin,
+        Action::Annotation,
     ];
 
-    assert_eq!(actions.len(), 22);
+    assert_eq!(actions.len(), 23);
     
     // Verify each action has correct numeric value
     assert_eq!(Action::Heartbeat as u8, 0);
     assert_eq!(Action::Message as u8, 14);
     assert_eq!(Action::PushAdmin as u8, 21);
+    assert_eq!(Action::Annotation as u8, 22);
 }
 
 #[test]
@@ -121,15 +126,16 @@ fn test_error_info() {
 
 #[test]
 fn test_message_flags() {
-    assert_eq!(MessageFlags::PRESENCE as u32, 1);
-    assert_eq!(MessageFlags::PUBLISH as u32, 2);
-    assert_eq!(MessageFlags::SUBSCRIBE as u32, 4);
-    assert_eq!(MessageFlags::PRESENCE_SUBSCRIBE as u32, 8);
-    assert_eq!(MessageFlags::HAS_PRESENCE as u32, 16);
-    assert_eq!(MessageFlags::HAS_BACKLOG as u32, 32);
-    assert_eq!(MessageFlags::RESUMED as u32, 64);
-    assert_eq!(MessageFlags::TRANSIENT as u32, 256);
-    assert_eq!(MessageFlags::ATTACH_RESUME as u32, 512);
+    assert_eq!(MessageFlags::HAS_PRESENCE as u32, 1);
+    assert_eq!(MessageFlags::HAS_BACKLOG as u32, 2);
+    assert_eq!(MessageFlags::RESUMED as u32, 4);
+    assert_eq!(MessageFlags::TRANSIENT as u32, 16);
+    assert_eq!(MessageFlags::ATTACH_RESUME as u32, 32);
+    assert_eq!(MessageFlags::PRESENCE as u32, 1 << 16);
+    assert_eq!(MessageFlags::PUBLISH as u32, 1 << 17);
+    assert_eq!(MessageFlags::SUBSCRIBE as u32, 1 << 18);
+    assert_eq!(MessageFlags::PRESENCE_SUBSCRIBE as u32, 1 << 19);
+    assert_eq!(MessageFlags::RESUMED as u32, flags::RESUMED);
 }
 
 #[test]
@@ -169,12 +175,14 @@ fn test_complex_protocol_message() {
         id: None,
         messages: None,
         presence: None,
+        annotations: None,
         flags: None,
         connection_serial: None,
         msg_serial: None,
         count: None,
         auth: None,
         channel_serial: None,
+        params: None,
     };
 
     let json = serde_json::to_string(&msg).expect("Serialization failed");
@@ -178,7 +178,7 @@ async fn test_list_devices() {
     
     if let Ok(devices) = result {
         // If successful, should return array (possibly empty)
-        assert!(devices.is_empty() || !devices.is_empty());
+        assert!(devices.items.is_empty() || !devices.items.is_empty());
     } else if let Err(e) = result {
         // Expected to fail without proper push setup
         println!("List devices error (expected): {}", e);
@@ -64,7 +64,7 @@ async fn test_channel_subscription() {
         .await
         .unwrap();
     
-    assert!(subscriptions.iter().any(|s| s.channel == "news:sports"));
+    assert!(subscriptions.items.iter().any(|s| s.channel == "news:sports"));
 }
 
 #[tokio::test]
@@ -224,7 +224,7 @@ async fn test_channel_metadata() {
     
     assert!(result.is_ok());
     let channels = result.unwrap();
-    assert!(channels.len() >= 0);
+    assert!(channels.items.len() >= 0);
 }
 
 #[tokio::test]
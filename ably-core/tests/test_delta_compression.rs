@@ -57,7 +57,7 @@ async fn test_delta_compression_plugin() {
     for (i, data) in test_data.iter().enumerate() {
         let message = Message {
             name: Some(i.to_string()),
-            data: Some(data.clone()),
+            data: Some(data.clone().into()),
             ..Default::default()
         };
         
@@ -82,7 +82,7 @@ async fn test_delta_compression_plugin() {
     
     for (i, message) in messages.iter().enumerate() {
         assert_eq!(message.name.as_ref().unwrap(), &i.to_string());
-        assert_eq!(message.data.as_ref().unwrap(), &test_data[i]);
+        assert_eq!(&message.data.as_ref().unwrap().to_json_value(), &test_data[i]);
     }
 
     client.disconnect().await.expect("Failed to disconnect");
@@ -121,7 +121,7 @@ async fn test_delta_decode_failure_recovery() {
     // This should trigger decode failure recovery
     let problematic_message = Message {
         name: Some("delta-test".to_string()),
-        data: Some(json!({"data": "test"})),
+        data: Some(json!({"data": "test"}).into()),
         extras: {
             let mut map = HashMap::new();
             map.insert("delta".to_string(), json!({
@@ -200,7 +200,7 @@ async fn test_websocket_max_frame_size() {
     // Try to send a large message
     let large_data = serde_json::Value::String("x".repeat(70000)); // Larger than max frame
     let message = ably_core::protocol::messages::Message {
-        data: Some(large_data),
+        data: Some(large_data.into()),
         ..Default::default()
     };
     let msg = ProtocolMessage {
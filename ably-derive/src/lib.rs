@@ -0,0 +1,287 @@
+//! Derive macros for the Ably Rust SDK
+//!
+//! `#[derive(AblyEvent)]` maps the variants of a channel event enum to
+//! `Message.name` and their payloads to `Message.data`:
+//!
+//! ```ignore
+//! #[derive(AblyEvent)]
+//! #[ably(rename_all = "snake_case")]
+//! enum OrderEvent {
+//!     // name "order_placed", data {"id": ..., "total": ...}
+//!     OrderPlaced { id: String, total: u32 },
+//!     // name "cancelled", data is the string form of the payload
+//!     #[ably(name = "cancelled", encoding = "string")]
+//!     OrderCancelled(String),
+//!     // name "receipt", data is raw bytes
+//!     #[ably(encoding = "binary")]
+//!     Receipt(Vec<u8>),
+//!     // name "heartbeat", no data
+//!     Heartbeat,
+//! }
+//! ```
+//!
+//! Encodings: `json` (default, payload is `Serialize`/`DeserializeOwned`),
+//! `string` (payload is `Display`/`FromStr`) and `binary` (payload is
+//! `AsRef<[u8]>`/`From<Vec<u8>>`). Struct variants are always encoded as a
+//! JSON object of their fields.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::spanned::Spanned;
+use syn::{parse_macro_input, Attribute, Data, DeriveInput, Fields, LitStr, Variant};
+
+#[proc_macro_derive(AblyEvent, attributes(ably))]
+pub fn derive_ably_event(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Encoding {
+    Json,
+    String,
+    Binary,
+}
+
+#[derive(Clone, Copy)]
+enum RenameRule {
+    None,
+    Lowercase,
+    SnakeCase,
+    KebabCase,
+    CamelCase,
+    ScreamingSnakeCase,
+}
+
+impl RenameRule {
+    fn parse(value: &LitStr) -> syn::Result<Self> {
+        match value.value().as_str() {
+            "lowercase" => Ok(RenameRule::Lowercase),
+            "snake_case" => Ok(RenameRule::SnakeCase),
+            "kebab-case" => Ok(RenameRule::KebabCase),
+            "camelCase" => Ok(RenameRule::CamelCase),
+            "SCREAMING_SNAKE_CASE" => Ok(RenameRule::ScreamingSnakeCase),
+            other => Err(syn::Error::new(value.span(), format!("unknown rename_all rule `{}`", other))),
+        }
+    }
+
+    fn apply(self, ident: &str) -> String {
+        let words = split_words(ident);
+        match self {
+            RenameRule::None => ident.to_string(),
+            RenameRule::Lowercase => ident.to_lowercase(),
+            RenameRule::SnakeCase => words.join("_"),
+            RenameRule::KebabCase => words.join("-"),
+            RenameRule::ScreamingSnakeCase => words.join("_").to_uppercase(),
+            RenameRule::CamelCase => words
+                .iter()
+                .enumerate()
+                .map(|(i, word)| match i {
+                    0 => word.clone(),
+                    _ => capitalize(word),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Split a PascalCase identifier into lowercase words
+fn split_words(ident: &str) -> Vec<String> {
+    let mut words: Vec<String> = Vec::new();
+    for c in ident.chars() {
+        match words.last_mut() {
+            Some(word) if !c.is_uppercase() => word.push(c),
+            _ => words.push(c.to_lowercase().collect()),
+        }
+    }
+    words
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+struct VariantAttrs {
+    name: Option<String>,
+    encoding: Option<Encoding>,
+}
+
+fn enum_rename_rule(attrs: &[Attribute]) -> syn::Result<RenameRule> {
+    let mut rule = RenameRule::None;
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("ably")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename_all") {
+                rule = RenameRule::parse(&meta.value()?.parse()?)?;
+                Ok(())
+            } else {
+                Err(meta.error("expected `rename_all`"))
+            }
+        })?;
+    }
+    Ok(rule)
+}
+
+fn variant_attrs(variant: &Variant) -> syn::Result<VariantAttrs> {
+    let mut attrs = VariantAttrs { name: None, encoding: None };
+    for attr in variant.attrs.iter().filter(|attr| attr.path().is_ident("ably")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                let name: LitStr = meta.value()?.parse()?;
+                attrs.name = Some(name.value());
+                Ok(())
+            } else if meta.path.is_ident("encoding") {
+                let encoding: LitStr = meta.value()?.parse()?;
+                attrs.encoding = Some(match encoding.value().as_str() {
+                    "json" => Encoding::Json,
+                    "string" => Encoding::String,
+                    "binary" => Encoding::Binary,
+                    other => {
+                        return Err(syn::Error::new(
+                            encoding.span(),
+                            format!("unknown encoding `{}`, expected `json`, `string` or `binary`", other),
+                        ))
+                    }
+                });
+                Ok(())
+            } else {
+                Err(meta.error("expected `name` or `encoding`"))
+            }
+        })?;
+    }
+    Ok(attrs)
+}
+
+struct VariantCode {
+    name: String,
+    pattern: TokenStream2,
+    encode: TokenStream2,
+    decode: TokenStream2,
+}
+
+fn expand_variant(variant: &Variant, rule: RenameRule) -> syn::Result<VariantCode> {
+    let attrs = variant_attrs(variant)?;
+    let ident = &variant.ident;
+    let name = attrs.name.unwrap_or_else(|| rule.apply(&ident.to_string()));
+    let support = quote!(::ably_core::client::event::__derive);
+
+    let code = match &variant.fields {
+        Fields::Unit => {
+            if attrs.encoding.is_some() {
+                return Err(syn::Error::new(variant.span(), "unit variants carry no data to encode"));
+            }
+            VariantCode {
+                pattern: quote!(Self::#ident),
+                encode: quote!(::core::option::Option::None),
+                decode: quote!(Self::#ident),
+                name,
+            }
+        }
+        Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+            let encode = match attrs.encoding.unwrap_or(Encoding::Json) {
+                Encoding::Json => quote!(#support::encode_json(#name, payload)?),
+                Encoding::String => quote!(#support::encode_string(payload)),
+                Encoding::Binary => quote!(#support::encode_binary(payload)),
+            };
+            let decode = match attrs.encoding.unwrap_or(Encoding::Json) {
+                Encoding::Json => quote!(#support::decode_json(#name, message)?),
+                Encoding::String => quote!(#support::decode_string(#name, message)?),
+                Encoding::Binary => quote!(#support::decode_binary(#name, message)?),
+            };
+            VariantCode {
+                pattern: quote!(Self::#ident(payload)),
+                encode: quote!(::core::option::Option::Some(#encode)),
+                decode: quote!(Self::#ident(#decode)),
+                name,
+            }
+        }
+        Fields::Named(fields) => {
+            if attrs.encoding.is_some_and(|encoding| encoding != Encoding::Json) {
+                return Err(syn::Error::new(variant.span(), "struct variants can only use the `json` encoding"));
+            }
+            let idents: Vec<_> = fields.named.iter().map(|field| field.ident.clone().unwrap()).collect();
+            let keys: Vec<String> = idents.iter().map(|ident| ident.to_string()).collect();
+            VariantCode {
+                pattern: quote!(Self::#ident { #(#idents),* }),
+                encode: quote!(::core::option::Option::Some(#support::encode_object(::std::vec![
+                    #(#support::encode_field(#name, #keys, #idents)?),*
+                ]))),
+                decode: quote!({
+                    let object: #support::Value = #support::decode_json(#name, message)?;
+                    Self::#ident { #(#idents: #support::decode_field(#name, &object, #keys)?),* }
+                }),
+                name,
+            }
+        }
+        Fields::Unnamed(_) => {
+            return Err(syn::Error::new(
+                variant.span(),
+                "AblyEvent variants must be unit, single-field tuple or struct variants",
+            ))
+        }
+    };
+    Ok(code)
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let Data::Enum(data) = &input.data else {
+        return Err(syn::Error::new(input.span(), "AblyEvent can only be derived for enums"));
+    };
+
+    let rule = enum_rename_rule(&input.attrs)?;
+    let variants = data
+        .variants
+        .iter()
+        .map(|variant| expand_variant(variant, rule))
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    for (i, variant) in variants.iter().enumerate() {
+        if variants[..i].iter().any(|earlier| earlier.name == variant.name) {
+            return Err(syn::Error::new(
+                data.variants[i].span(),
+                format!("duplicate event name `{}`", variant.name),
+            ));
+        }
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let names: Vec<&String> = variants.iter().map(|variant| &variant.name).collect();
+    let patterns: Vec<&TokenStream2> = variants.iter().map(|variant| &variant.pattern).collect();
+    let encodes: Vec<&TokenStream2> = variants.iter().map(|variant| &variant.encode).collect();
+    let decodes: Vec<&TokenStream2> = variants.iter().map(|variant| &variant.decode).collect();
+
+    Ok(quote! {
+        impl #impl_generics ::ably_core::client::event::AblyEvent for #ident #ty_generics #where_clause {
+            const NAMES: &'static [&'static str] = &[#(#names),*];
+
+            fn event_name(&self) -> &'static str {
+                #[allow(unused_variables)]
+                match self {
+                    #(#patterns => #names,)*
+                }
+            }
+
+            fn to_message(&self) -> ::ably_core::error::AblyResult<::ably_core::protocol::messages::Message> {
+                let data = match self {
+                    #(#patterns => #encodes,)*
+                };
+                ::core::result::Result::Ok(::ably_core::client::event::__derive::message(self.event_name(), data))
+            }
+
+            fn from_message(
+                message: &::ably_core::protocol::messages::Message,
+            ) -> ::ably_core::error::AblyResult<::core::option::Option<Self>> {
+                let event = match message.name.as_deref() {
+                    #(::core::option::Option::Some(#names) => #decodes,)*
+                    _ => return ::core::result::Result::Ok(::core::option::Option::None),
+                };
+                ::core::result::Result::Ok(::core::option::Option::Some(event))
+            }
+        }
+    })
+}
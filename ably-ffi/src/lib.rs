@@ -10,6 +10,7 @@ use std::sync::Mutex;
 
 use ably_core::client::rest::RestClient as CoreRestClient;
 use ably_core::client::realtime::RealtimeClient as CoreRealtimeClient;
+use ably_core::crypto::CipherParams;
 use ably_core::protocol::messages::{Message, PresenceMessage};
 use ably_core::error::{AblyError, ErrorCode};
 use serde_json;
@@ -31,9 +32,20 @@ pub struct AblyRealtimeClient {
 pub struct AblyRestChannel {
     client: Arc<CoreRestClient>,
     channel_name: String,
+    cipher: Option<CipherParams>,
     runtime: Arc<Runtime>,
 }
 
+impl AblyRestChannel {
+    fn core_channel(&self) -> ably_core::client::rest::Channel<'_> {
+        let channel = self.client.channel(&self.channel_name);
+        match &self.cipher {
+            Some(params) => channel.with_cipher(params.clone()),
+            None => channel,
+        }
+    }
+}
+
 /// Opaque handle for Realtime channel
 pub struct AblyRealtimeChannel {
     client: Arc<Mutex<CoreRealtimeClient>>,
@@ -150,6 +162,7 @@ pub extern "C" fn ably_rest_client_channel(
                 let handle = Box::new(AblyRestChannel {
                     client: Arc::clone(&client.client),
                     channel_name,
+                    cipher: None,
                     runtime: Arc::clone(&client.runtime),
                 });
                 
@@ -211,7 +224,7 @@ pub extern "C" fn ably_rest_channel_publish(
             ..Default::default()
         };
         
-        let rest_channel = channel.client.channel(&channel.channel_name);
+        let rest_channel = channel.core_channel();
         
         let result = channel.runtime.block_on(async {
             rest_channel.publish(message).await
@@ -230,6 +243,43 @@ pub extern "C" fn ably_rest_channel_publish(
     }
 }
 
+/// Encrypt messages on a REST channel with a base64 key
+#[no_mangle]
+pub extern "C" fn ably_rest_channel_set_cipher_key(
+    channel: *mut AblyRestChannel,
+    key: *const c_char,
+) -> AblyResult {
+    if channel.is_null() {
+        return AblyResult {
+            success: 0,
+            error: create_error(AblyError::from_ably_code(50000, "Null channel")),
+        };
+    }
+    
+    unsafe {
+        let channel = &mut *channel;
+        
+        let params = match from_c_string(key) {
+            Ok(k) => CipherParams::from_key(k),
+            Err(e) => Err(AblyError::from_ably_code(50000, &e)),
+        };
+        
+        match params {
+            Ok(params) => {
+                channel.cipher = Some(params);
+                AblyResult {
+                    success: 1,
+                    error: ptr::null_mut(),
+                }
+            }
+            Err(e) => AblyResult {
+                success: 0,
+                error: create_error(e),
+            },
+        }
+    }
+}
+
 /// Get message history from REST channel
 #[no_mangle]
 pub extern "C" fn ably_rest_channel_history(
@@ -246,7 +296,7 @@ pub extern "C" fn ably_rest_channel_history(
     
     unsafe {
         let channel = &*channel;
-        let rest_channel = channel.client.channel(&channel.channel_name);
+        let rest_channel = channel.core_channel();
         
         let result = channel.runtime.block_on(async {
             let mut query = rest_channel.history();
@@ -482,6 +532,51 @@ pub extern "C" fn ably_realtime_channel_detach(channel: *mut AblyRealtimeChannel
     }
 }
 
+/// Encrypt messages on a Realtime channel with a base64 key
+#[no_mangle]
+pub extern "C" fn ably_realtime_channel_set_cipher_key(
+    channel: *mut AblyRealtimeChannel,
+    key: *const c_char,
+) -> AblyResult {
+    if channel.is_null() {
+        return AblyResult {
+            success: 0,
+            error: create_error(AblyError::from_ably_code(50000, "Null channel")),
+        };
+    }
+    
+    unsafe {
+        let channel = &*channel;
+        
+        let params = match from_c_string(key) {
+            Ok(k) => CipherParams::from_key(k),
+            Err(e) => Err(AblyError::from_ably_code(50000, &e)),
+        };
+        
+        let result = match params {
+            Ok(params) => channel.runtime.block_on(async {
+                let client = channel.client.lock().unwrap();
+                let ch = client.channel(&channel.channel_name).await;
+                let mut options = ch.options().await;
+                options.cipher = Some(params);
+                ch.set_options(options).await
+            }),
+            Err(e) => Err(e),
+        };
+        
+        match result {
+            Ok(_) => AblyResult {
+                success: 1,
+                error: ptr::null_mut(),
+            },
+            Err(e) => AblyResult {
+                success: 0,
+                error: create_error(e),
+            },
+        }
+    }
+}
+
 /// Publish a message to Realtime channel
 #[no_mangle]
 pub extern "C" fn ably_realtime_channel_publish(
@@ -608,6 +703,47 @@ pub extern "C" fn ably_crypto_generate_random_key(
     }
 }
 
+/// Get default cipher params for a base64 key as a JSON string
+#[no_mangle]
+pub extern "C" fn ably_crypto_get_default_params(
+    key: *const c_char,
+    out_json: *mut *mut c_char,
+) -> AblyResult {
+    if out_json.is_null() {
+        return AblyResult {
+            success: 0,
+            error: create_error(AblyError::from_ably_code(50000, "Invalid parameters")),
+        };
+    }
+    
+    unsafe {
+        let params = match from_c_string(key) {
+            Ok(k) => ably_core::crypto::get_default_params(k),
+            Err(e) => Err(AblyError::from_ably_code(50000, &e)),
+        };
+        
+        match params {
+            Ok(params) => {
+                let json = serde_json::json!({
+                    "algorithm": "aes",
+                    "keyLength": params.key_length(),
+                    "mode": params.mode.to_string(),
+                    "key": params.key_as_base64(),
+                });
+                *out_json = to_c_string(&json.to_string());
+                AblyResult {
+                    success: 1,
+                    error: ptr::null_mut(),
+                }
+            }
+            Err(e) => AblyResult {
+                success: 0,
+                error: create_error(e),
+            },
+        }
+    }
+}
+
 // Tests for FFI bindings
 #[cfg(test)]
 mod tests {
@@ -626,4 +762,19 @@ mod tests {
         assert!(!client.is_null());
         ably_rest_client_free(client);
     }
+    
+    #[test]
+    fn test_crypto_get_default_params() {
+        let key = CString::new("WUP6u0K7MXI5Zeo0VppPwg==").unwrap();
+        let mut json: *mut c_char = ptr::null_mut();
+        let result = ably_crypto_get_default_params(key.as_ptr(), &mut json);
+        assert_eq!(result.success, 1);
+        
+        let params: serde_json::Value = unsafe {
+            serde_json::from_str(CStr::from_ptr(json).to_str().unwrap()).unwrap()
+        };
+        assert_eq!(params["keyLength"], 128);
+        assert_eq!(params["mode"], "cbc");
+        ably_string_free(json);
+    }
 }
\ No newline at end of file
@@ -5,6 +5,7 @@ use napi::bindgen_prelude::*;
 use napi_derive::napi;
 use ably_core::client::rest::RestClient as CoreRestClient;
 use ably_core::client::realtime::RealtimeClient as CoreRealtimeClient;
+use ably_core::crypto::CipherParams;
 use ably_core::protocol::messages::{Message, PresenceMessage};
 use ably_core::error::{AblyError, ErrorCode};
 use serde::{Serialize, Deserialize};
@@ -59,6 +60,7 @@ impl RestClient {
         RestChannel {
             client: Arc::clone(&self.inner),
             channel_name: name,
+            cipher: None,
         }
     }
     
@@ -85,10 +87,28 @@ impl RestClient {
 pub struct RestChannel {
     client: Arc<CoreRestClient>,
     channel_name: String,
+    cipher: Option<CipherParams>,
+}
+
+impl RestChannel {
+    fn core_channel(&self) -> ably_core::client::rest::Channel<'_> {
+        let channel = self.client.channel(&self.channel_name);
+        match &self.cipher {
+            Some(params) => channel.with_cipher(params.clone()),
+            None => channel,
+        }
+    }
 }
 
 #[napi]
 impl RestChannel {
+    /// Encrypt messages on this channel with a base64 key
+    #[napi]
+    pub fn set_cipher_key(&mut self, key: String) -> Result<()> {
+        self.cipher = Some(CipherParams::from_key(key).map_err(ably_error_to_napi)?);
+        Ok(())
+    }
+    
     /// Publish a message
     #[napi]
     pub async fn publish(&self, name: Option<String>, data: String) -> Result<()> {
@@ -98,7 +118,7 @@ impl RestChannel {
             ..Default::default()
         };
         
-        let channel = self.client.channel(&self.channel_name);
+        let channel = self.core_channel();
         channel.publish(message).await
             .map_err(|e| ably_error_to_napi(e))
     }
@@ -106,7 +126,7 @@ impl RestChannel {
     /// Get message history as JSON string
     #[napi]
     pub async fn history(&self, limit: Option<u32>) -> Result<String> {
-        let channel = self.client.channel(&self.channel_name);
+        let channel = self.core_channel();
         let mut query = channel.history();
         
         if let Some(limit) = limit {
@@ -229,6 +249,19 @@ impl RealtimeChannel {
             .map_err(|e| ably_error_to_napi(e))
     }
     
+    /// Encrypt messages on this channel with a base64 key
+    #[napi]
+    pub async fn set_cipher_key(&self, key: String) -> Result<()> {
+        let params = CipherParams::from_key(key).map_err(ably_error_to_napi)?;
+        let client = self.client.lock().await;
+        let channel = client.channel(&self.channel_name).await;
+        
+        let mut options = channel.options().await;
+        options.cipher = Some(params);
+        channel.set_options(options).await
+            .map_err(|e| ably_error_to_napi(e))
+    }
+    
     /// Publish a message
     #[napi]
     pub async fn publish(&self, name: Option<String>, data: String) -> Result<()> {
@@ -304,15 +337,15 @@ impl Crypto {
     /// Create cipher params from key
     #[napi]
     pub fn get_default_params(key: String) -> Result<String> {
-        use ably_core::crypto::CipherParams;
-        
-        let _params = CipherParams::from_key(&key)
+        let params = ably_core::crypto::get_default_params(key)
             .map_err(|e| ably_error_to_napi(e))?;
         
-        // Convert params to JSON
-        // Note: We'd need to expose CipherParams fields or create a method to get them
-        // For now, return a placeholder
-        Ok("{}".to_string())
+        Ok(serde_json::json!({
+            "algorithm": "aes",
+            "keyLength": params.key_length(),
+            "mode": params.mode.to_string(),
+            "key": params.key_as_base64(),
+        }).to_string())
     }
 }
 
@@ -5,6 +5,7 @@ use wasm_bindgen::prelude::*;
 use wasm_bindgen_futures::spawn_local;
 use ably_core::client::rest::RestClient as CoreRestClient;
 use ably_core::client::realtime::RealtimeClient as CoreRealtimeClient;
+use ably_core::crypto::CipherParams;
 use ably_core::protocol::messages::{Message, PresenceMessage};
 use ably_core::error::{AblyError, ErrorCode};
 use serde::{Serialize, Deserialize};
@@ -99,6 +100,7 @@ impl RestClient {
         RestChannel {
             client: Arc::clone(&self.inner),
             channel_name: name.to_string(),
+            cipher: None,
         }
     }
     
@@ -122,10 +124,28 @@ impl RestClient {
 pub struct RestChannel {
     client: Arc<CoreRestClient>,
     channel_name: String,
+    cipher: Option<CipherParams>,
+}
+
+impl RestChannel {
+    fn core_channel(&self) -> ably_core::client::rest::Channel<'_> {
+        let channel = self.client.channel(&self.channel_name);
+        match &self.cipher {
+            Some(params) => channel.with_cipher(params.clone()),
+            None => channel,
+        }
+    }
 }
 
 #[wasm_bindgen]
 impl RestChannel {
+    /// Encrypt messages on this channel with a base64 key
+    #[wasm_bindgen]
+    pub fn set_cipher_key(&mut self, key: String) -> Result<(), WasmAblyError> {
+        self.cipher = Some(CipherParams::from_key(key).map_err(WasmAblyError::from)?);
+        Ok(())
+    }
+    
     /// Publish a message
     #[wasm_bindgen]
     pub async fn publish(&self, name: Option<String>, data: JsValue) -> Result<(), WasmAblyError> {
@@ -135,7 +155,7 @@ impl RestChannel {
             ..Default::default()
         };
         
-        let channel = self.client.channel(&self.channel_name);
+        let channel = self.core_channel();
         channel.publish(message).await
             .map_err(WasmAblyError::from)
     }
@@ -143,7 +163,7 @@ impl RestChannel {
     /// Get message history
     #[wasm_bindgen]
     pub async fn history(&self, limit: Option<u32>) -> Result<JsValue, WasmAblyError> {
-        let channel = self.client.channel(&self.channel_name);
+        let channel = self.core_channel();
         let mut query = channel.history();
         
         if let Some(limit) = limit {
@@ -274,6 +294,19 @@ impl RealtimeChannel {
             .map_err(WasmAblyError::from)
     }
     
+    /// Encrypt messages on this channel with a base64 key
+    #[wasm_bindgen]
+    pub async fn set_cipher_key(&self, key: String) -> Result<(), WasmAblyError> {
+        let params = CipherParams::from_key(key).map_err(WasmAblyError::from)?;
+        let client = self.client.lock().unwrap();
+        let channel = client.channel(&self.channel_name).await;
+        
+        let mut options = channel.options().await;
+        options.cipher = Some(params);
+        channel.set_options(options).await
+            .map_err(WasmAblyError::from)
+    }
+    
     /// Publish a message
     #[wasm_bindgen]
     pub async fn publish(&self, name: Option<String>, data: JsValue) -> Result<(), WasmAblyError> {
@@ -348,16 +381,21 @@ impl Crypto {
     /// Create cipher params from key
     #[wasm_bindgen]
     pub fn get_default_params(key: String) -> Result<JsValue, WasmAblyError> {
-        use ably_core::crypto::CipherParams;
-        
-        let _params = CipherParams::from_key(&key)
+        let params = ably_core::crypto::get_default_params(key)
             .map_err(WasmAblyError::from)?;
         
         // Convert params to a simple JavaScript object
         let js_obj = js_sys::Object::new();
+        let fields = [
+            ("algorithm", JsValue::from_str("aes")),
+            ("keyLength", JsValue::from_f64(params.key_length() as f64)),
+            ("mode", JsValue::from_str(&params.mode.to_string())),
+            ("key", JsValue::from_str(&params.key_as_base64())),
+        ];
+        for (name, value) in fields {
+            let _ = js_sys::Reflect::set(&js_obj, &JsValue::from_str(name), &value);
+        }
         
-        // Note: We'd need to expose CipherParams fields or create a method to get them
-        // For now, return a placeholder
         Ok(JsValue::from(js_obj))
     }
 }